@@ -1,6 +1,30 @@
+import { mkdtempSync, readFileSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+
 import test from 'ava'
 
-import { sum, decrypt } from '../index.js'
+import {
+  sum,
+  decrypt,
+  decryptAsync,
+  decryptMany,
+  getMachineFingerprint,
+  LicenseErrorCode,
+  LicenseStore,
+  LicenseVerifier,
+} from '../index.js'
+
+const HELLO_WORLD_ENCRYPTED = '0m1sZE8TXVU2LS9bpjFu4orBPEmA4GGfxW37oQQpKOhlsl5LtfMlU1pSdu5Q/KLSwL05db8Ku5yOo/YgwmfxNHOFA1DfD9AI3/Ygvvej8KX3yaIyup6xN9znwltb7LylU0OYL93AbzLGpmqY7G01b9J5mx2GoQnFVhDRaizmIS+xYFjE1yzBrqfUSVv3wtznNpsgvB7RyJC5I95zbiSt/XcqYAdvugw4JH5LgqT8Gydpt32zFrDaFMSKe2ss+nPbDS2nEUrWXL4Bvi3VOs9RkqoLzUCa52mHVimMj9oG7C/KFhC6Vk7sXpR8FRv5FphNk+mCBMGRrGd10RvduKRhiQ=='
+// 内置密钥加密的 license：enterprise 版，开通 api_access 和 sso，2100 年过期
+const VALID_LICENSE = 'QktMQwECEGYzOGFiMWRhMWU2ZDYxOGYBAOzQ59FBJ5XjW2dezo4ciUWpsn6mcH3nUqAYmO/tB5wvq5XzVyi5HrS40VT4x2jmSvaALG7MIKdnP2a6sj6xofw6xY2IIzvcN1QcChyygpnU+HuKPxXx6GrDFMISUv4Jy1p2hbU+MrsJ7cr29LWdt9ciqxYpryky0gO4wHOlDb0aBT/R9KTIcVrQWsrSZ3idTrOH4SBED09R6Ga+VARn29pSPpZz7lBp4e4ueHHi/1dgfxF70WZ/1+7ho8vCaB2v5mRQG/jc/WUzcg1u/ogJZ3R4yk5rmoJ2BVPNXFV9k+udeC5aUm6EKAfOROzjRgdfTOKgMiTpsLDOabP493tMs5uX7fQ1tgjUf2o3L4CVCfL6ULcL1zraLWiULEZ85cglGZ36tvQAd6WxS/DwXtEiu9l21S6eC3X6kJIqx1cRl8AzcUJcjSNlSw8NmJBbwlw8u6uTofOev0su17Hh5KHCdr2+nKSsq0/PCnnOeNXN02pA0x33HvvPxelcxedFL38KXwaMeTn9N+/bT/7fxKForqmJFx5X4wHUaeOTE5uOAxvhICqzjrmESWpdN/I5IvTMcvdK0ub8Q37BtiH+yb7uRr6GV0C99w=='
+// 2023 年已过期的 license
+const EXPIRED_LICENSE = 'QktMQwECEGYzOGFiMWRhMWU2ZDYxOGYBAH9sLGHO3HQ0DA4kSsTLOpHcdG1kmF6HR71eJanJ6S3ONUCTyGnR6GmFbnUEt3FBN832WMvNaPfyvLhmWYaDIN9ASVegrHNpo52zuBEIckN0bDq3fhb9t1n5snqIZDtVu+eBF2HvfrIW+tSmAdg4fW+JAt/ZAzOjLcIuel9wLtGkPjPKIfS2IWbTH6Ybho4hDXs7karGwFMDoT1CIJXbLTleMqxP3pOxNrRNjjZOIr2/cCvvUZzTaswCusNGNh0HWQEY5aBfaIx6d2Us418wMdiobzOkdtXT/k3u0qC85vCMoz+ZCAubpjQ5eZ8ryciRZ/DJahDcaYvAr4lJSyPcAlSW6TI1XUrTiBEEbfdWs+BXI5BQLjNLBVhjPdSEf7rFUId/IH9TwmjGZ4Vjb+Y+/SoN1wSnS4qox7mGSKrCojm9yw8viERTYPQ/etnsODyepdrxLi41tApO3E4bEdsX6mnPPEUH2A=='
+
+// 内置签名密钥签名的吊销列表：吊销 license acme-2025
+const REVOCATION_LIST = 'QktMQwEDEDIzMDU3NDc4ODM3ZDI0ZGUAAACeeyJ0eXBlIjoicmV2b2NhdGlvbl9saXN0IiwiaXNzdWVkX2F0IjoxNzU0NjExMjAwLCJuZXh0X3VwZGF0ZSI6MTc1NDY5NzYwMCwibGljZW5zZXMiOlt7ImxpY2Vuc2VfaWQiOiJhY21lLTIwMjUiLCJyZXZva2VkX2F0IjoxNzU0NjA3NjAwLCJyZWFzb24iOiJyZWZ1bmRlZCJ9XX3Fn72yJtLisdvFgh6f1/UorMUrVjr8GnJ0ccsJIqF5rR0/9r+1qctOyZVm9w/GqEEQEaSYT4W81ugPNHmUZdMD'
+// 较新的列表，另外吊销了 VALID_LICENSE 的加密密钥 f38ab1da1e6d618f
+const REVOCATION_LIST_WITH_KEY = 'QktMQwEDEDIzMDU3NDc4ODM3ZDI0ZGUAAADdeyJ0eXBlIjoicmV2b2NhdGlvbl9saXN0IiwiaXNzdWVkX2F0IjoxNzU0Njk3NjAwLCJuZXh0X3VwZGF0ZSI6MTc1NDc4NDAwMCwibGljZW5zZXMiOlt7ImxpY2Vuc2VfaWQiOiJhY21lLTIwMjUiLCJyZXZva2VkX2F0IjoxNzU0NjA3NjAwLCJyZWFzb24iOiJyZWZ1bmRlZCJ9XSwia2V5cyI6W3sia2V5X2lkIjoiZjM4YWIxZGExZTZkNjE4ZiIsInJldm9rZWRfYXQiOjE3NTQ2OTc2MDB9XX37HLE7IYGYoZ2+p9SwUV2naVm9cCptPSEwhmFYSxc3BupwwZsGbbJMxFpo0qgi7+DXzHenmsqRL195mjwGzecE'
 
 // 基本功能测试
 test('sum function', (t) => {
@@ -271,3 +295,197 @@ test('input validation stress test', (t) => {
     }
   });
 })
+
+// 异步解密测试
+test('decryptAsync resolves without blocking', async (t) => {
+  const decrypted = await decryptAsync(HELLO_WORLD_ENCRYPTED)
+  t.is(decrypted, 'Hello, World!')
+
+  const results = await Promise.all(Array.from({ length: 20 }, () => decryptAsync(HELLO_WORLD_ENCRYPTED)))
+  t.true(results.every((result) => result === 'Hello, World!'))
+})
+
+test('decryptAsync rejects with error code', async (t) => {
+  const error = await t.throwsAsync(decryptAsync(''), { message: /Input cannot be empty/ })
+  t.is((error as any).code, 'LICENSE_EMPTY_INPUT')
+
+  await t.throwsAsync(decryptAsync('invalid_base64!'), { message: /Invalid base64 encoding/ })
+})
+
+// 批量解密测试
+test('decryptMany keeps order and per-item errors', async (t) => {
+  const results = await decryptMany([HELLO_WORLD_ENCRYPTED, '', 'invalid_base64!', HELLO_WORLD_ENCRYPTED])
+  t.is(results.length, 4)
+
+  t.true(results[0].ok)
+  t.is(results[0].value, 'Hello, World!')
+
+  t.false(results[1].ok)
+  t.is(results[1].code, LicenseErrorCode.EmptyInput)
+  t.regex(results[1].message!, /Input cannot be empty/)
+
+  t.is(results[2].code, 'LICENSE_BAD_BASE64')
+  t.is(results[3].value, 'Hello, World!')
+
+  t.deepEqual(await decryptMany([]), [])
+})
+
+// LicenseVerifier 测试
+test('LicenseVerifier verify returns typed payload', (t) => {
+  const verifier = new LicenseVerifier()
+  const { payload, inGracePeriod } = verifier.verify(VALID_LICENSE)
+
+  t.false(inGracePeriod)
+  t.is(payload.licensee, 'Bika Test Co.')
+  t.is(payload.issuedAt, 1754611200)
+  t.is(payload.expiresAt, 4102444800)
+  t.is(payload.maxSeats, 10)
+  t.is(payload.edition, 'enterprise')
+  t.deepEqual(payload.features, ['api_access', 'sso'])
+  t.deepEqual(payload.claims, { plan: 'pro' })
+
+  t.true(verifier.hasFeature(VALID_LICENSE, 'sso'))
+  t.false(verifier.hasFeature(VALID_LICENSE, 'data_export'))
+})
+
+test('LicenseVerifier checks expiry and required features', (t) => {
+  const verifier = new LicenseVerifier()
+  const error = t.throws(() => verifier.verify(EXPIRED_LICENSE), { message: /expired/i })
+  t.is((error as any).code, 'LICENSE_EXPIRED')
+
+  // decode 不检查有效期
+  t.is(verifier.decode(EXPIRED_LICENSE).licensee, 'Expired Co.')
+
+  // 宽限期足够长时仍可使用
+  const lenient = new LicenseVerifier(null, { gracePeriod: 100 * 365 * 24 * 3600 })
+  t.true(lenient.verify(EXPIRED_LICENSE).inGracePeriod)
+
+  const strict = new LicenseVerifier(null, { requiredFeatures: ['api_access', 'data_export'] })
+  const missing = t.throws(() => strict.verify(VALID_LICENSE), { message: /data_export/ })
+  t.is((missing as any).code, 'LICENSE_MISSING_FEATURE')
+})
+
+test('LicenseVerifier rejects invalid keys and tokens', (t) => {
+  const invalidKey = t.throws(() => new LicenseVerifier('not a key'))
+  t.is((invalidKey as any).code, 'LICENSE_KEY_LOAD_FAILED')
+
+  const verifier = new LicenseVerifier()
+  const error = t.throws(() => verifier.verify(''))
+  t.is((error as any).code, 'LICENSE_EMPTY_INPUT')
+})
+
+// 机器指纹测试
+test('getMachineFingerprint is stable and hashed', (t) => {
+  const fingerprint = getMachineFingerprint()
+  t.deepEqual(getMachineFingerprint(), fingerprint)
+  t.is(fingerprint.id.length, 32)
+  for (const hash of Object.values(fingerprint.components)) {
+    t.regex(hash, /^[0-9a-f]{32}$/)
+  }
+})
+
+test('LicenseVerifier accepts an explicit machine fingerprint', (t) => {
+  // 未绑定机器的 license 不受指纹影响
+  const verifier = new LicenseVerifier(null, {
+    machineFingerprint: { hostname: '00000000000000000000000000000000' },
+    fingerprintTolerance: 1,
+  })
+  t.deepEqual(verifier.verify(VALID_LICENSE).payload.fingerprints, [])
+  t.is(LicenseErrorCode.FingerprintMismatch, 'LICENSE_FINGERPRINT_MISMATCH')
+})
+
+// 本地存储测试
+test('LicenseStore saves, reloads and detects tampering', (t) => {
+  const dir = mkdtempSync(join(tmpdir(), 'license-store-'))
+  const store = new LicenseStore(dir, { machineFingerprint: { machine_id: 'abc' } })
+  t.is(store.load(), null)
+
+  const saved = store.save(VALID_LICENSE, 'activation-response')
+  t.is(saved.license, VALID_LICENSE)
+  const loaded = store.load()!
+  t.is(loaded.activation, 'activation-response')
+  t.true(loaded.lastSeen >= saved.savedAt)
+  t.is(new LicenseVerifier().verify(loaded.license).payload.licensee, 'Bika Test Co.')
+
+  // 其他机器的指纹推导出不同的密钥
+  const other = new LicenseStore(dir, { machineFingerprint: { machine_id: 'xyz' } })
+  const tampered = t.throws(() => other.load()) as any
+  t.is(tampered.code, LicenseErrorCode.StoreTampered)
+
+  writeFileSync(store.path, readFileSync(store.path, 'utf8').replace('activation-response', 'forged'))
+  t.is((t.throws(() => store.load()) as any).code, 'LICENSE_STORE_TAMPERED')
+
+  store.clear()
+  t.is(store.load(), null)
+
+  const negative = t.throws(() => new LicenseStore(dir, { clockSkew: -1 })) as any
+  t.is(negative.code, LicenseErrorCode.InvalidOptions)
+})
+
+// 吊销列表测试
+test('LicenseVerifier rejects licenses revoked by the revocation list', (t) => {
+  const verifier = new LicenseVerifier(null, { revocationList: REVOCATION_LIST })
+  // VALID_LICENSE 没有 licenseId，只能通过吊销密钥失效
+  t.is(verifier.verify(VALID_LICENSE).payload.licenseId, undefined)
+
+  const info = verifier.setRevocationList(REVOCATION_LIST_WITH_KEY)
+  t.is(info.issuedAt, 1754697600)
+  t.deepEqual(info.licenseIds, ['acme-2025'])
+  t.deepEqual(info.keyIds, ['f38ab1da1e6d618f'])
+  const revoked = t.throws(() => verifier.verify(VALID_LICENSE)) as any
+  t.is(revoked.code, LicenseErrorCode.Revoked)
+  t.is(revoked.details.revokedAt, 1754697600)
+
+  // 旧列表不会替换新列表
+  t.is(verifier.setRevocationList(REVOCATION_LIST).issuedAt, 1754697600)
+  t.is((t.throws(() => verifier.setRevocationList(VALID_LICENSE)) as any).code, 'LICENSE_BAD_FORMAT')
+})
+
+test('LicenseStore caches the newest revocation list', (t) => {
+  const dir = mkdtempSync(join(tmpdir(), 'license-crl-'))
+  const store = new LicenseStore(dir, { machineFingerprint: { machine_id: 'abc' } })
+  t.is((t.throws(() => store.saveRevocationList(REVOCATION_LIST)) as any).code, 'LICENSE_STORAGE_FAILED')
+
+  store.save(VALID_LICENSE)
+  t.is(store.saveRevocationList(REVOCATION_LIST_WITH_KEY).issuedAt, 1754697600)
+  t.is(store.saveRevocationList(REVOCATION_LIST).issuedAt, 1754697600)
+
+  const loaded = store.load()!
+  t.is(loaded.revocationList, REVOCATION_LIST_WITH_KEY)
+  const verifier = new LicenseVerifier(null, { revocationList: loaded.revocationList })
+  t.is((t.throws(() => verifier.verify(loaded.license)) as any).code, 'LICENSE_REVOKED')
+  store.clear()
+})
+
+// 错误对象带稳定的 code 和结构化 details
+test('thrown errors carry code and details', (t) => {
+  const empty = t.throws(() => decrypt(''), { message: /Input cannot be empty/ }) as any
+  t.is(empty.code, LicenseErrorCode.EmptyInput)
+  t.is(empty.code, 'LICENSE_EMPTY_INPUT')
+  t.deepEqual(empty.details, {})
+
+  const verifier = new LicenseVerifier()
+  const expired = t.throws(() => verifier.verify(EXPIRED_LICENSE)) as any
+  t.is(expired.code, LicenseErrorCode.Expired)
+  t.is(expired.details.expiresAt, 1700000000)
+
+  const strict = new LicenseVerifier(null, { requiredFeatures: ['data_export'] })
+  const missing = t.throws(() => strict.verify(VALID_LICENSE)) as any
+  t.is(missing.code, LicenseErrorCode.MissingFeature)
+  t.is(missing.details.feature, 'data_export')
+
+  const badKey = t.throws(() => new LicenseVerifier('not a key')) as any
+  t.is(badKey.code, LicenseErrorCode.KeyLoad)
+  t.is(typeof badKey.details.reason, 'string')
+})
+
+test('rejected promises carry code and details', async (t) => {
+  const error = (await t.throwsAsync(decryptAsync('QktMQwH/AAA='))) as any
+  t.is(error.code, LicenseErrorCode.UnsupportedAlgorithm)
+  t.truthy(error.details.algorithm)
+
+  const [result] = await decryptMany(['QktMQwL/AAA='])
+  t.false(result.ok)
+  t.is(result.code, LicenseErrorCode.UnsupportedVersion)
+  t.is(result.details!.version, 2)
+})
@@ -1,5 +1,131 @@
 /* auto-generated by NAPI-RS */
 /* eslint-disable */
+export declare class LicenseStore {
+  constructor(dir: string, options?: LicenseStoreOptions | undefined | null)
+  get path(): string
+  save(license: string, activation?: string | undefined | null): StoredLicense
+  saveRevocationList(token: string, keys?: string | Array<string> | undefined | null): RevocationListInfo
+  load(): StoredLicense | null
+  clear(): void
+}
+
+export declare class LicenseVerifier {
+  constructor(keys?: string | Array<string> | undefined | null, options?: LicenseVerifierOptions | undefined | null)
+  setRevocationList(token: string): RevocationListInfo
+  verify(token: string): VerifyResult
+  decode(token: string): LicensePayload
+  hasFeature(token: string, name: string): boolean
+}
+
 export declare function decrypt(encryptedMessage: string): string
 
+export declare function decryptAsync(encryptedMessage: string): Promise<string>
+
+export declare function decryptMany(encryptedMessages: Array<string>): Promise<Array<DecryptResult>>
+
+export interface DecryptResult {
+  ok: boolean
+  value?: string
+  code?: LicenseErrorCode
+  message?: string
+  details?: LicenseErrorDetails
+}
+
+export declare enum LicenseErrorCode {
+  EmptyInput = 'LICENSE_EMPTY_INPUT',
+  InputTooLong = 'LICENSE_INPUT_TOO_LONG',
+  InvalidBase64 = 'LICENSE_BAD_BASE64',
+  InvalidChunkHeader = 'LICENSE_BAD_CHUNK_HEADER',
+  InvalidBlockSize = 'LICENSE_BAD_BLOCK_SIZE',
+  DecryptionFailed = 'LICENSE_DECRYPT_FAILED',
+  InvalidPadding = 'LICENSE_BAD_PADDING',
+  PlaintextTooLong = 'LICENSE_PLAINTEXT_TOO_LONG',
+  InvalidUtf8 = 'LICENSE_BAD_UTF8',
+  KeyLoad = 'LICENSE_KEY_LOAD_FAILED',
+  InvalidFormat = 'LICENSE_BAD_FORMAT',
+  InvalidSignature = 'LICENSE_BAD_SIGNATURE',
+  UnsupportedAlgorithm = 'LICENSE_UNSUPPORTED_ALGORITHM',
+  InvalidPayload = 'LICENSE_BAD_PAYLOAD',
+  Expired = 'LICENSE_EXPIRED',
+  NotYetValid = 'LICENSE_NOT_YET_VALID',
+  UnsupportedVersion = 'LICENSE_UNSUPPORTED_VERSION',
+  UnknownKeyId = 'LICENSE_UNKNOWN_KEY',
+  MissingFeature = 'LICENSE_MISSING_FEATURE',
+  EncryptionFailed = 'LICENSE_ENCRYPT_FAILED',
+  FingerprintMismatch = 'LICENSE_FINGERPRINT_MISMATCH',
+  ActivationMismatch = 'LICENSE_ACTIVATION_MISMATCH',
+  Storage = 'LICENSE_STORAGE_FAILED',
+  StoreTampered = 'LICENSE_STORE_TAMPERED',
+  ClockRollback = 'LICENSE_CLOCK_ROLLBACK',
+  Revoked = 'LICENSE_REVOKED',
+  InvalidOptions = 'LICENSE_BAD_OPTIONS'
+}
+
+export declare function getMachineFingerprint(): MachineFingerprint
+
+export interface LicenseErrorDetails {
+  reason?: string
+  algorithm?: string
+  version?: number
+  expiresAt?: number
+  notBefore?: number
+  keyId?: string
+  feature?: string
+  lastSeen?: number
+  revokedAt?: number
+}
+
+export interface LicensePayload {
+  licensee: string
+  licenseId?: string
+  issuedAt: number
+  expiresAt?: number
+  notBefore?: number
+  maxSeats?: number
+  edition?: string
+  features: Array<string>
+  claims: Record<string, any>
+  fingerprints: Array<Record<string, string>>
+}
+
+export interface LicenseStoreOptions {
+  clockSkew?: number
+  machineFingerprint?: Record<string, string>
+  fingerprintTolerance?: number
+}
+
+export interface LicenseVerifierOptions {
+  clockSkew?: number
+  gracePeriod?: number
+  requiredFeatures?: Array<string>
+  fingerprintTolerance?: number
+  machineFingerprint?: Record<string, string>
+  revocationList?: string
+}
+
+export interface MachineFingerprint {
+  id: string
+  components: Record<string, string>
+}
+
+export interface RevocationListInfo {
+  issuedAt: number
+  nextUpdate?: number
+  licenseIds: Array<string>
+  keyIds: Array<string>
+}
+
+export interface StoredLicense {
+  license: string
+  activation?: string
+  revocationList?: string
+  savedAt: number
+  lastSeen: number
+}
+
 export declare function sum(a: number, b: number): number
+
+export interface VerifyResult {
+  payload: LicensePayload
+  inGracePeriod: boolean
+}
@@ -393,5 +393,11 @@ if (!nativeBinding) {
 }
 
 module.exports = nativeBinding
+module.exports.LicenseStore = nativeBinding.LicenseStore
+module.exports.LicenseVerifier = nativeBinding.LicenseVerifier
 module.exports.decrypt = nativeBinding.decrypt
+module.exports.decryptAsync = nativeBinding.decryptAsync
+module.exports.decryptMany = nativeBinding.decryptMany
+module.exports.LicenseErrorCode = nativeBinding.LicenseErrorCode
+module.exports.getMachineFingerprint = nativeBinding.getMachineFingerprint
 module.exports.sum = nativeBinding.sum
@@ -0,0 +1,185 @@
+use std::fmt;
+
+use base64::prelude::*;
+use lib::container::{
+  split_hybrid_payload, split_signed_payload, Algorithm, Container, MAX_LICENSE_LENGTH,
+};
+use lib::signature::SIGNED_PREFIX;
+use lib::LicenseError;
+use serde::Serialize;
+
+/// 不解密、不校验签名，只解析 license 的格式信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Inspection {
+  /// container / chunked / signed / single-block
+  pub format: &'static str,
+  /// license 文本长度
+  pub length: usize,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub version: Option<u8>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub algorithm: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub key_id: Option<String>,
+  /// RSA 密文块数
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub chunks: Option<usize>,
+  /// 容器 payload 或密文的字节数
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub payload_bytes: Option<usize>,
+  /// 签名 license 中未经校验的原文
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub unverified_message: Option<String>,
+}
+
+impl Inspection {
+  fn new(format: &'static str, token: &str) -> Self {
+    Inspection {
+      format,
+      length: token.len(),
+      version: None,
+      algorithm: None,
+      key_id: None,
+      chunks: None,
+      payload_bytes: None,
+      unverified_message: None,
+    }
+  }
+}
+
+impl fmt::Display for Inspection {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "format:         {}", self.format)?;
+    writeln!(f, "length:         {}", self.length)?;
+    if let Some(version) = self.version {
+      writeln!(f, "version:        {}", version)?;
+    }
+    if let Some(algorithm) = &self.algorithm {
+      writeln!(f, "algorithm:      {}", algorithm)?;
+    }
+    if let Some(key_id) = &self.key_id {
+      let key_id = if key_id.is_empty() { "(none)" } else { key_id };
+      writeln!(f, "key id:         {}", key_id)?;
+    }
+    if let Some(chunks) = self.chunks {
+      writeln!(f, "chunks:         {}", chunks)?;
+    }
+    if let Some(payload_bytes) = self.payload_bytes {
+      writeln!(f, "payload bytes:  {}", payload_bytes)?;
+    }
+    if let Some(message) = &self.unverified_message {
+      writeln!(f, "message (unverified):")?;
+      writeln!(f, "{}", message)?;
+    }
+    Ok(())
+  }
+}
+
+/// 识别 license 格式并解析头部，支持新容器和所有旧格式
+///
+/// `block_size` 为 RSA 密钥的模数长度（字节），给出时计算 RSA 容器的分块数。
+pub fn inspect(token: &str, block_size: Option<usize>) -> Result<Inspection, LicenseError> {
+  if token.is_empty() {
+    return Err(LicenseError::EmptyInput);
+  }
+  if token.len() > MAX_LICENSE_LENGTH {
+    return Err(LicenseError::InputTooLong);
+  }
+
+  if let Some(container) = Container::try_decode(token)? {
+    return inspect_container(token, &container, block_size);
+  }
+  if token.starts_with("CHUNK:") {
+    return inspect_chunked(token);
+  }
+  if token.starts_with(SIGNED_PREFIX) {
+    return inspect_signed(token);
+  }
+
+  let mut inspection = Inspection::new("single-block", token);
+  let block = decode_base64(token)?;
+  inspection.algorithm = Some(Algorithm::RsaPkcs1v15.name().to_string());
+  inspection.chunks = Some(1);
+  inspection.payload_bytes = Some(block.len());
+  Ok(inspection)
+}
+
+fn inspect_container(
+  token: &str,
+  container: &Container,
+  block_size: Option<usize>,
+) -> Result<Inspection, LicenseError> {
+  let mut inspection = Inspection::new("container", token);
+  inspection.version = Some(container.version);
+  inspection.algorithm = Some(container.algorithm.name().to_string());
+  inspection.key_id = Some(container.key_id.clone());
+  inspection.payload_bytes = Some(container.payload.len());
+
+  match container.algorithm {
+    // 块大小取决于密钥模数，没有给出密钥时只报告 payload 字节数
+    Algorithm::RsaPkcs1v15 | Algorithm::RsaOaepSha256 => {
+      if let Some(block_size) = block_size {
+        let length = container.payload.len();
+        if block_size == 0 || length == 0 || !length.is_multiple_of(block_size) {
+          return Err(LicenseError::InvalidBlockSize);
+        }
+        inspection.chunks = Some(length / block_size);
+      }
+    }
+    // 内容由 AES-GCM 整体加密，不分块
+    Algorithm::HybridRsaOaepAes256Gcm => {
+      split_hybrid_payload(&container.payload)?;
+    }
+    Algorithm::Ed25519 | Algorithm::RsaPkcs1v15Sha256 => {
+      let (message, _) = split_signed_payload(&container.payload)?;
+      inspection.unverified_message = Some(String::from_utf8_lossy(message).into_owned());
+    }
+  }
+  Ok(inspection)
+}
+
+// CHUNK:<count>:<block1>|<block2>|...
+fn inspect_chunked(token: &str) -> Result<Inspection, LicenseError> {
+  let parts: Vec<&str> = token.splitn(3, ':').collect();
+  if parts.len() != 3 {
+    return Err(LicenseError::InvalidChunkHeader("Invalid chunked format"));
+  }
+  let chunk_count: usize = match parts[1].parse() {
+    Ok(count) => count,
+    Err(_) => return Err(LicenseError::InvalidChunkHeader("Invalid chunk count")),
+  };
+  let blocks: Vec<&str> = parts[2].split('|').collect();
+  if blocks.len() != chunk_count {
+    return Err(LicenseError::InvalidChunkHeader("Chunk count mismatch"));
+  }
+
+  let mut payload_bytes = 0;
+  for block in blocks {
+    payload_bytes += decode_base64(block)?.len();
+  }
+  let mut inspection = Inspection::new("chunked", token);
+  inspection.algorithm = Some(Algorithm::RsaPkcs1v15.name().to_string());
+  inspection.chunks = Some(chunk_count);
+  inspection.payload_bytes = Some(payload_bytes);
+  Ok(inspection)
+}
+
+// SIG:<alg>:<base64 payload>:<base64 signature>
+fn inspect_signed(token: &str) -> Result<Inspection, LicenseError> {
+  let parts: Vec<&str> = token.split(':').collect();
+  if parts.len() != 4 {
+    return Err(LicenseError::InvalidFormat("Invalid signed format"));
+  }
+  let message = decode_base64(parts[2])?;
+  let mut inspection = Inspection::new("signed", token);
+  inspection.algorithm = Some(parts[1].to_string());
+  inspection.payload_bytes = Some(message.len());
+  inspection.unverified_message = Some(String::from_utf8_lossy(&message).into_owned());
+  Ok(inspection)
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, LicenseError> {
+  BASE64_STANDARD
+    .decode(text)
+    .map_err(|_| LicenseError::InvalidBase64)
+}
@@ -0,0 +1,619 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use lib::keys::{Jwk, PrivateKey, PublicKey};
+use lib::{
+  ActivationRequest, ActivationResponse, Clock, Fingerprint, Keyring, LicenseError, LicensePayload,
+  RevocationList, SystemClock, ValidationOptions, Validity,
+};
+use serde_json::Value;
+
+mod inspect;
+
+/// 离线签发、检查和校验 license，适用于不联网的机器
+#[derive(Parser)]
+#[command(name = "license-cli", version)]
+struct Cli {
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// 生成密钥对：RSA 用于加密 license，Ed25519 用于签名
+  Keygen(KeygenArgs),
+  /// 签发 license，内容来自 JSON 文件或命令行参数
+  Issue(IssueArgs),
+  /// 查看 license 格式、版本、算法、key id 和分块数，不需要私钥
+  Inspect(InspectArgs),
+  /// 校验签名（或解密）并检查有效期，输出 license 内容
+  Verify(VerifyArgs),
+  /// 解密 license，输出原文
+  Decrypt(DecryptArgs),
+  /// 输出本机指纹 JSON，签发绑定机器的 license 时通过 `issue --bind` 传入
+  Fingerprint,
+  /// 在不联网的机器上生成离线激活请求
+  ActivationRequest(ActivationRequestArgs),
+  /// 确认离线激活请求，输出签名的激活响应
+  Activate(ActivateArgs),
+  /// 在发起请求的机器上校验激活响应
+  Install(InstallArgs),
+  /// 签名吊销列表，`verify --revocation-list` 用它拒绝吊销的 license
+  RevocationList(RevocationListArgs),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum KeyType {
+  Rsa,
+  Ed25519,
+}
+
+#[derive(Args)]
+struct KeygenArgs {
+  #[arg(long = "type", value_enum, default_value_t = KeyType::Rsa)]
+  key_type: KeyType,
+  /// RSA 密钥长度：2048、3072 或 4096
+  #[arg(long, default_value_t = 2048)]
+  bits: usize,
+  #[arg(long, value_enum, default_value_t = KeyFormat::Pem)]
+  format: KeyFormat,
+  /// 输出文件前缀，写入 <OUT>.key.<FORMAT> 和 <OUT>.pub.<FORMAT>；不指定时输出到 stdout
+  #[arg(long)]
+  out: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum KeyFormat {
+  /// 私钥 PKCS#8 PEM，公钥 SPKI PEM
+  Pem,
+  /// JSON Web Key
+  Jwk,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum EncryptAlgorithm {
+  /// RSA-OAEP + AES-256-GCM
+  Hybrid,
+  /// 仅 RSA-OAEP(SHA-256)
+  RsaOaep,
+}
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("key").required(true).args(["encrypt_with", "sign_with"])))]
+struct IssueArgs {
+  /// RSA 公钥（SPKI / PKCS#1 PEM 或 JWK），加密签发
+  #[arg(long, value_name = "PUBLIC_KEY")]
+  encrypt_with: Option<PathBuf>,
+  /// Ed25519 或 RSA 私钥（PEM 或 JWK），签名签发
+  #[arg(long, value_name = "PRIVATE_KEY")]
+  sign_with: Option<PathBuf>,
+  /// 加密算法，默认 hybrid
+  #[arg(long, value_enum, conflicts_with = "sign_with")]
+  algorithm: Option<EncryptAlgorithm>,
+  /// license 内容 JSON 文件，`-` 表示 stdin
+  #[arg(long, conflicts_with_all = [
+    "licensee", "license_id", "issued_at", "expires_at", "valid_days", "not_before",
+    "max_seats", "edition", "features", "claims", "bind",
+  ])]
+  payload: Option<PathBuf>,
+  /// 被授权方
+  #[arg(long, required_unless_present = "payload")]
+  licensee: Option<String>,
+  /// license 标识（服务端 LicenseCode 的 name），吊销列表按它匹配
+  #[arg(long)]
+  license_id: Option<String>,
+  /// 签发时间（Unix 秒），默认当前时间
+  #[arg(long)]
+  issued_at: Option<i64>,
+  /// 过期时间（Unix 秒）
+  #[arg(long, conflicts_with = "valid_days")]
+  expires_at: Option<i64>,
+  /// 从签发时间起的有效天数
+  #[arg(long)]
+  valid_days: Option<i64>,
+  /// 生效时间（Unix 秒）
+  #[arg(long)]
+  not_before: Option<i64>,
+  #[arg(long)]
+  max_seats: Option<u32>,
+  #[arg(long)]
+  edition: Option<String>,
+  /// 开通的功能，可重复
+  #[arg(long = "feature")]
+  features: Vec<String>,
+  /// 自定义字段 KEY=VALUE，可重复；VALUE 按 JSON 解析，失败时作为字符串
+  #[arg(long = "claim", value_parser = parse_claim)]
+  claims: Vec<(String, Value)>,
+  /// 绑定机器，文件内容为 `license-cli fingerprint` 的输出，可重复
+  #[arg(long, value_name = "FINGERPRINT")]
+  bind: Vec<PathBuf>,
+  /// 输出文件，不指定时输出到 stdout
+  #[arg(long)]
+  out: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct InspectArgs {
+  /// license 文本，不指定或为 `-` 时从 stdin 读取
+  token: Option<String>,
+  /// RSA 公钥或私钥（PEM 或 JWK），按其模数长度计算 RSA 容器的分块数
+  #[arg(long)]
+  key: Option<PathBuf>,
+  /// 输出 JSON
+  #[arg(long)]
+  json: bool,
+}
+
+#[derive(Args)]
+struct VerifyArgs {
+  /// license 文本，不指定或为 `-` 时从 stdin 读取
+  token: Option<String>,
+  /// 公钥或 RSA 私钥（PEM 或 JWK），可重复，按 key id 选择；不指定时使用内置密钥
+  #[arg(long = "key")]
+  keys: Vec<PathBuf>,
+  /// 校验时刻（Unix 秒），默认当前时间
+  #[arg(long)]
+  now: Option<i64>,
+  /// 过期后的宽限期（秒）
+  #[arg(long, default_value_t = 0)]
+  grace_period: i64,
+  /// 允许的时钟偏差（秒）
+  #[arg(long, default_value_t = 0)]
+  clock_skew: i64,
+  /// 要求开通的功能，可重复
+  #[arg(long = "require-feature")]
+  required_features: Vec<String>,
+  /// 绑定机器的 license 允许变化的指纹组件数
+  #[arg(long, default_value_t = 0)]
+  fingerprint_tolerance: usize,
+  /// 吊销列表文件（服务端 /crl 的响应），用同一组密钥校验签名
+  #[arg(long, value_name = "FILE")]
+  revocation_list: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ActivationRequestArgs {
+  /// license 标识（服务端 LicenseCode 的 name）
+  #[arg(long)]
+  license_id: String,
+  /// 激活码
+  #[arg(long)]
+  code: String,
+  /// 输出文件，不指定时输出到 stdout；安装响应时需要用到
+  #[arg(long)]
+  out: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ActivateArgs {
+  /// 激活请求文本，不指定或为 `-` 时从 stdin 读取
+  request: Option<String>,
+  /// 签名私钥（PEM 或 JWK）
+  #[arg(long, value_name = "PRIVATE_KEY")]
+  sign_with: PathBuf,
+  /// 激活失效时间（Unix 秒）
+  #[arg(long)]
+  expires_at: Option<i64>,
+  /// 输出文件，不指定时输出到 stdout
+  #[arg(long)]
+  out: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct InstallArgs {
+  /// 激活响应文本，不指定或为 `-` 时从 stdin 读取
+  response: Option<String>,
+  /// `activation-request` 生成的请求文件
+  #[arg(long)]
+  request: PathBuf,
+  /// 签名公钥（PEM 或 JWK），可重复；不指定时使用内置密钥
+  #[arg(long = "key")]
+  keys: Vec<PathBuf>,
+  /// 允许变化的指纹组件数
+  #[arg(long, default_value_t = 0)]
+  fingerprint_tolerance: usize,
+}
+
+#[derive(Args)]
+struct RevocationListArgs {
+  /// 吊销列表 JSON 文件，`-` 或不指定时从 stdin 读取
+  list: Option<PathBuf>,
+  /// 签名私钥（PEM 或 JWK）
+  #[arg(long, value_name = "PRIVATE_KEY")]
+  sign_with: PathBuf,
+  /// 输出文件，不指定时输出到 stdout
+  #[arg(long)]
+  out: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct DecryptArgs {
+  /// license 文本，不指定或为 `-` 时从 stdin 读取
+  token: Option<String>,
+  /// RSA 私钥（PEM 或 JWK），可重复，按 key id 选择；不指定时使用内置私钥
+  #[arg(long = "key")]
+  keys: Vec<PathBuf>,
+}
+
+enum CliError {
+  License(LicenseError),
+  Io(PathBuf, io::Error),
+}
+
+impl fmt::Display for CliError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      CliError::License(error) => write!(f, "{}: {}", error.code(), error),
+      CliError::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+    }
+  }
+}
+
+impl From<LicenseError> for CliError {
+  fn from(error: LicenseError) -> Self {
+    CliError::License(error)
+  }
+}
+
+fn main() -> ExitCode {
+  let cli = Cli::parse();
+  let result = match cli.command {
+    Command::Keygen(args) => keygen(args),
+    Command::Issue(args) => issue(args),
+    Command::Inspect(args) => inspect(args),
+    Command::Verify(args) => verify(args),
+    Command::Decrypt(args) => decrypt(args),
+    Command::Fingerprint => fingerprint(),
+    Command::ActivationRequest(args) => activation_request(args),
+    Command::Activate(args) => activate(args),
+    Command::Install(args) => install(args),
+    Command::RevocationList(args) => revocation_list(args),
+  };
+  match result {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(error) => {
+      eprintln!("error: {}", error);
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn keygen(args: KeygenArgs) -> Result<(), CliError> {
+  let private_key = match args.key_type {
+    KeyType::Rsa => PrivateKey::generate_rsa(args.bits)?,
+    KeyType::Ed25519 => PrivateKey::generate_ed25519(),
+  };
+  let public_key = private_key.public_key();
+  let key_id = private_key.key_id()?;
+  let (private_text, public_text, extension) = match args.format {
+    KeyFormat::Pem => (
+      private_key.to_pkcs8_pem()?,
+      public_key.to_spki_pem()?,
+      "pem",
+    ),
+    KeyFormat::Jwk => (
+      format!("{}\n", private_key.to_jwk()?.to_json()?),
+      format!("{}\n", public_key.to_jwk()?.to_json()?),
+      "jwk",
+    ),
+  };
+
+  match args.out {
+    Some(prefix) => {
+      let private_path = with_suffix(&prefix, &format!(".key.{}", extension));
+      let public_path = with_suffix(&prefix, &format!(".pub.{}", extension));
+      write_private_file(&private_path, private_text.as_bytes())?;
+      fs::write(&public_path, public_text).map_err(|e| CliError::Io(public_path.clone(), e))?;
+      println!("private key: {}", private_path.display());
+      println!("public key:  {}", public_path.display());
+      println!("key id:      {}", key_id);
+    }
+    None => {
+      print!("{}{}", private_text, public_text);
+      eprintln!("key id: {}", key_id);
+    }
+  }
+  Ok(())
+}
+
+fn issue(args: IssueArgs) -> Result<(), CliError> {
+  let payload = match &args.payload {
+    Some(path) => LicensePayload::from_json(&read_text(path)?)?,
+    None => payload_from_args(&args)?,
+  };
+
+  let token = if let Some(path) = &args.encrypt_with {
+    let encryptor = load_public_key(path)?.to_encryptor()?;
+    match args.algorithm.unwrap_or(EncryptAlgorithm::Hybrid) {
+      EncryptAlgorithm::Hybrid => encryptor.encrypt_payload(&payload)?,
+      EncryptAlgorithm::RsaOaep => encryptor.encrypt_rsa_oaep(&payload.to_json()?)?,
+    }
+  } else if let Some(path) = &args.sign_with {
+    load_private_key(path)?
+      .to_signing_key()
+      .sign_payload(&payload)?
+  } else {
+    unreachable!("clap requires --encrypt-with or --sign-with")
+  };
+
+  write_output(args.out.as_deref(), &token)
+}
+
+fn write_output(path: Option<&Path>, text: &str) -> Result<(), CliError> {
+  match path {
+    Some(path) => {
+      fs::write(path, format!("{}\n", text)).map_err(|e| CliError::Io(path.to_path_buf(), e))
+    }
+    None => {
+      println!("{}", text);
+      Ok(())
+    }
+  }
+}
+
+fn payload_from_args(args: &IssueArgs) -> Result<LicensePayload, CliError> {
+  let issued_at = args.issued_at.unwrap_or_else(|| SystemClock.now());
+  let mut payload = LicensePayload::new(args.licensee.clone().unwrap_or_default(), issued_at);
+  payload.license_id = args.license_id.clone();
+  payload.expires_at = match (args.expires_at, args.valid_days) {
+    (Some(expires_at), _) => Some(expires_at),
+    (None, Some(days)) => Some(expires_after(issued_at, days)?),
+    (None, None) => None,
+  };
+  payload.not_before = args.not_before;
+  payload.max_seats = args.max_seats;
+  payload.edition = args.edition.clone();
+  payload.features = args.features.clone();
+  payload.claims = args.claims.iter().cloned().collect();
+  payload.fingerprints = args
+    .bind
+    .iter()
+    .map(|path| load_fingerprint(path))
+    .collect::<Result<_, _>>()?;
+  Ok(payload)
+}
+
+// `--valid-days` 换算成过期时间，溢出时报错而不是回绕
+fn expires_after(issued_at: i64, days: i64) -> Result<i64, LicenseError> {
+  days
+    .checked_mul(24 * 60 * 60)
+    .and_then(|seconds| issued_at.checked_add(seconds))
+    .ok_or(LicenseError::InvalidOptions("--valid-days is out of range"))
+}
+
+// 接受 `fingerprint` 子命令的输出，或只有组件的 JSON 对象
+fn load_fingerprint(path: &Path) -> Result<Fingerprint, CliError> {
+  let mut value: Value = serde_json::from_str(&read_text(path)?)
+    .map_err(|e| LicenseError::InvalidPayload(e.to_string()))?;
+  if let Some(components) = value.get_mut("components") {
+    value = components.take();
+  }
+  let fingerprint: Fingerprint =
+    serde_json::from_value(value).map_err(|e| LicenseError::InvalidPayload(e.to_string()))?;
+  if fingerprint.is_empty() {
+    return Err(
+      LicenseError::InvalidPayload(format!("{}: empty fingerprint", path.display())).into(),
+    );
+  }
+  Ok(fingerprint)
+}
+
+fn inspect(args: InspectArgs) -> Result<(), CliError> {
+  let token = read_token(args.token)?;
+  let block_size = match &args.key {
+    Some(path) => Some(load_any_public_key(path)?.rsa_block_size()?),
+    None => None,
+  };
+  let inspection = inspect::inspect(&token, block_size)?;
+  if args.json {
+    println!("{}", serde_json::to_string_pretty(&inspection).unwrap());
+  } else {
+    print!("{}", inspection);
+  }
+  Ok(())
+}
+
+fn verify(args: VerifyArgs) -> Result<(), CliError> {
+  let token = read_token(args.token)?;
+  let keyring = keyring_from(&args.keys)?;
+  let payload = match &args.revocation_list {
+    Some(path) => {
+      RevocationList::verify(&keyring, &read_text(path)?)?.decode_license(&keyring, &token)?
+    }
+    None => keyring.decode_license(&token)?,
+  };
+
+  let options = ValidationOptions::new(args.grace_period, args.clock_skew)?;
+  let now = args.now.unwrap_or_else(|| SystemClock.now());
+  let validity = options.validate(&payload, now)?;
+  payload.require_features(&args.required_features)?;
+  payload.check_fingerprint(&Fingerprint::current(), args.fingerprint_tolerance)?;
+
+  println!("{}", serde_json::to_string_pretty(&payload).unwrap());
+  match validity {
+    Validity::Valid => eprintln!("license is valid"),
+    Validity::InGracePeriod { expires_at } => {
+      eprintln!("license expired at {}, still in grace period", expires_at)
+    }
+  }
+  Ok(())
+}
+
+fn decrypt(args: DecryptArgs) -> Result<(), CliError> {
+  let token = read_token(args.token)?;
+  println!("{}", keyring_from(&args.keys)?.decrypt(&token)?);
+  Ok(())
+}
+
+fn fingerprint() -> Result<(), CliError> {
+  let fingerprint = Fingerprint::current();
+  let output = serde_json::json!({
+    "id": fingerprint.id(),
+    "components": fingerprint,
+  });
+  println!("{}", serde_json::to_string_pretty(&output).unwrap());
+  Ok(())
+}
+
+fn activation_request(args: ActivationRequestArgs) -> Result<(), CliError> {
+  let request = ActivationRequest::current(args.license_id, args.code, SystemClock.now());
+  write_output(args.out.as_deref(), &request.encode()?)
+}
+
+fn activate(args: ActivateArgs) -> Result<(), CliError> {
+  let request = ActivationRequest::decode(&read_token(args.request)?)?;
+  let mut response = ActivationResponse::for_request(&request, SystemClock.now());
+  response.expires_at = args.expires_at;
+  let token = response.sign(&load_private_key(&args.sign_with)?.to_signing_key())?;
+  eprintln!(
+    "activated {} for machine {}",
+    request.license_id,
+    request.fingerprint.id()
+  );
+  write_output(args.out.as_deref(), &token)
+}
+
+fn install(args: InstallArgs) -> Result<(), CliError> {
+  let token = read_token(args.response)?;
+  let request = ActivationRequest::decode(&read_text(&args.request)?)?;
+  let response = ActivationResponse::install(
+    &keyring_from(&args.keys)?,
+    &token,
+    &request,
+    &Fingerprint::current(),
+    args.fingerprint_tolerance,
+  )?;
+  println!("{}", serde_json::to_string_pretty(&response).unwrap());
+  eprintln!("activation is valid");
+  Ok(())
+}
+
+fn revocation_list(args: RevocationListArgs) -> Result<(), CliError> {
+  let text = read_text(args.list.as_deref().unwrap_or(Path::new("-")))?;
+  let list: RevocationList =
+    serde_json::from_str(&text).map_err(|e| LicenseError::InvalidPayload(e.to_string()))?;
+  let token = list.sign(&load_private_key(&args.sign_with)?.to_signing_key())?;
+  eprintln!(
+    "revoked {} licenses and {} keys",
+    list.licenses.len(),
+    list.keys.len()
+  );
+  write_output(args.out.as_deref(), &token)
+}
+
+// 未指定密钥时使用内置的解密私钥和签名公钥
+fn keyring_from(paths: &[PathBuf]) -> Result<Keyring, CliError> {
+  if paths.is_empty() {
+    return Ok(Keyring::with_default_keys()?);
+  }
+  let mut keyring = Keyring::new();
+  for path in paths {
+    let text = read_text(path)?;
+    if !is_jwk(&text) {
+      keyring.add_pem(&text)?;
+      continue;
+    }
+    let jwk = Jwk::from_json(&text)?;
+    if jwk.is_private() {
+      keyring.add_decryptor(PrivateKey::from_jwk(&jwk)?.to_decryptor()?)?;
+    } else {
+      keyring.add_verifying_key(PublicKey::from_jwk(&jwk)?.to_verifying_key())?;
+    }
+  }
+  Ok(keyring)
+}
+
+// 密钥文件可以是 PEM 或 JWK
+fn load_private_key(path: &Path) -> Result<PrivateKey, CliError> {
+  let text = read_text(path)?;
+  if is_jwk(&text) {
+    return Ok(PrivateKey::from_jwk(&Jwk::from_json(&text)?)?);
+  }
+  Ok(PrivateKey::from_pem(&text)?)
+}
+
+fn load_public_key(path: &Path) -> Result<PublicKey, CliError> {
+  let text = read_text(path)?;
+  if is_jwk(&text) {
+    return Ok(PublicKey::from_jwk(&Jwk::from_json(&text)?)?);
+  }
+  Ok(PublicKey::from_pem(&text)?)
+}
+
+// 公钥，或私钥对应的公钥
+fn load_any_public_key(path: &Path) -> Result<PublicKey, CliError> {
+  let text = read_text(path)?;
+  if is_jwk(&text) {
+    let jwk = Jwk::from_json(&text)?;
+    if jwk.is_private() {
+      return Ok(PrivateKey::from_jwk(&jwk)?.public_key());
+    }
+    return Ok(PublicKey::from_jwk(&jwk)?);
+  }
+  if text.contains("PRIVATE KEY-----") {
+    return Ok(PrivateKey::from_pem(&text)?.public_key());
+  }
+  Ok(PublicKey::from_pem(&text)?)
+}
+
+fn is_jwk(text: &str) -> bool {
+  text.trim_start().starts_with('{')
+}
+
+fn parse_claim(text: &str) -> Result<(String, Value), String> {
+  match text.split_once('=') {
+    Some((key, value)) if !key.is_empty() => {
+      let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+      Ok((key.to_string(), value))
+    }
+    _ => Err(format!("expected KEY=VALUE, got `{}`", text)),
+  }
+}
+
+fn read_token(token: Option<String>) -> Result<String, CliError> {
+  let token = match token.as_deref() {
+    None | Some("-") => read_text(Path::new("-"))?,
+    Some(token) => token.to_string(),
+  };
+  Ok(token.trim().to_string())
+}
+
+// `-` 表示 stdin
+fn read_text(path: &Path) -> Result<String, CliError> {
+  if path == Path::new("-") {
+    let mut text = String::new();
+    io::stdin()
+      .read_to_string(&mut text)
+      .map_err(|e| CliError::Io(path.to_path_buf(), e))?;
+    return Ok(text);
+  }
+  fs::read_to_string(path).map_err(|e| CliError::Io(path.to_path_buf(), e))
+}
+
+fn with_suffix(prefix: &Path, suffix: &str) -> PathBuf {
+  let mut path = prefix.as_os_str().to_owned();
+  path.push(suffix);
+  PathBuf::from(path)
+}
+
+// 私钥文件只允许当前用户读写
+fn write_private_file(path: &Path, contents: &[u8]) -> Result<(), CliError> {
+  let mut options = fs::OpenOptions::new();
+  options.write(true).create(true).truncate(true);
+  #[cfg(unix)]
+  std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+  let write = |mut file: fs::File| {
+    // mode 只在新建文件时生效，覆盖已有文件时在写入私钥前收紧权限
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(contents)
+  };
+  options
+    .open(path)
+    .and_then(write)
+    .map_err(|e| CliError::Io(path.to_path_buf(), e))
+}
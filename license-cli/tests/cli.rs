@@ -0,0 +1,464 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use serde_json::Value;
+
+fn run(args: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_license-cli"))
+    .args(args)
+    .output()
+    .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("license-cli-{}-{}", name, std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+fn keygen(dir: &Path, key_type: &str) -> (String, String, String) {
+  let prefix = dir.join(key_type);
+  let output = stdout(&run(&[
+    "keygen",
+    "--type",
+    key_type,
+    "--out",
+    prefix.to_str().unwrap(),
+  ]));
+  let key_id = output
+    .lines()
+    .find_map(|line| line.strip_prefix("key id:"))
+    .unwrap()
+    .trim()
+    .to_string();
+  (
+    format!("{}.key.pem", prefix.display()),
+    format!("{}.pub.pem", prefix.display()),
+    key_id,
+  )
+}
+
+#[test]
+fn test_sign_and_verify() {
+  let dir = temp_dir("sign");
+  let (private_key, public_key, key_id) = keygen(&dir, "ed25519");
+
+  let token = stdout(&run(&[
+    "issue",
+    "--sign-with",
+    &private_key,
+    "--licensee",
+    "Air Gapped Ltd",
+    "--issued-at",
+    "1754611200",
+    "--expires-at",
+    "1786147200",
+    "--feature",
+    "sso",
+    "--claim",
+    "seats_used=3",
+  ]));
+  let token = token.trim();
+
+  let inspection: Value =
+    serde_json::from_str(&stdout(&run(&["inspect", "--json", token]))).unwrap();
+  assert_eq!(inspection["format"], "container");
+  assert_eq!(inspection["version"], 1);
+  assert_eq!(inspection["algorithm"], "ED25519");
+  assert_eq!(inspection["key_id"], key_id.as_str());
+
+  let payload: Value = serde_json::from_str(&stdout(&run(&[
+    "verify",
+    "--key",
+    &public_key,
+    "--now",
+    "1760000000",
+    "--require-feature",
+    "sso",
+    token,
+  ])))
+  .unwrap();
+  assert_eq!(payload["licensee"], "Air Gapped Ltd");
+  assert_eq!(payload["claims"]["seats_used"], 3);
+
+  // 过期、缺少功能、内置公钥不匹配都会失败
+  let expired = run(&["verify", "--key", &public_key, "--now", "1786147200", token]);
+  assert!(!expired.status.success());
+  assert!(String::from_utf8_lossy(&expired.stderr).contains("LICENSE_EXPIRED"));
+
+  let missing = run(&[
+    "verify",
+    "--key",
+    &public_key,
+    "--now",
+    "1760000000",
+    "--require-feature",
+    "audit",
+    token,
+  ]);
+  assert!(String::from_utf8_lossy(&missing.stderr).contains("LICENSE_MISSING_FEATURE"));
+
+  let default_keys = run(&["verify", token]);
+  assert!(!default_keys.status.success());
+
+  // 有效天数溢出时报错，而不是签发一个回绕的过期时间
+  let overflow = run(&[
+    "issue",
+    "--sign-with",
+    &private_key,
+    "--licensee",
+    "Air Gapped Ltd",
+    "--valid-days",
+    &i64::MAX.to_string(),
+  ]);
+  assert!(!overflow.status.success());
+  assert!(String::from_utf8_lossy(&overflow.stderr).contains("--valid-days is out of range"));
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn test_private_key_permissions() {
+  use std::os::unix::fs::PermissionsExt;
+
+  // 覆盖已有的其他人可读的文件时同样收紧权限
+  let dir = temp_dir("permissions");
+  let private_key = dir.join("ed25519.key.pem");
+  fs::write(&private_key, "old").unwrap();
+  fs::set_permissions(&private_key, fs::Permissions::from_mode(0o644)).unwrap();
+
+  keygen(&dir, "ed25519");
+  let mode = fs::metadata(&private_key).unwrap().permissions().mode();
+  assert_eq!(mode & 0o777, 0o600);
+  assert!(fs::read_to_string(&private_key)
+    .unwrap()
+    .contains("PRIVATE KEY"));
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_encrypt_and_decrypt() {
+  let dir = temp_dir("encrypt");
+  let (private_key, public_key, key_id) = keygen(&dir, "rsa");
+
+  let payload_path = dir.join("payload.json");
+  fs::write(
+    &payload_path,
+    r#"{"licensee":"Offline Tools Ltd","issued_at":1754611200,"edition":"enterprise"}"#,
+  )
+  .unwrap();
+
+  for (algorithm, name) in [
+    ("hybrid", "RSA-OAEP-SHA256+AES-256-GCM"),
+    ("rsa-oaep", "RSA-OAEP-SHA256"),
+  ] {
+    let token = stdout(&run(&[
+      "issue",
+      "--encrypt-with",
+      &public_key,
+      "--algorithm",
+      algorithm,
+      "--payload",
+      payload_path.to_str().unwrap(),
+    ]));
+    let token = token.trim();
+
+    let inspection = stdout(&run(&["inspect", token]));
+    assert!(inspection.contains(name), "{}", inspection);
+    assert!(inspection.contains(&key_id));
+
+    // 不知道密钥长度时不猜测分块数，混合加密不分块
+    let inspection: Value =
+      serde_json::from_str(&stdout(&run(&["inspect", "--json", token]))).unwrap();
+    assert!(inspection.get("chunks").is_none());
+    assert!(inspection["payload_bytes"].as_u64().unwrap() > 0);
+    for key in [&public_key, &private_key] {
+      let inspection: Value =
+        serde_json::from_str(&stdout(&run(&["inspect", "--json", "--key", key, token]))).unwrap();
+      if algorithm == "rsa-oaep" {
+        assert_eq!(inspection["chunks"], 1);
+      } else {
+        assert!(inspection.get("chunks").is_none());
+      }
+    }
+
+    let plain = stdout(&run(&["decrypt", "--key", &private_key, token]));
+    assert!(plain.contains("Offline Tools Ltd"));
+
+    let payload: Value =
+      serde_json::from_str(&stdout(&run(&["verify", "--key", &private_key, token]))).unwrap();
+    assert_eq!(payload["edition"], "enterprise");
+
+    // 内置私钥不是这把密钥
+    assert!(!run(&["decrypt", token]).status.success());
+  }
+
+  // 超过一块明文长度的内容按 RSA-2048 的 256 字节分块
+  let token = stdout(&run(&[
+    "issue",
+    "--encrypt-with",
+    &public_key,
+    "--algorithm",
+    "rsa-oaep",
+    "--licensee",
+    &"Offline Tools Ltd ".repeat(20),
+  ]));
+  let inspection: Value = serde_json::from_str(&stdout(&run(&[
+    "inspect",
+    "--json",
+    "--key",
+    &public_key,
+    token.trim(),
+  ])))
+  .unwrap();
+  let payload_bytes = inspection["payload_bytes"].as_u64().unwrap();
+  assert_eq!(inspection["chunks"].as_u64(), Some(payload_bytes / 256));
+  assert!(payload_bytes / 256 >= 2);
+
+  // Ed25519 密钥不能用来计算 RSA 分块数
+  let (_, ed25519_key, _) = keygen(&dir, "ed25519");
+  assert!(!run(&["inspect", "--key", &ed25519_key, token.trim()])
+    .status
+    .success());
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_inspect_legacy_formats() {
+  let inspection: Value =
+    serde_json::from_str(&stdout(&run(&["inspect", "--json", "CHUNK:2:AAAA|AAAA"]))).unwrap();
+  assert_eq!(inspection["format"], "chunked");
+  assert_eq!(inspection["chunks"], 2);
+
+  let inspection: Value = serde_json::from_str(&stdout(&run(&[
+    "inspect",
+    "--json",
+    "SIG:ED25519:e30=:AAAA",
+  ])))
+  .unwrap();
+  assert_eq!(inspection["format"], "signed");
+  assert_eq!(inspection["algorithm"], "ED25519");
+  assert_eq!(inspection["unverified_message"], "{}");
+
+  let output = run(&["inspect", "CHUNK:3:AAAA|AAAA"]);
+  assert!(!output.status.success());
+  assert!(String::from_utf8_lossy(&output.stderr).contains("LICENSE_BAD_CHUNK_HEADER"));
+}
+
+#[test]
+fn test_issue_requires_key_and_licensee() {
+  assert!(!run(&["issue", "--licensee", "No Key"]).status.success());
+  assert!(!run(&["issue", "--sign-with", "missing.pem"])
+    .status
+    .success());
+  assert!(!run(&["keygen", "--bits", "1024"]).status.success());
+}
+
+#[test]
+fn test_jwk_keys() {
+  let dir = temp_dir("jwk");
+  let prefix = dir.join("ed25519");
+  stdout(&run(&[
+    "keygen",
+    "--type",
+    "ed25519",
+    "--format",
+    "jwk",
+    "--out",
+    prefix.to_str().unwrap(),
+  ]));
+  let private_key = format!("{}.key.jwk", prefix.display());
+  let public_key = format!("{}.pub.jwk", prefix.display());
+  let jwk: Value = serde_json::from_str(&fs::read_to_string(&public_key).unwrap()).unwrap();
+  assert_eq!(jwk["kty"], "OKP");
+  assert!(jwk.get("d").is_none());
+
+  let token = stdout(&run(&[
+    "issue",
+    "--sign-with",
+    &private_key,
+    "--licensee",
+    "JWK Co.",
+  ]));
+  let payload: Value = serde_json::from_str(&stdout(&run(&[
+    "verify",
+    "--key",
+    &public_key,
+    token.trim(),
+  ])))
+  .unwrap();
+  assert_eq!(payload["licensee"], "JWK Co.");
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_node_locked_license() {
+  let dir = temp_dir("bind");
+  let (private_key, public_key, _) = keygen(&dir, "ed25519");
+
+  let fingerprint: Value = serde_json::from_str(&stdout(&run(&["fingerprint"]))).unwrap();
+  assert_eq!(fingerprint["id"].as_str().unwrap().len(), 32);
+  if fingerprint["components"].as_object().unwrap().is_empty() {
+    // 取不到任何机器标识的环境无法绑定
+    return;
+  }
+  let this_machine = dir.join("this.json");
+  fs::write(&this_machine, fingerprint.to_string()).unwrap();
+  let other_machine = dir.join("other.json");
+  fs::write(&other_machine, r#"{"hostname": "00", "machine_id": "11"}"#).unwrap();
+
+  let issue = |bind: &Path| {
+    stdout(&run(&[
+      "issue",
+      "--sign-with",
+      &private_key,
+      "--licensee",
+      "Node Locked Ltd",
+      "--bind",
+      bind.to_str().unwrap(),
+    ]))
+    .trim()
+    .to_string()
+  };
+
+  let payload: Value = serde_json::from_str(&stdout(&run(&[
+    "verify",
+    "--key",
+    &public_key,
+    &issue(&this_machine),
+  ])))
+  .unwrap();
+  assert_eq!(payload["fingerprints"][0], fingerprint["components"]);
+
+  let output = run(&["verify", "--key", &public_key, &issue(&other_machine)]);
+  assert!(!output.status.success());
+  assert!(String::from_utf8_lossy(&output.stderr).contains("LICENSE_FINGERPRINT_MISMATCH"));
+}
+
+#[test]
+fn test_offline_activation() {
+  let dir = temp_dir("activation");
+  let (private_key, public_key, _) = keygen(&dir, "ed25519");
+
+  let request = dir.join("request.txt");
+  stdout(&run(&[
+    "activation-request",
+    "--license-id",
+    "acme-2025",
+    "--code",
+    "CODE-1234",
+    "--out",
+    request.to_str().unwrap(),
+  ]));
+  let request_token = fs::read_to_string(&request).unwrap();
+
+  let response = stdout(&run(&[
+    "activate",
+    "--sign-with",
+    &private_key,
+    "--expires-at",
+    "4102444800",
+    request_token.trim(),
+  ]));
+
+  let installed: Value = serde_json::from_str(&stdout(&run(&[
+    "install",
+    "--request",
+    request.to_str().unwrap(),
+    "--key",
+    &public_key,
+    response.trim(),
+  ])))
+  .unwrap();
+  assert_eq!(installed["license_id"], "acme-2025");
+  assert_eq!(installed["expires_at"], 4102444800i64);
+
+  // 另一次请求的响应不能安装
+  let other = dir.join("other.txt");
+  stdout(&run(&[
+    "activation-request",
+    "--license-id",
+    "acme-2025",
+    "--code",
+    "CODE-1234",
+    "--out",
+    other.to_str().unwrap(),
+  ]));
+  let output = run(&[
+    "install",
+    "--request",
+    other.to_str().unwrap(),
+    "--key",
+    &public_key,
+    response.trim(),
+  ]);
+  assert!(!output.status.success());
+  assert!(String::from_utf8_lossy(&output.stderr).contains("LICENSE_ACTIVATION_MISMATCH"));
+}
+
+#[test]
+fn test_revocation_list() {
+  let dir = temp_dir("revocation");
+  let (private_key, public_key, _) = keygen(&dir, "ed25519");
+
+  let issue = |license_id: &str| {
+    stdout(&run(&[
+      "issue",
+      "--sign-with",
+      &private_key,
+      "--licensee",
+      "Acme",
+      "--license-id",
+      license_id,
+    ]))
+  };
+  let revoked = issue("acme-2025");
+  let active = issue("acme-2026");
+
+  let list = dir.join("crl.json");
+  fs::write(
+    &list,
+    r#"{"issued_at": 1754611200, "licenses": [{"license_id": "acme-2025", "revoked_at": 1754607600}]}"#,
+  )
+  .unwrap();
+  let crl = dir.join("crl.txt");
+  stdout(&run(&[
+    "revocation-list",
+    "--sign-with",
+    &private_key,
+    "--out",
+    crl.to_str().unwrap(),
+    list.to_str().unwrap(),
+  ]));
+
+  let verify = |token: &str| {
+    run(&[
+      "verify",
+      "--key",
+      &public_key,
+      "--revocation-list",
+      crl.to_str().unwrap(),
+      token.trim(),
+    ])
+  };
+  let output = verify(&revoked);
+  assert!(!output.status.success());
+  assert!(String::from_utf8_lossy(&output.stderr).contains("LICENSE_REVOKED"));
+
+  let payload: Value = serde_json::from_str(&stdout(&verify(&active))).unwrap();
+  assert_eq!(payload["license_id"], "acme-2026");
+}
@@ -1,5 +1,4 @@
 // 演示新的长消息加密功能的示例
-use lib::decrypt;
 
 fn main() {
   println!("=== 长消息加密功能演示 ===\n");
@@ -49,7 +48,7 @@ fn simulate_very_long_message() {
         数字签名: SHA256(...)\n\
         {}",
     "=".repeat(50),
-    vec![
+    [
       "基础模块",
       "高级模块",
       "企业模块",
@@ -61,7 +60,7 @@ fn simulate_very_long_message() {
       "审计日志"
     ]
     .join(", "),
-    vec![
+    [
       "不得逆向工程",
       "不得二次销售",
       "仅限授权用户使用",
@@ -74,7 +73,7 @@ fn simulate_very_long_message() {
   );
 
   println!("实际许可证长度: {} 字符", license_content.len());
-  println!("需要的块数: {} 块", (license_content.len() + 244) / 245);
+  println!("需要的块数: {} 块", license_content.len().div_ceil(245));
   println!("格式: CHUNK:N:block1|block2|...|blockN");
   println!("✅ 支持任意长度的许可证内容\n");
 }
@@ -0,0 +1,294 @@
+use base64::prelude::*;
+use ed25519_dalek::Signer;
+use rsa::pkcs8::EncodePublicKey;
+use rsa::rand_core::{OsRng, RngCore};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::container::{signed_payload, signing_input, split_signed_payload, Algorithm, Container};
+use crate::{keyring, Fingerprint, Keyring, LicenseError, VerifyingKey};
+
+#[cfg(feature = "issuer")]
+use crate::issuer::SigningKey;
+
+/// 激活请求消息中的 `type`
+pub const ACTIVATION_REQUEST_TYPE: &str = "activation_request";
+
+/// 激活响应消息中的 `type`，与 license 内容区分开，签名 license 不能当作激活响应使用
+pub const ACTIVATION_RESPONSE_TYPE: &str = "activation_response";
+
+/// 离线激活请求：在不联网的机器上生成，带到联网的机器上换取激活响应
+///
+/// 文本形式为 Ed25519 签名的 `Container`，签名密钥每次生成请求时随机产生，
+/// 公钥放在消息中，只用于发现传递过程中的篡改或损坏，不代表请求方身份。
+/// 请求方需要保留请求（或其文本），安装响应时用于核对 nonce。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivationRequest {
+  /// license 标识，对应服务端 LicenseCode 的 name
+  pub license_id: String,
+  /// 激活码，对应服务端 LicenseCode 的 code
+  pub code: String,
+  /// 请求激活的机器
+  pub fingerprint: Fingerprint,
+  /// 随机数，响应中原样带回
+  pub nonce: String,
+  /// 生成请求的时间（Unix 秒）
+  pub requested_at: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignedRequest {
+  #[serde(rename = "type")]
+  kind: String,
+  /// 一次性签名公钥，base64
+  public_key: String,
+  #[serde(flatten)]
+  request: ActivationRequest,
+}
+
+impl ActivationRequest {
+  pub fn new(
+    license_id: impl Into<String>,
+    code: impl Into<String>,
+    fingerprint: Fingerprint,
+    now: i64,
+  ) -> Self {
+    let mut nonce = [0u8; 16];
+    OsRng.fill_bytes(&mut nonce);
+    ActivationRequest {
+      license_id: license_id.into(),
+      code: code.into(),
+      fingerprint,
+      nonce: BASE64_STANDARD.encode(nonce),
+      requested_at: now,
+    }
+  }
+
+  /// 为当前机器生成激活请求，见 `Fingerprint::current`
+  pub fn current(license_id: impl Into<String>, code: impl Into<String>, now: i64) -> Self {
+    Self::new(license_id, code, Fingerprint::current(), now)
+  }
+
+  /// 签名并编码为文本
+  pub fn encode(&self) -> Result<String, LicenseError> {
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(&secret);
+    let verifying_key = signing_key.verifying_key();
+    let der = verifying_key
+      .to_public_key_der()
+      .map_err(|e| LicenseError::KeyLoad(e.to_string()))?;
+
+    let message = to_json(&SignedRequest {
+      kind: ACTIVATION_REQUEST_TYPE.to_string(),
+      public_key: BASE64_STANDARD.encode(verifying_key.as_bytes()),
+      request: self.clone(),
+    })?;
+    let mut container = Container::new(Algorithm::Ed25519, keyring::key_id(der.as_bytes()), vec![]);
+    let signature = signing_key.sign(&signing_input(
+      &container.header_bytes()?,
+      message.as_bytes(),
+    ));
+    container.payload = signed_payload(message.as_bytes(), &signature.to_bytes());
+    container.encode()
+  }
+
+  /// 解码并校验签名
+  pub fn decode(token: &str) -> Result<Self, LicenseError> {
+    let container = decode_signed_container(token, NOT_SIGNED)?;
+    if container.algorithm != Algorithm::Ed25519 {
+      return Err(LicenseError::UnsupportedAlgorithm(
+        container.algorithm.name().to_string(),
+      ));
+    }
+    let (message, signature) = split_signed_payload(&container.payload)?;
+    let signed: SignedRequest = from_json(message, ACTIVATION_REQUEST_TYPE, UNEXPECTED_TYPE)?;
+
+    let public_key: [u8; 32] = BASE64_STANDARD
+      .decode(&signed.public_key)
+      .map_err(|_| LicenseError::InvalidBase64)?
+      .try_into()
+      .map_err(|_| LicenseError::KeyLoad("Invalid request public key".to_string()))?;
+    let public_key = ed25519_dalek::VerifyingKey::from_bytes(&public_key)
+      .map_err(|e| LicenseError::KeyLoad(e.to_string()))?;
+    let key = VerifyingKey::Ed25519(public_key);
+    if key.key_id()? != container.key_id {
+      return Err(LicenseError::InvalidSignature);
+    }
+    key.verify(
+      &signing_input(&container.header_bytes()?, message),
+      signature,
+    )?;
+    Ok(signed.request)
+  }
+}
+
+/// 离线激活响应：由签发方用签名私钥对请求签名确认，在请求方机器上安装
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivationResponse {
+  pub license_id: String,
+  /// 被激活的机器，取自请求
+  pub fingerprint: Fingerprint,
+  /// 请求中的随机数
+  pub nonce: String,
+  /// 激活时间（Unix 秒）
+  pub activated_at: i64,
+  /// 激活失效时间，`None` 表示与 license 同期
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub expires_at: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignedResponse {
+  #[serde(rename = "type")]
+  kind: String,
+  #[serde(flatten)]
+  response: ActivationResponse,
+}
+
+impl ActivationResponse {
+  /// 确认激活请求，请求中的机器指纹和 nonce 原样带回
+  pub fn for_request(request: &ActivationRequest, now: i64) -> Self {
+    ActivationResponse {
+      license_id: request.license_id.clone(),
+      fingerprint: request.fingerprint.clone(),
+      nonce: request.nonce.clone(),
+      activated_at: now,
+      expires_at: None,
+    }
+  }
+
+  /// 签名并编码为文本，格式同签名 license
+  #[cfg(feature = "issuer")]
+  pub fn sign(&self, signing_key: &SigningKey) -> Result<String, LicenseError> {
+    signing_key.sign(&to_json(&SignedResponse {
+      kind: ACTIVATION_RESPONSE_TYPE.to_string(),
+      response: self.clone(),
+    })?)
+  }
+
+  /// 按容器头部的 key id 从钥匙串中选择公钥校验签名
+  pub fn verify(keyring: &Keyring, token: &str) -> Result<Self, LicenseError> {
+    let container = decode_signed_container(token, NOT_SIGNED)?;
+    let key = match keyring.verifying_key(&container.key_id) {
+      Some(key) => key,
+      None => return Err(LicenseError::UnknownKeyId(container.key_id)),
+    };
+    Self::verify_container(key, &container)
+  }
+
+  /// 使用指定公钥校验签名
+  pub fn verify_with(key: &VerifyingKey, token: &str) -> Result<Self, LicenseError> {
+    Self::verify_container(key, &decode_signed_container(token, NOT_SIGNED)?)
+  }
+
+  fn verify_container(key: &VerifyingKey, container: &Container) -> Result<Self, LicenseError> {
+    let message = verify_signed_message(key, container)?;
+    let signed: SignedResponse = from_json(message, ACTIVATION_RESPONSE_TYPE, UNEXPECTED_TYPE)?;
+    Ok(signed.response)
+  }
+
+  /// 安装激活响应：校验签名，并核对响应确实对应 `request` 且属于当前机器
+  ///
+  /// 安装后保存响应文本，之后启动时用 `verify` 和 `check_machine` 重新校验。
+  pub fn install(
+    keyring: &Keyring,
+    token: &str,
+    request: &ActivationRequest,
+    current: &Fingerprint,
+    tolerance: usize,
+  ) -> Result<Self, LicenseError> {
+    let response = Self::verify(keyring, token)?;
+    if response.license_id != request.license_id {
+      return Err(LicenseError::ActivationMismatch(
+        "License id does not match the request",
+      ));
+    }
+    if response.nonce != request.nonce {
+      return Err(LicenseError::ActivationMismatch(
+        "Nonce does not match the request",
+      ));
+    }
+    response.check_machine(current, tolerance)?;
+    Ok(response)
+  }
+
+  /// 检查激活是否属于 `current` 机器，规则同 `LicensePayload::check_fingerprint`
+  pub fn check_machine(&self, current: &Fingerprint, tolerance: usize) -> Result<(), LicenseError> {
+    if self.fingerprint.matches(current, tolerance) {
+      Ok(())
+    } else {
+      Err(LicenseError::FingerprintMismatch)
+    }
+  }
+
+  /// 激活在 `now` 是否已失效
+  pub fn is_expired(&self, now: i64) -> bool {
+    self.expires_at.is_some_and(|expires_at| now >= expires_at)
+  }
+}
+
+const NOT_SIGNED: &str = "Activation must be a signed container";
+const UNEXPECTED_TYPE: &str = "Unexpected activation message type";
+
+// 以下供激活和吊销列表等签名消息共用，`not_signed`、`unexpected_type` 为对应的错误原因
+
+pub(crate) fn decode_signed_container(
+  token: &str,
+  not_signed: &'static str,
+) -> Result<Container, LicenseError> {
+  let token = token.trim();
+  if token.is_empty() {
+    return Err(LicenseError::EmptyInput);
+  }
+  if !Container::is_container(token) {
+    return Err(LicenseError::InvalidFormat(not_signed));
+  }
+  let container = Container::decode(token)?;
+  if !container.algorithm.is_signature() {
+    return Err(LicenseError::InvalidFormat(not_signed));
+  }
+  Ok(container)
+}
+
+// 校验签名，返回签名的消息
+pub(crate) fn verify_signed_message<'a>(
+  key: &VerifyingKey,
+  container: &'a Container,
+) -> Result<&'a [u8], LicenseError> {
+  if container.algorithm != key.algorithm().container_algorithm() {
+    return Err(LicenseError::UnsupportedAlgorithm(
+      container.algorithm.name().to_string(),
+    ));
+  }
+  let (message, signature) = split_signed_payload(&container.payload)?;
+  key.verify(
+    &signing_input(&container.header_bytes()?, message),
+    signature,
+  )?;
+  Ok(message)
+}
+
+pub(crate) fn to_json<T: Serialize>(value: &T) -> Result<String, LicenseError> {
+  serde_json::to_string(value).map_err(|e| LicenseError::InvalidPayload(e.to_string()))
+}
+
+// 先检查 `type` 再解析，防止把签名 license、激活请求、响应和吊销列表混用
+pub(crate) fn from_json<T: DeserializeOwned>(
+  message: &[u8],
+  expected: &str,
+  unexpected_type: &'static str,
+) -> Result<T, LicenseError> {
+  #[derive(Deserialize)]
+  struct MessageType {
+    #[serde(rename = "type", default)]
+    kind: String,
+  }
+
+  let message_type: MessageType =
+    serde_json::from_slice(message).map_err(|e| LicenseError::InvalidPayload(e.to_string()))?;
+  if message_type.kind != expected {
+    return Err(LicenseError::InvalidFormat(unexpected_type));
+  }
+  serde_json::from_slice(message).map_err(|e| LicenseError::InvalidPayload(e.to_string()))
+}
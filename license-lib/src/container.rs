@@ -0,0 +1,282 @@
+use base64::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::LicenseError;
+
+/// license 文本（容器及旧格式）最大长度，防止超大输入占用内存
+pub const MAX_LICENSE_LENGTH: usize = 1024 * 1024;
+
+/// 混合加密 payload 中 AES-GCM nonce 的长度（字节）
+pub const NONCE_LENGTH: usize = 12;
+
+/// 容器魔数
+pub const CONTAINER_MAGIC: &[u8; 4] = b"BKLC";
+
+/// 当前容器格式版本
+pub const CONTAINER_VERSION: u8 = 1;
+
+/// 按块加密的 payload（旧格式 CHUNK、`RsaPkcs1v15`、`RsaOaepSha256`）最多包含的块数
+///
+/// 每块都需要一次 RSA 私钥运算，限制块数避免超大输入占用过多 CPU。
+pub const MAX_BLOCKS: usize = 100;
+
+/// 容器文本形式（base64）的固定开头，由魔数决定
+///
+/// 旧格式的单块 RSA 密文恰好以此开头的概率约为 2^-30，解析失败时仍会回退到旧格式。
+pub const CONTAINER_TEXT_PREFIX: &str = "QktMQ";
+
+/// 容器中 payload 使用的算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+  /// RSA PKCS#1 v1.5 加密，payload 为若干个密钥长度的密文块
+  /// 存在 padding oracle 风险，仅用于读取旧 license，新 license 不再使用
+  RsaPkcs1v15 = 1,
+  /// RSA-OAEP(SHA-256) 包裹的 AES-256-GCM 密钥
+  /// payload: u16 wrapped key 长度 | wrapped key | nonce | ciphertext
+  HybridRsaOaepAes256Gcm = 2,
+  /// Ed25519 签名，payload: u32 消息长度 | 消息 | 签名
+  Ed25519 = 3,
+  /// RSASSA-PKCS1-v1_5 + SHA-256 签名，payload 布局同 Ed25519
+  RsaPkcs1v15Sha256 = 4,
+  /// RSA-OAEP(SHA-256) 直接加密，payload 为若干个密钥长度的密文块
+  /// 每块明文最多为密钥长度 - 66 字节，OAEP label 见 `oaep_block_label`
+  RsaOaepSha256 = 5,
+}
+
+impl Algorithm {
+  pub fn from_id(id: u8) -> Result<Self, LicenseError> {
+    match id {
+      1 => Ok(Algorithm::RsaPkcs1v15),
+      2 => Ok(Algorithm::HybridRsaOaepAes256Gcm),
+      3 => Ok(Algorithm::Ed25519),
+      4 => Ok(Algorithm::RsaPkcs1v15Sha256),
+      5 => Ok(Algorithm::RsaOaepSha256),
+      _ => Err(LicenseError::UnsupportedAlgorithm(format!("id {}", id))),
+    }
+  }
+
+  pub fn id(&self) -> u8 {
+    *self as u8
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Algorithm::RsaPkcs1v15 => "RSA-PKCS1v15",
+      Algorithm::HybridRsaOaepAes256Gcm => "RSA-OAEP-SHA256+AES-256-GCM",
+      Algorithm::Ed25519 => "ED25519",
+      Algorithm::RsaPkcs1v15Sha256 => "RS256",
+      Algorithm::RsaOaepSha256 => "RSA-OAEP-SHA256",
+    }
+  }
+
+  /// 是否为签名算法（否则为加密算法）
+  pub fn is_signature(&self) -> bool {
+    matches!(self, Algorithm::Ed25519 | Algorithm::RsaPkcs1v15Sha256)
+  }
+}
+
+/// 自描述的 license 容器
+///
+/// 二进制布局（version 1）：
+/// ```text
+/// magic "BKLC" | version u8 | algorithm u8 | key id 长度 u8 | key id | payload
+/// ```
+/// 文本形式为整个二进制的标准 base64。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Container {
+  pub version: u8,
+  pub algorithm: Algorithm,
+  pub key_id: String,
+  pub payload: Vec<u8>,
+}
+
+impl Container {
+  pub fn new(algorithm: Algorithm, key_id: impl Into<String>, payload: Vec<u8>) -> Self {
+    Container {
+      version: CONTAINER_VERSION,
+      algorithm,
+      key_id: key_id.into(),
+      payload,
+    }
+  }
+
+  /// 文本是否为容器格式
+  pub fn is_container(text: &str) -> bool {
+    text.starts_with(CONTAINER_TEXT_PREFIX)
+  }
+
+  /// 头部字节（payload 之前的部分），加密时作为 AAD，签名时计入签名范围
+  pub fn header_bytes(&self) -> Result<Vec<u8>, LicenseError> {
+    if self.key_id.len() > u8::MAX as usize {
+      return Err(LicenseError::InvalidFormat("Key id too long"));
+    }
+    let mut header = Vec::with_capacity(7 + self.key_id.len());
+    header.extend_from_slice(CONTAINER_MAGIC);
+    header.push(self.version);
+    header.push(self.algorithm.id());
+    header.push(self.key_id.len() as u8);
+    header.extend_from_slice(self.key_id.as_bytes());
+    Ok(header)
+  }
+
+  pub fn to_bytes(&self) -> Result<Vec<u8>, LicenseError> {
+    let mut bytes = self.header_bytes()?;
+    bytes.extend_from_slice(&self.payload);
+    Ok(bytes)
+  }
+
+  pub fn encode(&self) -> Result<String, LicenseError> {
+    Ok(BASE64_STANDARD.encode(self.to_bytes()?))
+  }
+
+  /// 二进制是否以容器魔数开头
+  fn has_magic(bytes: &[u8]) -> bool {
+    bytes.len() >= 5 && &bytes[..4] == CONTAINER_MAGIC
+  }
+
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, LicenseError> {
+    if !Self::has_magic(bytes) {
+      return Err(LicenseError::InvalidFormat("Invalid container magic"));
+    }
+
+    // 按版本分发，新版本在这里增加分支
+    match bytes[4] {
+      1 => Self::from_bytes_v1(bytes),
+      version => Err(LicenseError::UnsupportedVersion(version)),
+    }
+  }
+
+  fn from_bytes_v1(bytes: &[u8]) -> Result<Self, LicenseError> {
+    if bytes.len() < 7 {
+      return Err(LicenseError::InvalidFormat("Truncated container header"));
+    }
+    let algorithm = Algorithm::from_id(bytes[5])?;
+    let key_id_end = 7 + bytes[6] as usize;
+    if bytes.len() < key_id_end {
+      return Err(LicenseError::InvalidFormat("Truncated container header"));
+    }
+    let key_id = match std::str::from_utf8(&bytes[7..key_id_end]) {
+      Ok(key_id) => key_id.to_string(),
+      Err(_) => return Err(LicenseError::InvalidFormat("Invalid container key id")),
+    };
+
+    Ok(Container {
+      version: 1,
+      algorithm,
+      key_id,
+      payload: bytes[key_id_end..].to_vec(),
+    })
+  }
+
+  pub fn decode(text: &str) -> Result<Self, LicenseError> {
+    Self::from_bytes(&decode_text(text)?)
+  }
+
+  /// 解析可能为旧格式的 license 文本
+  ///
+  /// 不是容器（前缀或魔数不符）时返回 `Ok(None)`，由调用方按旧格式处理；
+  /// 是容器但解析失败时返回错误。
+  pub fn try_decode(text: &str) -> Result<Option<Self>, LicenseError> {
+    if !Self::is_container(text) {
+      return Ok(None);
+    }
+    let bytes = decode_text(text)?;
+    if !Self::has_magic(&bytes) {
+      // 与容器前缀相同的旧格式密文
+      return Ok(None);
+    }
+    Self::from_bytes(&bytes).map(Some)
+  }
+}
+
+fn decode_text(text: &str) -> Result<Vec<u8>, LicenseError> {
+  if text.len() > MAX_LICENSE_LENGTH {
+    return Err(LicenseError::InputTooLong);
+  }
+  BASE64_STANDARD
+    .decode(text)
+    .map_err(|_| LicenseError::InvalidBase64)
+}
+
+/// `Algorithm::RsaOaepSha256` 容器中第 `index` 块（共 `count` 块）的 OAEP label
+///
+/// label 绑定容器头部、块序号、块数和前一个密文块的 SHA-256（第一块为空），
+/// 块被重排、删除或从其他容器拼接过来时解密失败。
+pub fn oaep_block_label(header: &[u8], index: usize, count: usize, previous: &[u8]) -> String {
+  let previous = if previous.is_empty() {
+    String::new()
+  } else {
+    BASE64_STANDARD.encode(Sha256::digest(previous))
+  };
+  format!(
+    "{}:{}:{}:{}",
+    BASE64_STANDARD.encode(header),
+    index,
+    count,
+    previous
+  )
+}
+
+/// 组装混合加密 payload
+pub fn hybrid_payload(wrapped_key: &[u8], nonce: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+  let mut payload = Vec::with_capacity(2 + wrapped_key.len() + nonce.len() + ciphertext.len());
+  payload.extend_from_slice(&(wrapped_key.len() as u16).to_be_bytes());
+  payload.extend_from_slice(wrapped_key);
+  payload.extend_from_slice(nonce);
+  payload.extend_from_slice(ciphertext);
+  payload
+}
+
+/// 混合加密 payload 的各部分
+#[derive(Debug, Clone, Copy)]
+pub struct HybridParts<'a> {
+  pub wrapped_key: &'a [u8],
+  pub nonce: &'a [u8],
+  pub ciphertext: &'a [u8],
+}
+
+/// 拆分混合加密 payload
+pub fn split_hybrid_payload(payload: &[u8]) -> Result<HybridParts<'_>, LicenseError> {
+  if payload.len() < 2 {
+    return Err(LicenseError::InvalidFormat("Truncated hybrid payload"));
+  }
+  let key_end = 2 + u16::from_be_bytes([payload[0], payload[1]]) as usize;
+  if payload.len() < key_end + NONCE_LENGTH {
+    return Err(LicenseError::InvalidFormat("Truncated hybrid payload"));
+  }
+  Ok(HybridParts {
+    wrapped_key: &payload[2..key_end],
+    nonce: &payload[key_end..key_end + NONCE_LENGTH],
+    ciphertext: &payload[key_end + NONCE_LENGTH..],
+  })
+}
+
+/// 组装签名 payload
+pub fn signed_payload(message: &[u8], signature: &[u8]) -> Vec<u8> {
+  let mut payload = Vec::with_capacity(4 + message.len() + signature.len());
+  payload.extend_from_slice(&(message.len() as u32).to_be_bytes());
+  payload.extend_from_slice(message);
+  payload.extend_from_slice(signature);
+  payload
+}
+
+/// 拆分签名 payload，返回 (消息, 签名)
+pub fn split_signed_payload(payload: &[u8]) -> Result<(&[u8], &[u8]), LicenseError> {
+  if payload.len() < 4 {
+    return Err(LicenseError::InvalidFormat("Truncated signed payload"));
+  }
+  let message_end =
+    4 + u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]) as usize;
+  if payload.len() < message_end {
+    return Err(LicenseError::InvalidFormat("Truncated signed payload"));
+  }
+  Ok((&payload[4..message_end], &payload[message_end..]))
+}
+
+/// 签名覆盖的数据：容器头部 + 消息
+pub fn signing_input(header: &[u8], message: &[u8]) -> Vec<u8> {
+  let mut input = Vec::with_capacity(header.len() + message.len());
+  input.extend_from_slice(header);
+  input.extend_from_slice(message);
+  input
+}
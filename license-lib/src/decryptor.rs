@@ -0,0 +1,309 @@
+use std::path::Path;
+use std::sync::OnceLock;
+
+use base64::prelude::*;
+use rsa::pkcs8::EncodePublicKey;
+use rsa::traits::PublicKeyParts;
+use rsa::{Oaep, Pkcs1v15Encrypt, RsaPrivateKey};
+use sha2::Sha256;
+
+use crate::container::{oaep_block_label, split_hybrid_payload, Algorithm, Container, MAX_BLOCKS};
+use crate::keys::PrivateKey;
+use crate::{envelope, keyring, LicenseError, LicensePayload, PRIVATE_KEY};
+
+static SHARED_DECRYPTOR: OnceLock<Result<Decryptor, LicenseError>> = OnceLock::new();
+
+/// 持有运行时提供的 RSA 私钥的解密器
+///
+/// 每个部署可以使用自己的密钥对，而不必依赖编译进来的 `PRIVATE_KEY`。
+#[derive(Clone)]
+pub struct Decryptor {
+  private_key: RsaPrivateKey,
+}
+
+impl Decryptor {
+  pub fn new(private_key: RsaPrivateKey) -> Self {
+    Decryptor { private_key }
+  }
+
+  /// 使用内置的 `PRIVATE_KEY`
+  pub fn default_key() -> Result<Self, LicenseError> {
+    Self::from_pem(PRIVATE_KEY)
+  }
+
+  /// 内置 `PRIVATE_KEY` 的共享解密器
+  ///
+  /// 首次调用时解析并缓存，之后的调用不再解析 PEM，可跨线程使用。
+  pub fn shared() -> Result<&'static Decryptor, LicenseError> {
+    SHARED_DECRYPTOR
+      .get_or_init(Self::default_key)
+      .as_ref()
+      .map_err(Clone::clone)
+  }
+
+  /// 从 PEM 字符串加载，支持 PKCS#8 (`BEGIN PRIVATE KEY`) 和 PKCS#1 (`BEGIN RSA PRIVATE KEY`)
+  ///
+  /// 解析见 `keys::PrivateKey::from_pem`。
+  pub fn from_pem(pem: &str) -> Result<Self, LicenseError> {
+    PrivateKey::from_pem(pem)?.to_decryptor()
+  }
+
+  /// 从 DER 字节加载，先尝试 PKCS#8，再尝试 PKCS#1
+  pub fn from_der(der: &[u8]) -> Result<Self, LicenseError> {
+    PrivateKey::from_der(der)?.to_decryptor()
+  }
+
+  /// 从任意字节加载，自动识别 PEM 或 DER
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, LicenseError> {
+    match std::str::from_utf8(bytes) {
+      Ok(text) if text.trim_start().starts_with("-----BEGIN") => Self::from_pem(text),
+      _ => Self::from_der(bytes),
+    }
+  }
+
+  /// 从密钥文件加载（PEM 或 DER）
+  pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LicenseError> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)
+      .map_err(|e| LicenseError::KeyLoad(format!("{}: {}", path.display(), e)))?;
+    Self::from_bytes(&bytes)
+  }
+
+  /// 从环境变量加载，变量内容为 PEM 字符串
+  pub fn from_env(name: &str) -> Result<Self, LicenseError> {
+    let pem = std::env::var(name).map_err(|e| LicenseError::KeyLoad(format!("{}: {}", name, e)))?;
+    Self::from_pem(&pem)
+  }
+
+  pub fn private_key(&self) -> &RsaPrivateKey {
+    &self.private_key
+  }
+
+  /// 由公钥推导的 key id，见 `keyring::key_id`
+  pub fn key_id(&self) -> Result<String, LicenseError> {
+    let der = self
+      .private_key
+      .to_public_key()
+      .to_public_key_der()
+      .map_err(|e| LicenseError::KeyLoad(e.to_string()))?;
+    Ok(keyring::key_id(der.as_bytes()))
+  }
+
+  pub fn decrypt(&self, base64_message: &str) -> Result<String, LicenseError> {
+    // Check input length and validity
+    if base64_message.is_empty() {
+      return Err(LicenseError::EmptyInput);
+    }
+
+    // Versioned container, dispatch on the algorithm in its header
+    // (a legacy single block that happens to share the prefix falls through)
+    if let Some(container) = Container::try_decode(base64_message)? {
+      return self.decrypt_container(&container);
+    }
+
+    // Legacy chunked format (starts with "CHUNK:")
+    if base64_message.starts_with("CHUNK:") {
+      return self.decrypt_chunked(base64_message);
+    }
+
+    // Legacy format - single RSA block
+    self.decrypt_single_block(base64_message)
+  }
+
+  /// RSA 密文块长度（字节），等于密钥模数长度，RSA-2048 为 256
+  pub fn block_size(&self) -> usize {
+    self.private_key.size()
+  }
+
+  /// PKCS#1 v1.5 每块最大明文长度：密钥长度(字节) - 11，RSA-2048 为 245
+  ///
+  /// 密钥太小、放不下填充时返回 `LicenseError::KeyLoad`。
+  pub fn max_pkcs1v15_message_length(&self) -> Result<usize, LicenseError> {
+    padded_message_length(self.block_size(), 11)
+      .ok_or_else(|| LicenseError::KeyLoad("RSA key too small for PKCS#1 v1.5 padding".to_string()))
+  }
+
+  /// OAEP(SHA-256) 每块最大明文长度：密钥长度(字节) - 2 * 32 - 2，RSA-2048 为 190
+  ///
+  /// 密钥太小、放不下填充时返回 `LicenseError::KeyLoad`。
+  pub fn max_oaep_message_length(&self) -> Result<usize, LicenseError> {
+    padded_message_length(self.block_size(), 66)
+      .ok_or_else(|| LicenseError::KeyLoad("RSA key too small for OAEP padding".to_string()))
+  }
+
+  // 单块 base64 文本的长度上限：一个密文块的 base64 长度再留一些余量
+  fn max_block_text_length(&self) -> usize {
+    self.block_size().div_ceil(3) * 4 + 64
+  }
+
+  /// 解密已解析的容器
+  pub fn decrypt_container(&self, container: &Container) -> Result<String, LicenseError> {
+    match container.algorithm {
+      Algorithm::RsaPkcs1v15 | Algorithm::RsaOaepSha256 => self.decrypt_blocks(
+        container.algorithm,
+        &container.header_bytes()?,
+        &container.payload,
+      ),
+      Algorithm::HybridRsaOaepAes256Gcm => {
+        let parts = split_hybrid_payload(&container.payload)?;
+        envelope::open_sealed(
+          &self.private_key,
+          parts.wrapped_key,
+          parts.nonce,
+          parts.ciphertext,
+          &container.header_bytes()?,
+        )
+      }
+      Algorithm::Ed25519 | Algorithm::RsaPkcs1v15Sha256 => Err(LicenseError::InvalidFormat(
+        "Signed license must be verified, not decrypted",
+      )),
+    }
+  }
+
+  /// 解密并解析为 LicensePayload
+  pub fn decode_license(&self, token: &str) -> Result<LicensePayload, LicenseError> {
+    let json = self.decrypt(token)?;
+    LicensePayload::from_json(&json)
+  }
+
+  fn decrypt_single_block(&self, base64_message: &str) -> Result<String, LicenseError> {
+    // Check if the base64 string is too long
+    // The ciphertext is one key-sized block (RSA-2048: 256 bytes, 344 chars in base64)
+    // We allow some extra margin for safety
+    if base64_message.len() > self.max_block_text_length() {
+      return Err(LicenseError::InputTooLong);
+    }
+
+    // Decode base64
+    let encrypted_message = match BASE64_STANDARD.decode(base64_message) {
+      Ok(data) => data,
+      Err(_) => return Err(LicenseError::InvalidBase64),
+    };
+
+    // Check if the encrypted data has the right size for the key (256 bytes for RSA-2048)
+    if encrypted_message.len() != self.block_size() {
+      return Err(LicenseError::InvalidBlockSize);
+    }
+
+    let decrypted_message = match self
+      .private_key
+      .decrypt(Pkcs1v15Encrypt, &encrypted_message)
+    {
+      Ok(data) => data,
+      Err(_) => return Err(LicenseError::InvalidPadding),
+    };
+
+    match String::from_utf8(decrypted_message) {
+      Ok(text) => {
+        // Check if the decrypted text is too long (should be <= 245 for RSA-2048)
+        if text.len() > self.max_pkcs1v15_message_length()? {
+          return Err(LicenseError::PlaintextTooLong);
+        }
+        Ok(text)
+      }
+      Err(_) => Err(LicenseError::InvalidUtf8),
+    }
+  }
+
+  fn decrypt_chunked(&self, base64_message: &str) -> Result<String, LicenseError> {
+    // Parse format: "CHUNK:count:block1|block2|..."
+    let parts: Vec<&str> = base64_message.splitn(3, ':').collect();
+    if parts.len() != 3 {
+      return Err(LicenseError::InvalidChunkHeader("Invalid chunked format"));
+    }
+
+    if parts[0] != "CHUNK" {
+      return Err(LicenseError::InvalidChunkHeader("Invalid chunked prefix"));
+    }
+
+    let chunk_count: usize = match parts[1].parse() {
+      Ok(count) => count,
+      Err(_) => return Err(LicenseError::InvalidChunkHeader("Invalid chunk count")),
+    };
+
+    if chunk_count == 0 || chunk_count > MAX_BLOCKS {
+      return Err(LicenseError::InvalidChunkHeader("Invalid chunk count"));
+    }
+
+    let blocks: Vec<&str> = parts[2].split('|').collect();
+    if blocks.len() != chunk_count {
+      return Err(LicenseError::InvalidChunkHeader("Chunk count mismatch"));
+    }
+
+    let mut decrypted_parts = Vec::new();
+    for block in blocks {
+      // Decode this block
+      let encrypted_data = match BASE64_STANDARD.decode(block) {
+        Ok(data) => data,
+        Err(_) => return Err(LicenseError::InvalidBase64),
+      };
+
+      if encrypted_data.len() != self.block_size() {
+        return Err(LicenseError::InvalidBlockSize);
+      }
+
+      // Decrypt this block
+      let decrypted_block = match self.private_key.decrypt(Pkcs1v15Encrypt, &encrypted_data) {
+        Ok(data) => data,
+        Err(_) => return Err(LicenseError::InvalidPadding),
+      };
+
+      decrypted_parts.push(decrypted_block);
+    }
+
+    // Combine all decrypted parts
+    let combined: Vec<u8> = decrypted_parts.into_iter().flatten().collect();
+
+    match String::from_utf8(combined) {
+      Ok(text) => Ok(text),
+      Err(_) => Err(LicenseError::InvalidUtf8),
+    }
+  }
+
+  // 容器内的 RSA 密文块（OAEP 或旧的 PKCS#1 v1.5），每块长度等于密钥长度
+  // OAEP 块的 label 绑定容器头部、块序号和前一块，见 `oaep_block_label`
+  fn decrypt_blocks(
+    &self,
+    algorithm: Algorithm,
+    header: &[u8],
+    payload: &[u8],
+  ) -> Result<String, LicenseError> {
+    let block_size = self.block_size();
+    if payload.is_empty() || !payload.len().is_multiple_of(block_size) {
+      return Err(LicenseError::InvalidBlockSize);
+    }
+
+    let count = payload.len() / block_size;
+    if count > MAX_BLOCKS {
+      return Err(LicenseError::InvalidFormat("Too many blocks"));
+    }
+    let mut combined = Vec::new();
+    let mut previous: &[u8] = &[];
+    for (index, block) in payload.chunks(block_size).enumerate() {
+      let decrypted = match algorithm {
+        Algorithm::RsaOaepSha256 => {
+          let label = oaep_block_label(header, index, count, previous);
+          self
+            .private_key
+            .decrypt(Oaep::new_with_label::<Sha256, _>(label), block)
+        }
+        _ => self.private_key.decrypt(Pkcs1v15Encrypt, block),
+      };
+      previous = block;
+      match decrypted {
+        Ok(data) => combined.extend_from_slice(&data),
+        Err(_) => return Err(LicenseError::InvalidPadding),
+      }
+    }
+
+    match String::from_utf8(combined) {
+      Ok(text) => Ok(text),
+      Err(_) => Err(LicenseError::InvalidUtf8),
+    }
+  }
+}
+
+/// 每块最大明文长度：块长度减去填充长度，至少为 1 字节
+pub(crate) fn padded_message_length(block_size: usize, padding: usize) -> Option<usize> {
+  block_size.checked_sub(padding).filter(|length| *length > 0)
+}
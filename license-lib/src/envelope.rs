@@ -0,0 +1,46 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use rsa::traits::PublicKeyParts;
+use rsa::{Oaep, RsaPrivateKey};
+use sha2::Sha256;
+
+use crate::container::NONCE_LENGTH;
+use crate::LicenseError;
+
+/// 解开 RSA-OAEP 包裹的 AES 密钥并解密 AES-256-GCM 密文
+pub(crate) fn open_sealed(
+  private_key: &RsaPrivateKey,
+  wrapped_key: &[u8],
+  nonce: &[u8],
+  ciphertext: &[u8],
+  aad: &[u8],
+) -> Result<String, LicenseError> {
+  if wrapped_key.len() != private_key.size() || nonce.len() != NONCE_LENGTH {
+    return Err(LicenseError::InvalidBlockSize);
+  }
+
+  let content_key = match private_key.decrypt(Oaep::new::<Sha256>(), wrapped_key) {
+    Ok(key) => key,
+    Err(_) => return Err(LicenseError::InvalidPadding),
+  };
+  let cipher = match Aes256Gcm::new_from_slice(&content_key) {
+    Ok(cipher) => cipher,
+    Err(_) => return Err(LicenseError::DecryptionFailed),
+  };
+
+  let plaintext = match cipher.decrypt(
+    Nonce::from_slice(nonce),
+    Payload {
+      msg: ciphertext,
+      aad,
+    },
+  ) {
+    Ok(data) => data,
+    Err(_) => return Err(LicenseError::DecryptionFailed),
+  };
+
+  match String::from_utf8(plaintext) {
+    Ok(text) => Ok(text),
+    Err(_) => Err(LicenseError::InvalidUtf8),
+  }
+}
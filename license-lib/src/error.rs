@@ -0,0 +1,141 @@
+use std::fmt;
+
+/// license 解析、解密过程中可能出现的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LicenseError {
+  /// 输入为空
+  EmptyInput,
+  /// 输入超过允许的最大长度
+  InputTooLong,
+  /// base64 解码失败
+  InvalidBase64,
+  /// `CHUNK:count:...` 头部格式错误，附带具体原因
+  InvalidChunkHeader(&'static str),
+  /// 密文块大小与密钥长度不符
+  InvalidBlockSize,
+  /// 认证解密失败：AES-256-GCM 密文、nonce 或头部被改动
+  DecryptionFailed,
+  /// RSA 解密时填充校验失败，通常是密钥不匹配或密文被改动
+  InvalidPadding,
+  /// 解密结果超过单块明文的长度上限
+  PlaintextTooLong,
+  /// 解密结果不是合法的 UTF-8
+  InvalidUtf8,
+  /// 密钥加载或解析失败
+  KeyLoad(String),
+  /// license 格式错误，附带具体原因
+  InvalidFormat(&'static str),
+  /// 签名校验失败
+  InvalidSignature,
+  /// 不支持的算法
+  UnsupportedAlgorithm(String),
+  /// license 内容不是合法的 LicensePayload JSON
+  InvalidPayload(String),
+  /// license 已过期，附带过期时间
+  Expired(i64),
+  /// license 尚未生效，附带生效时间
+  NotYetValid(i64),
+  /// 不支持的格式版本
+  UnsupportedVersion(u8),
+  /// 钥匙串中没有 license 头部指定的 key id
+  UnknownKeyId(String),
+  /// license 未开通要求的功能
+  MissingFeature(String),
+  /// 签发 license 时加密失败
+  EncryptionFailed(String),
+  /// license 绑定的机器指纹与当前机器不匹配
+  FingerprintMismatch,
+  /// 激活响应与激活请求不对应，附带具体原因
+  ActivationMismatch(&'static str),
+  /// 读写本地存储失败，附带具体原因
+  Storage(String),
+  /// 本地存储校验失败：被改动过或来自其他机器
+  StoreTampered,
+  /// 系统时间早于上次看到的时间，附带上次看到的时间
+  ClockRollback(i64),
+  /// license 或其签发密钥已被吊销，附带吊销时间
+  Revoked(i64),
+  /// 校验选项无效，附带具体原因
+  InvalidOptions(&'static str),
+}
+
+impl LicenseError {
+  /// 稳定的错误码，供 napi 绑定和 worker 接口返回给调用方
+  pub fn code(&self) -> &'static str {
+    match self {
+      LicenseError::EmptyInput => "LICENSE_EMPTY_INPUT",
+      LicenseError::InputTooLong => "LICENSE_INPUT_TOO_LONG",
+      LicenseError::InvalidBase64 => "LICENSE_BAD_BASE64",
+      LicenseError::InvalidChunkHeader(_) => "LICENSE_BAD_CHUNK_HEADER",
+      LicenseError::InvalidBlockSize => "LICENSE_BAD_BLOCK_SIZE",
+      LicenseError::DecryptionFailed => "LICENSE_DECRYPT_FAILED",
+      LicenseError::InvalidPadding => "LICENSE_BAD_PADDING",
+      LicenseError::PlaintextTooLong => "LICENSE_PLAINTEXT_TOO_LONG",
+      LicenseError::InvalidUtf8 => "LICENSE_BAD_UTF8",
+      LicenseError::KeyLoad(_) => "LICENSE_KEY_LOAD_FAILED",
+      LicenseError::InvalidFormat(_) => "LICENSE_BAD_FORMAT",
+      LicenseError::InvalidSignature => "LICENSE_BAD_SIGNATURE",
+      LicenseError::UnsupportedAlgorithm(_) => "LICENSE_UNSUPPORTED_ALGORITHM",
+      LicenseError::InvalidPayload(_) => "LICENSE_BAD_PAYLOAD",
+      LicenseError::Expired(_) => "LICENSE_EXPIRED",
+      LicenseError::NotYetValid(_) => "LICENSE_NOT_YET_VALID",
+      LicenseError::UnsupportedVersion(_) => "LICENSE_UNSUPPORTED_VERSION",
+      LicenseError::UnknownKeyId(_) => "LICENSE_UNKNOWN_KEY",
+      LicenseError::MissingFeature(_) => "LICENSE_MISSING_FEATURE",
+      LicenseError::EncryptionFailed(_) => "LICENSE_ENCRYPT_FAILED",
+      LicenseError::FingerprintMismatch => "LICENSE_FINGERPRINT_MISMATCH",
+      LicenseError::ActivationMismatch(_) => "LICENSE_ACTIVATION_MISMATCH",
+      LicenseError::Storage(_) => "LICENSE_STORAGE_FAILED",
+      LicenseError::StoreTampered => "LICENSE_STORE_TAMPERED",
+      LicenseError::ClockRollback(_) => "LICENSE_CLOCK_ROLLBACK",
+      LicenseError::Revoked(_) => "LICENSE_REVOKED",
+      LicenseError::InvalidOptions(_) => "LICENSE_BAD_OPTIONS",
+    }
+  }
+}
+
+impl fmt::Display for LicenseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      LicenseError::EmptyInput => write!(f, "Input cannot be empty"),
+      LicenseError::InputTooLong => write!(f, "Input too long"),
+      LicenseError::InvalidBase64 => write!(f, "Invalid base64 encoding"),
+      LicenseError::InvalidChunkHeader(reason) => write!(f, "{}", reason),
+      LicenseError::InvalidBlockSize => write!(f, "Invalid encrypted data size"),
+      LicenseError::DecryptionFailed => write!(f, "Decryption failed"),
+      LicenseError::InvalidPadding => write!(f, "Invalid padding in decrypted data"),
+      LicenseError::PlaintextTooLong => write!(f, "Decrypted text too long"),
+      LicenseError::InvalidUtf8 => write!(f, "Invalid UTF-8 in decrypted data"),
+      LicenseError::KeyLoad(reason) => write!(f, "Failed to load key: {}", reason),
+      LicenseError::InvalidFormat(reason) => write!(f, "{}", reason),
+      LicenseError::InvalidSignature => write!(f, "Invalid license signature"),
+      LicenseError::UnsupportedAlgorithm(name) => write!(f, "Unsupported algorithm: {}", name),
+      LicenseError::InvalidPayload(reason) => write!(f, "Invalid license payload: {}", reason),
+      LicenseError::Expired(expires_at) => write!(f, "License expired at {}", expires_at),
+      LicenseError::NotYetValid(not_before) => {
+        write!(f, "License not valid before {}", not_before)
+      }
+      LicenseError::UnsupportedVersion(version) => {
+        write!(f, "Unsupported format version: {}", version)
+      }
+      LicenseError::UnknownKeyId(key_id) => write!(f, "Unknown key id: {}", key_id),
+      LicenseError::MissingFeature(name) => write!(f, "Missing feature: {}", name),
+      LicenseError::EncryptionFailed(reason) => write!(f, "Encryption failed: {}", reason),
+      LicenseError::FingerprintMismatch => write!(f, "License is bound to another machine"),
+      LicenseError::ActivationMismatch(reason) => write!(f, "{}", reason),
+      LicenseError::Storage(reason) => write!(f, "License storage failed: {}", reason),
+      LicenseError::StoreTampered => write!(f, "Stored license failed integrity check"),
+      LicenseError::ClockRollback(last_seen) => {
+        write!(
+          f,
+          "System clock is earlier than last seen time {}",
+          last_seen
+        )
+      }
+      LicenseError::Revoked(revoked_at) => write!(f, "License revoked at {}", revoked_at),
+      LicenseError::InvalidOptions(reason) => write!(f, "{}", reason),
+    }
+  }
+}
+
+impl std::error::Error for LicenseError {}
@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::hex;
+
+/// 机器指纹的一个组成部分，例如 machine-id、主机名
+///
+/// 实现这个 trait 可以加入自定义来源（如序列号、云实例 id）。
+pub trait FingerprintSource: Send + Sync {
+  /// 组件名，作为指纹中的键
+  fn name(&self) -> &str;
+  /// 原始值，取不到时返回 `None`，该组件不计入指纹
+  fn collect(&self) -> Option<String>;
+}
+
+/// `/etc/machine-id`（systemd）或 `/var/lib/dbus/machine-id`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MachineId;
+
+impl FingerprintSource for MachineId {
+  fn name(&self) -> &str {
+    "machine_id"
+  }
+
+  fn collect(&self) -> Option<String> {
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+      .iter()
+      .find_map(read_trimmed)
+  }
+}
+
+/// 主机名
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Hostname;
+
+impl FingerprintSource for Hostname {
+  fn name(&self) -> &str {
+    "hostname"
+  }
+
+  fn collect(&self) -> Option<String> {
+    read_trimmed("/proc/sys/kernel/hostname")
+      .or_else(|| read_trimmed("/etc/hostname"))
+      .or_else(|| std::env::var("HOSTNAME").ok())
+      .or_else(|| std::env::var("COMPUTERNAME").ok())
+  }
+}
+
+/// 物理网卡的 MAC 地址，排序后合并为一个组件
+///
+/// 只取 `/sys/class/net/<if>/device` 存在的网卡，docker、veth 等虚拟网卡不计入。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MacAddresses;
+
+impl FingerprintSource for MacAddresses {
+  fn name(&self) -> &str {
+    "mac"
+  }
+
+  fn collect(&self) -> Option<String> {
+    let mut addresses: Vec<String> = fs::read_dir("/sys/class/net")
+      .ok()?
+      .filter_map(|entry| entry.ok())
+      .filter(|entry| entry.path().join("device").exists())
+      .filter_map(|entry| read_trimmed(entry.path().join("address")))
+      .filter(|address| address != "00:00:00:00:00:00")
+      .collect();
+    if addresses.is_empty() {
+      return None;
+    }
+    addresses.sort();
+    addresses.dedup();
+    Some(addresses.join(","))
+  }
+}
+
+/// CPU 厂商、型号和逻辑核数（`/proc/cpuinfo`）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuInfo;
+
+impl FingerprintSource for CpuInfo {
+  fn name(&self) -> &str {
+    "cpu"
+  }
+
+  fn collect(&self) -> Option<String> {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok()?;
+    let field = |name: &str| {
+      cpuinfo.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        (key.trim() == name).then(|| value.trim().to_string())
+      })
+    };
+    let count = cpuinfo
+      .lines()
+      .filter(|line| line.starts_with("processor"))
+      .count();
+    let vendor = field("vendor_id").or_else(|| field("CPU implementer"))?;
+    let model = field("model name")
+      .or_else(|| field("CPU part"))
+      .unwrap_or_default();
+    Some(format!("{}|{}|{}", vendor, model, count))
+  }
+}
+
+/// 固定值来源，用于测试或由调用方自行获取的标识
+#[derive(Debug, Clone)]
+pub struct StaticSource {
+  pub name: String,
+  pub value: String,
+}
+
+impl StaticSource {
+  pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+    StaticSource {
+      name: name.into(),
+      value: value.into(),
+    }
+  }
+}
+
+impl FingerprintSource for StaticSource {
+  fn name(&self) -> &str {
+    &self.name
+  }
+
+  fn collect(&self) -> Option<String> {
+    Some(self.value.clone())
+  }
+}
+
+/// 默认来源：machine-id、主机名、MAC 地址、CPU 信息
+pub fn default_sources() -> Vec<Box<dyn FingerprintSource>> {
+  vec![
+    Box::new(MachineId),
+    Box::new(Hostname),
+    Box::new(MacAddresses),
+    Box::new(CpuInfo),
+  ]
+}
+
+/// 机器指纹：组件名到组件哈希的映射
+///
+/// 只保存哈希，license 中不会出现原始的 MAC 地址或主机名。
+/// 序列化为 JSON 对象，如 `{"hostname": "9f2c...", "mac": "51ab..."}`。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Fingerprint {
+  components: BTreeMap<String, String>,
+}
+
+impl Fingerprint {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// 使用默认来源计算当前机器的指纹
+  pub fn current() -> Self {
+    Self::collect(&default_sources())
+  }
+
+  /// 使用指定来源计算指纹，取不到值的来源会被跳过
+  pub fn collect(sources: &[Box<dyn FingerprintSource>]) -> Self {
+    let mut fingerprint = Self::new();
+    for source in sources {
+      if let Some(value) = source.collect() {
+        fingerprint.insert(source.name(), &value);
+      }
+    }
+    fingerprint
+  }
+
+  /// 加入一个组件，原始值规范化（去空白、转小写）后取哈希
+  pub fn insert(&mut self, name: &str, value: &str) {
+    let value = value.trim().to_lowercase();
+    if value.is_empty() {
+      return;
+    }
+    self
+      .components
+      .insert(name.to_string(), component_hash(name, &value));
+  }
+
+  /// 从已经哈希过的组件构造，例如 `components()` 的结果或 JS 传入的指纹
+  pub fn from_components(components: impl IntoIterator<Item = (String, String)>) -> Self {
+    Fingerprint {
+      components: components.into_iter().collect(),
+    }
+  }
+
+  pub fn with_component(mut self, name: &str, value: &str) -> Self {
+    self.insert(name, value);
+    self
+  }
+
+  /// 组件名到组件哈希
+  pub fn components(&self) -> &BTreeMap<String, String> {
+    &self.components
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.components.is_empty()
+  }
+
+  /// 整个指纹的稳定标识：按组件名排序后的 SHA-256 前 16 字节的十六进制
+  pub fn id(&self) -> String {
+    let mut hasher = Sha256::new();
+    for (name, hash) in &self.components {
+      hasher.update(name.as_bytes());
+      hasher.update(b"=");
+      hasher.update(hash.as_bytes());
+      hasher.update(b"\n");
+    }
+    hex::encode(&hasher.finalize()[..16])
+  }
+
+  /// 本指纹中在 `current` 里缺失或取值不同的组件数
+  pub fn changed_components(&self, current: &Fingerprint) -> usize {
+    self
+      .components
+      .iter()
+      .filter(|(name, hash)| current.components.get(*name) != Some(*hash))
+      .count()
+  }
+
+  /// 模糊匹配：变化的组件数不超过 `tolerance`，且至少有一个组件相同
+  pub fn matches(&self, current: &Fingerprint, tolerance: usize) -> bool {
+    let changed = self.changed_components(current);
+    changed <= tolerance && changed < self.components.len()
+  }
+}
+
+// 组件名参与哈希，不同组件的相同取值不会得到相同的哈希
+fn component_hash(name: &str, value: &str) -> String {
+  let mut hasher = Sha256::new();
+  hasher.update(b"bika-license-fingerprint\0");
+  hasher.update(name.as_bytes());
+  hasher.update(b"\0");
+  hasher.update(value.as_bytes());
+  hex::encode(&hasher.finalize()[..16])
+}
+
+fn read_trimmed(path: impl AsRef<Path>) -> Option<String> {
+  let text = fs::read_to_string(path).ok()?;
+  let text = text.trim();
+  (!text.is_empty()).then(|| text.to_string())
+}
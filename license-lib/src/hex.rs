@@ -0,0 +1,16 @@
+//! 小写十六进制编码，key id、指纹组件、store MAC 和 API key 哈希共用
+
+pub fn encode(bytes: &[u8]) -> String {
+  bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// 解码十六进制文本，长度为奇数或含非十六进制字符时返回 `None`
+pub fn decode(text: &str) -> Option<Vec<u8>> {
+  if !text.len().is_multiple_of(2) {
+    return None;
+  }
+  (0..text.len())
+    .step_by(2)
+    .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+    .collect()
+}
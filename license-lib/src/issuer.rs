@@ -0,0 +1,210 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use rsa::pkcs8::EncodePublicKey;
+use rsa::rand_core::{OsRng, RngCore};
+use rsa::signature::{SignatureEncoding, Signer};
+use rsa::traits::PublicKeyParts;
+use rsa::{Oaep, RsaPublicKey};
+use sha2::Sha256;
+
+use crate::container::{
+  hybrid_payload, oaep_block_label, signed_payload, signing_input, Algorithm, Container,
+  MAX_BLOCKS, MAX_LICENSE_LENGTH, NONCE_LENGTH,
+};
+use crate::decryptor::padded_message_length;
+use crate::keys::{PrivateKey, PublicKey};
+use crate::{keyring, LicenseError, LicensePayload, VerifyingKey};
+
+/// 签发加密 license 的加密器，持有解析后的 RSA 公钥及其 key id
+///
+/// 输出的容器头部带 key id，客户端 `Keyring` 据此选择私钥。
+#[derive(Clone)]
+pub struct Encryptor {
+  public_key: RsaPublicKey,
+  key_id: String,
+}
+
+impl Encryptor {
+  pub fn new(public_key: RsaPublicKey) -> Result<Self, LicenseError> {
+    let der = public_key
+      .to_public_key_der()
+      .map_err(|e| LicenseError::KeyLoad(e.to_string()))?;
+    Ok(Encryptor {
+      key_id: keyring::key_id(der.as_bytes()),
+      public_key,
+    })
+  }
+
+  /// 从 PEM 加载，支持 SPKI (`BEGIN PUBLIC KEY`) 和 PKCS#1 (`BEGIN RSA PUBLIC KEY`)
+  ///
+  /// 解析见 `keys::PublicKey::from_pem`。
+  pub fn from_pem(pem: &str) -> Result<Self, LicenseError> {
+    PublicKey::from_pem(pem)?.to_encryptor()
+  }
+
+  /// 使用指定的 key id 代替由公钥推导的 key id，对应 `Keyring::add_decryptor_with_id`
+  pub fn with_key_id(mut self, key_id: impl Into<String>) -> Self {
+    self.key_id = key_id.into();
+    self
+  }
+
+  pub fn public_key(&self) -> &RsaPublicKey {
+    &self.public_key
+  }
+
+  pub fn key_id(&self) -> &str {
+    &self.key_id
+  }
+
+  /// OAEP(SHA-256) 每块最大明文长度：密钥长度(字节) - 2 * 32 - 2
+  ///
+  /// 密钥太小、放不下填充时返回 `LicenseError::EncryptionFailed`。
+  pub fn max_oaep_message_length(&self) -> Result<usize, LicenseError> {
+    padded_message_length(self.public_key.size(), 66).ok_or_else(|| {
+      LicenseError::EncryptionFailed("RSA key too small for OAEP padding".to_string())
+    })
+  }
+
+  /// 混合加密：随机 AES-256-GCM 密钥加密内容，RSA-OAEP(SHA-256) 加密 AES 密钥
+  ///
+  /// 输出 `Algorithm::HybridRsaOaepAes256Gcm` 容器，容器头部作为 AAD。
+  pub fn encrypt(&self, message: &str) -> Result<String, LicenseError> {
+    let mut content_key = [0u8; 32];
+    OsRng.fill_bytes(&mut content_key);
+    let mut nonce = [0u8; NONCE_LENGTH];
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut container = Container::new(
+      Algorithm::HybridRsaOaepAes256Gcm,
+      self.key_id.as_str(),
+      Vec::new(),
+    );
+    let aad = container.header_bytes()?;
+    let cipher = Aes256Gcm::new_from_slice(&content_key)
+      .map_err(|e| LicenseError::EncryptionFailed(e.to_string()))?;
+    let ciphertext = cipher
+      .encrypt(
+        Nonce::from_slice(&nonce),
+        Payload {
+          msg: message.as_bytes(),
+          aad: &aad,
+        },
+      )
+      .map_err(|e| LicenseError::EncryptionFailed(e.to_string()))?;
+
+    let wrapped_key = self
+      .public_key
+      .encrypt(&mut OsRng, Oaep::new::<Sha256>(), &content_key)
+      .map_err(|e| LicenseError::EncryptionFailed(e.to_string()))?;
+
+    container.payload = hybrid_payload(&wrapped_key, &nonce, &ciphertext);
+    encode_limited(&container)
+  }
+
+  /// RSA-OAEP(SHA-256) 直接加密，按块拼接，输出 `Algorithm::RsaOaepSha256` 容器
+  ///
+  /// 不依赖 AES，适合较短的 license。每块的 OAEP label 绑定头部、块序号和前一块，见 `oaep_block_label`。
+  pub fn encrypt_rsa_oaep(&self, message: &str) -> Result<String, LicenseError> {
+    let mut chunks: Vec<&[u8]> = message
+      .as_bytes()
+      .chunks(self.max_oaep_message_length()?)
+      .collect();
+    if chunks.is_empty() {
+      // 空消息也输出一个密文块
+      chunks.push(&[]);
+    }
+    if chunks.len() > MAX_BLOCKS {
+      return Err(LicenseError::EncryptionFailed(format!(
+        "Message needs more than {} RSA-OAEP blocks, use hybrid encryption",
+        MAX_BLOCKS
+      )));
+    }
+
+    let mut container = Container::new(
+      Algorithm::RsaOaepSha256,
+      self.key_id.as_str(),
+      Vec::with_capacity(chunks.len() * self.public_key.size()),
+    );
+    let header = container.header_bytes()?;
+    let count = chunks.len();
+    let mut previous = Vec::new();
+    for (index, chunk) in chunks.into_iter().enumerate() {
+      let label = oaep_block_label(&header, index, count, &previous);
+      previous = self
+        .public_key
+        .encrypt(&mut OsRng, Oaep::new_with_label::<Sha256, _>(label), chunk)
+        .map_err(|e| LicenseError::EncryptionFailed(e.to_string()))?;
+      container.payload.extend_from_slice(&previous);
+    }
+
+    encode_limited(&container)
+  }
+
+  /// 序列化并混合加密 license 内容
+  pub fn encrypt_payload(&self, payload: &LicensePayload) -> Result<String, LicenseError> {
+    self.encrypt(&payload.to_json()?)
+  }
+}
+
+/// 签发签名 license 的私钥，对应 `VerifyingKey`
+#[derive(Clone)]
+pub enum SigningKey {
+  Ed25519(ed25519_dalek::SigningKey),
+  Rsa(rsa::pkcs1v15::SigningKey<Sha256>),
+}
+
+impl SigningKey {
+  /// 从 PKCS#8 PEM (`BEGIN PRIVATE KEY`) 加载，自动识别 Ed25519 或 RSA
+  ///
+  /// 解析见 `keys::PrivateKey::from_pem`。
+  pub fn from_pkcs8_pem(pem: &str) -> Result<Self, LicenseError> {
+    Ok(PrivateKey::from_pem(pem)?.to_signing_key())
+  }
+
+  pub fn algorithm(&self) -> Algorithm {
+    match self {
+      SigningKey::Ed25519(_) => Algorithm::Ed25519,
+      SigningKey::Rsa(_) => Algorithm::RsaPkcs1v15Sha256,
+    }
+  }
+
+  /// 对应的校验公钥
+  pub fn verifying_key(&self) -> VerifyingKey {
+    match self {
+      SigningKey::Ed25519(key) => VerifyingKey::Ed25519(key.verifying_key()),
+      SigningKey::Rsa(key) => VerifyingKey::Rsa(rsa::pkcs1v15::VerifyingKey::new(
+        key.as_ref().to_public_key(),
+      )),
+    }
+  }
+
+  /// 由公钥推导的 key id，与 `VerifyingKey::key_id` 一致
+  pub fn key_id(&self) -> Result<String, LicenseError> {
+    self.verifying_key().key_id()
+  }
+
+  /// 签名 `message`，输出签名算法的容器，签名覆盖容器头部和消息
+  pub fn sign(&self, message: &str) -> Result<String, LicenseError> {
+    let mut container = Container::new(self.algorithm(), self.key_id()?, Vec::new());
+    let input = signing_input(&container.header_bytes()?, message.as_bytes());
+    let signature = match self {
+      SigningKey::Ed25519(key) => key.sign(&input).to_bytes().to_vec(),
+      SigningKey::Rsa(key) => key.sign(&input).to_vec(),
+    };
+    container.payload = signed_payload(message.as_bytes(), &signature);
+    encode_limited(&container)
+  }
+
+  /// 序列化并签名 license 内容
+  pub fn sign_payload(&self, payload: &LicensePayload) -> Result<String, LicenseError> {
+    self.sign(&payload.to_json()?)
+  }
+}
+
+fn encode_limited(container: &Container) -> Result<String, LicenseError> {
+  let text = container.encode()?;
+  if text.len() > MAX_LICENSE_LENGTH {
+    return Err(LicenseError::InputTooLong);
+  }
+  Ok(text)
+}
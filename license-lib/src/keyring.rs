@@ -0,0 +1,187 @@
+use sha2::{Digest, Sha256};
+
+use crate::container::Container;
+use crate::signature::{self, verify_container};
+use crate::{hex, Decryptor, LicenseError, LicensePayload, VerifyingKey};
+
+/// 根据公钥的 DER 编码计算 key id：SHA-256 前 8 字节的十六进制
+///
+/// 服务端和客户端各自从同一把公钥推导，无需额外配置。
+pub fn key_id(public_key_der: &[u8]) -> String {
+  hex::encode(&Sha256::digest(public_key_der)[..8])
+}
+
+/// 多密钥钥匙串，用于密钥轮换
+///
+/// 容器头部带 key id 时按 id 选择密钥；旧格式或未带 key id 的 license 依次尝试所有密钥。
+#[derive(Clone, Default)]
+pub struct Keyring {
+  decryptors: Vec<(String, Decryptor)>,
+  verifying_keys: Vec<(String, VerifyingKey)>,
+}
+
+impl Keyring {
+  pub fn new() -> Self {
+    Keyring::default()
+  }
+
+  /// 包含内置 PRIVATE_KEY 和 SIGNING_PUBLIC_KEY 的钥匙串
+  pub fn with_default_keys() -> Result<Self, LicenseError> {
+    let mut keyring = Keyring::new();
+    keyring.add_decryptor(Decryptor::shared()?.clone())?;
+    keyring.add_verifying_key(VerifyingKey::shared()?.clone())?;
+    Ok(keyring)
+  }
+
+  /// 添加解密密钥，key id 由公钥推导
+  pub fn add_decryptor(&mut self, decryptor: Decryptor) -> Result<&mut Self, LicenseError> {
+    let key_id = decryptor.key_id()?;
+    Ok(self.add_decryptor_with_id(key_id, decryptor))
+  }
+
+  /// 以指定 key id 添加解密密钥，已存在的同名密钥会被替换
+  pub fn add_decryptor_with_id(
+    &mut self,
+    key_id: impl Into<String>,
+    decryptor: Decryptor,
+  ) -> &mut Self {
+    let key_id = key_id.into();
+    self.decryptors.retain(|(id, _)| *id != key_id);
+    self.decryptors.push((key_id, decryptor));
+    self
+  }
+
+  /// 添加签名校验公钥，key id 由公钥推导
+  pub fn add_verifying_key(&mut self, key: VerifyingKey) -> Result<&mut Self, LicenseError> {
+    let key_id = key.key_id()?;
+    Ok(self.add_verifying_key_with_id(key_id, key))
+  }
+
+  /// 从 PEM 添加密钥：公钥 (`BEGIN PUBLIC KEY`) 用于校验签名，RSA 私钥用于解密
+  pub fn add_pem(&mut self, pem: &str) -> Result<&mut Self, LicenseError> {
+    if pem.trim_start().starts_with("-----BEGIN PUBLIC KEY-----") {
+      self.add_verifying_key(VerifyingKey::from_public_key_pem(pem)?)
+    } else {
+      self.add_decryptor(Decryptor::from_pem(pem)?)
+    }
+  }
+
+  /// 以指定 key id 添加签名校验公钥，已存在的同名密钥会被替换
+  pub fn add_verifying_key_with_id(
+    &mut self,
+    key_id: impl Into<String>,
+    key: VerifyingKey,
+  ) -> &mut Self {
+    let key_id = key_id.into();
+    self.verifying_keys.retain(|(id, _)| *id != key_id);
+    self.verifying_keys.push((key_id, key));
+    self
+  }
+
+  /// 移除密钥（解密密钥和校验公钥），返回是否存在
+  pub fn remove(&mut self, key_id: &str) -> bool {
+    let before = self.decryptors.len() + self.verifying_keys.len();
+    self.decryptors.retain(|(id, _)| id != key_id);
+    self.verifying_keys.retain(|(id, _)| id != key_id);
+    before != self.decryptors.len() + self.verifying_keys.len()
+  }
+
+  pub fn decryptor(&self, key_id: &str) -> Option<&Decryptor> {
+    self
+      .decryptors
+      .iter()
+      .find(|(id, _)| id == key_id)
+      .map(|(_, decryptor)| decryptor)
+  }
+
+  pub fn verifying_key(&self, key_id: &str) -> Option<&VerifyingKey> {
+    self
+      .verifying_keys
+      .iter()
+      .find(|(id, _)| id == key_id)
+      .map(|(_, key)| key)
+  }
+
+  pub fn key_ids(&self) -> Vec<&str> {
+    self
+      .decryptors
+      .iter()
+      .map(|(id, _)| id.as_str())
+      .chain(self.verifying_keys.iter().map(|(id, _)| id.as_str()))
+      .collect()
+  }
+
+  pub fn decrypt(&self, token: &str) -> Result<String, LicenseError> {
+    if token.is_empty() {
+      return Err(LicenseError::EmptyInput);
+    }
+
+    if let Some(container) = Container::try_decode(token)? {
+      if !container.key_id.is_empty() {
+        return match self.decryptor(&container.key_id) {
+          Some(decryptor) => decryptor.decrypt_container(&container),
+          None => Err(LicenseError::UnknownKeyId(container.key_id)),
+        };
+      }
+    }
+
+    // 旧格式没有 key id，依次尝试
+    let mut last_error = LicenseError::KeyLoad("Keyring has no decryption keys".to_string());
+    for (_, decryptor) in &self.decryptors {
+      match decryptor.decrypt(token) {
+        Ok(text) => return Ok(text),
+        Err(error) if is_wrong_key(&error) => last_error = error,
+        Err(error) => return Err(error),
+      }
+    }
+    Err(last_error)
+  }
+
+  pub fn verify(&self, token: &str) -> Result<LicensePayload, LicenseError> {
+    if token.is_empty() {
+      return Err(LicenseError::EmptyInput);
+    }
+
+    if let Some(container) = Container::try_decode(token)? {
+      if !container.key_id.is_empty() {
+        return match self.verifying_key(&container.key_id) {
+          Some(key) => verify_container(key, &container),
+          None => Err(LicenseError::UnknownKeyId(container.key_id)),
+        };
+      }
+    }
+
+    let mut last_error = LicenseError::KeyLoad("Keyring has no verifying keys".to_string());
+    for (_, key) in &self.verifying_keys {
+      match signature::verify_license_with(key, token) {
+        Ok(payload) => return Ok(payload),
+        Err(error) if is_wrong_key(&error) => last_error = error,
+        Err(error) => return Err(error),
+      }
+    }
+    Err(last_error)
+  }
+
+  /// 解码 license：签名 license 校验签名，加密 license 解密，均返回结构化内容
+  pub fn decode_license(&self, token: &str) -> Result<LicensePayload, LicenseError> {
+    if signature::is_signed(token) {
+      return self.verify(token);
+    }
+    LicensePayload::from_json(&self.decrypt(token)?)
+  }
+}
+
+// 这些错误说明可能用错了密钥，可以继续尝试下一把
+// 旧格式单块的长度上限取决于密钥长度，较大密钥的密文对较小的密钥来说会超长
+fn is_wrong_key(error: &LicenseError) -> bool {
+  matches!(
+    error,
+    LicenseError::DecryptionFailed
+      | LicenseError::InvalidPadding
+      | LicenseError::InputTooLong
+      | LicenseError::InvalidBlockSize
+      | LicenseError::InvalidUtf8
+      | LicenseError::InvalidSignature
+      | LicenseError::UnsupportedAlgorithm(_)
+  )
+}
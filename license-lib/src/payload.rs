@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{Fingerprint, LicenseError};
+
+/// 结构化的 license 内容，加密或签名前序列化为 JSON
+///
+/// 时间字段均为 Unix 时间戳（秒）。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LicensePayload {
+  /// 被授权方
+  pub licensee: String,
+  /// license 标识，对应服务端 LicenseCode 的 name，吊销列表按它匹配
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub license_id: Option<String>,
+  /// 签发时间
+  pub issued_at: i64,
+  /// 过期时间，`None` 表示永久有效
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub expires_at: Option<i64>,
+  /// 生效时间，`None` 表示签发即生效
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub not_before: Option<i64>,
+  /// 最大席位数，`None` 表示不限
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_seats: Option<u32>,
+  /// 版本，如 "community" / "enterprise"
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub edition: Option<String>,
+  /// 开通的功能列表
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub features: Vec<String>,
+  /// 自定义字段
+  #[serde(default, skip_serializing_if = "Map::is_empty")]
+  pub claims: Map<String, Value>,
+  /// 绑定的机器指纹，为空表示不限机器；有多个时匹配任意一个即可
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub fingerprints: Vec<Fingerprint>,
+}
+
+impl LicensePayload {
+  pub fn new(licensee: impl Into<String>, issued_at: i64) -> Self {
+    LicensePayload {
+      licensee: licensee.into(),
+      issued_at,
+      ..Default::default()
+    }
+  }
+
+  pub fn from_json(json: &str) -> Result<Self, LicenseError> {
+    serde_json::from_str(json).map_err(|e| LicenseError::InvalidPayload(e.to_string()))
+  }
+
+  pub fn to_json(&self) -> Result<String, LicenseError> {
+    serde_json::to_string(self).map_err(|e| LicenseError::InvalidPayload(e.to_string()))
+  }
+
+  pub fn has_feature(&self, name: &str) -> bool {
+    self.features.iter().any(|feature| feature == name)
+  }
+
+  /// 检查是否开通了所有要求的功能，返回第一个缺失的功能
+  pub fn require_features<S: AsRef<str>>(&self, names: &[S]) -> Result<(), LicenseError> {
+    match names.iter().find(|name| !self.has_feature(name.as_ref())) {
+      Some(name) => Err(LicenseError::MissingFeature(name.as_ref().to_string())),
+      None => Ok(()),
+    }
+  }
+
+  /// 是否绑定了机器
+  pub fn is_node_locked(&self) -> bool {
+    !self.fingerprints.is_empty()
+  }
+
+  /// 检查 license 能否在 `current` 机器上使用
+  ///
+  /// 未绑定机器时总是通过；否则任一绑定的指纹中变化的组件数不超过 `tolerance` 即可，
+  /// 见 `Fingerprint::matches`。
+  pub fn check_fingerprint(
+    &self,
+    current: &Fingerprint,
+    tolerance: usize,
+  ) -> Result<(), LicenseError> {
+    if !self.is_node_locked()
+      || self
+        .fingerprints
+        .iter()
+        .any(|fingerprint| fingerprint.matches(current, tolerance))
+    {
+      return Ok(());
+    }
+    Err(LicenseError::FingerprintMismatch)
+  }
+
+  pub fn claim(&self, name: &str) -> Option<&Value> {
+    self.claims.get(name)
+  }
+}
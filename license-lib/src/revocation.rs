@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+use crate::activation::{decode_signed_container, from_json, verify_signed_message};
+use crate::container::Container;
+use crate::{Keyring, LicenseError, LicensePayload, VerifyingKey};
+
+#[cfg(feature = "issuer")]
+use crate::activation::to_json;
+#[cfg(feature = "issuer")]
+use crate::issuer::SigningKey;
+
+/// 吊销列表消息中的 `type`
+pub const REVOCATION_LIST_TYPE: &str = "revocation_list";
+
+const NOT_SIGNED: &str = "Revocation list must be a signed container";
+const UNEXPECTED_TYPE: &str = "Unexpected revocation list type";
+
+/// 吊销列表（CRL）：由签发方签名，列出吊销的 license 和密钥
+///
+/// 文本格式同签名 license。客户端定期拉取并缓存（见 `LicenseStore::save_revocation_list`），
+/// 离线时使用缓存的列表校验。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevocationList {
+  /// 生成时间（Unix 秒），较新的列表覆盖较旧的
+  pub issued_at: i64,
+  /// 建议的下次更新时间，之后列表视为过时
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub next_update: Option<i64>,
+  /// 吊销的 license，按 `LicensePayload::license_id` 匹配
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub licenses: Vec<RevokedLicense>,
+  /// 吊销的密钥，按容器头部的 key id 匹配，用这些密钥签发的 license 全部失效
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub keys: Vec<RevokedKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevokedLicense {
+  pub license_id: String,
+  pub revoked_at: i64,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevokedKey {
+  pub key_id: String,
+  pub revoked_at: i64,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignedList {
+  #[serde(rename = "type")]
+  kind: String,
+  #[serde(flatten)]
+  list: RevocationList,
+}
+
+impl RevocationList {
+  pub fn new(issued_at: i64) -> Self {
+    RevocationList {
+      issued_at,
+      ..Default::default()
+    }
+  }
+
+  /// 签名并编码为文本
+  #[cfg(feature = "issuer")]
+  pub fn sign(&self, signing_key: &SigningKey) -> Result<String, LicenseError> {
+    signing_key.sign(&to_json(&SignedList {
+      kind: REVOCATION_LIST_TYPE.to_string(),
+      list: self.clone(),
+    })?)
+  }
+
+  /// 按容器头部的 key id 从钥匙串中选择公钥校验签名
+  pub fn verify(keyring: &Keyring, token: &str) -> Result<Self, LicenseError> {
+    let container = decode_signed_container(token, NOT_SIGNED)?;
+    let key = match keyring.verifying_key(&container.key_id) {
+      Some(key) => key,
+      None => return Err(LicenseError::UnknownKeyId(container.key_id)),
+    };
+    Self::verify_container(key, &container)
+  }
+
+  /// 使用指定公钥校验签名
+  pub fn verify_with(key: &VerifyingKey, token: &str) -> Result<Self, LicenseError> {
+    Self::verify_container(key, &decode_signed_container(token, NOT_SIGNED)?)
+  }
+
+  fn verify_container(key: &VerifyingKey, container: &Container) -> Result<Self, LicenseError> {
+    let message = verify_signed_message(key, container)?;
+    let signed: SignedList = from_json(message, REVOCATION_LIST_TYPE, UNEXPECTED_TYPE)?;
+    Ok(signed.list)
+  }
+
+  /// 列表在 `now` 是否已过时，应重新拉取
+  pub fn is_stale(&self, now: i64) -> bool {
+    self
+      .next_update
+      .is_some_and(|next_update| now >= next_update)
+  }
+
+  pub fn revoked_license(&self, license_id: &str) -> Option<&RevokedLicense> {
+    self
+      .licenses
+      .iter()
+      .find(|revoked| revoked.license_id == license_id)
+  }
+
+  pub fn revoked_key(&self, key_id: &str) -> Option<&RevokedKey> {
+    self.keys.iter().find(|revoked| revoked.key_id == key_id)
+  }
+
+  /// 检查 license 内容是否被吊销，没有 `license_id` 的 license 只能通过吊销密钥失效
+  pub fn check(&self, payload: &LicensePayload) -> Result<(), LicenseError> {
+    match payload
+      .license_id
+      .as_deref()
+      .and_then(|license_id| self.revoked_license(license_id))
+    {
+      Some(revoked) => Err(LicenseError::Revoked(revoked.revoked_at)),
+      None => Ok(()),
+    }
+  }
+
+  /// 检查 license 文本的签发密钥是否被吊销，旧格式没有 key id，不检查
+  pub fn check_key(&self, token: &str) -> Result<(), LicenseError> {
+    let token = token.trim();
+    if !Container::is_container(token) {
+      return Ok(());
+    }
+    let key_id = match Container::decode(token) {
+      Ok(container) => container.key_id,
+      Err(_) => return Ok(()),
+    };
+    match self.revoked_key(&key_id) {
+      Some(revoked) => Err(LicenseError::Revoked(revoked.revoked_at)),
+      None => Ok(()),
+    }
+  }
+
+  /// 解码 license 并检查签发密钥和 license 本身是否被吊销，见 `Keyring::decode_license`
+  pub fn decode_license(
+    &self,
+    keyring: &Keyring,
+    token: &str,
+  ) -> Result<LicensePayload, LicenseError> {
+    self.check_key(token)?;
+    let payload = keyring.decode_license(token)?;
+    self.check(&payload)?;
+    Ok(payload)
+  }
+}
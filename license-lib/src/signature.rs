@@ -0,0 +1,218 @@
+use std::sync::OnceLock;
+
+use base64::prelude::*;
+use rsa::pkcs8::EncodePublicKey;
+use rsa::signature::Verifier;
+use sha2::Sha256;
+
+use crate::container::{signing_input, split_signed_payload, Algorithm, Container};
+use crate::keys::PublicKey;
+use crate::{keyring, LicenseError, LicensePayload};
+
+// 签名公钥，放客户端，用于校验 license 签名
+// 对应的私钥只保存在服务端（worker secret: LICENSE_SIGNING_KEY）
+pub const SIGNING_PUBLIC_KEY: &str = r#"-----BEGIN PUBLIC KEY-----
+MCowBQYDK2VwAyEAlckDDJUn47TdunzLAjiFbQKMUPqeYoR1bJjW6dpAuRs=
+-----END PUBLIC KEY-----
+"#;
+
+/// 旧签名 token 的前缀，格式为 `SIG:<alg>:<base64 payload>:<base64 signature>`
+///
+/// 新 license 使用签名算法的 `Container`，旧格式仍然可以校验。
+pub const SIGNED_PREFIX: &str = "SIG:";
+
+/// license 签名算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureAlgorithm {
+  Ed25519,
+  /// RSASSA-PKCS1-v1_5 + SHA-256
+  RsaPkcs1v15Sha256,
+}
+
+impl SignatureAlgorithm {
+  /// token 中使用的算法名
+  pub fn name(&self) -> &'static str {
+    match self {
+      SignatureAlgorithm::Ed25519 => "ED25519",
+      SignatureAlgorithm::RsaPkcs1v15Sha256 => "RS256",
+    }
+  }
+
+  /// 对应的容器算法
+  pub fn container_algorithm(&self) -> Algorithm {
+    match self {
+      SignatureAlgorithm::Ed25519 => Algorithm::Ed25519,
+      SignatureAlgorithm::RsaPkcs1v15Sha256 => Algorithm::RsaPkcs1v15Sha256,
+    }
+  }
+
+  pub fn from_name(name: &str) -> Result<Self, LicenseError> {
+    match name {
+      "ED25519" => Ok(SignatureAlgorithm::Ed25519),
+      "RS256" => Ok(SignatureAlgorithm::RsaPkcs1v15Sha256),
+      _ => Err(LicenseError::UnsupportedAlgorithm(name.to_string())),
+    }
+  }
+}
+
+/// 用于校验 license 签名的公钥
+#[derive(Clone)]
+pub enum VerifyingKey {
+  Ed25519(ed25519_dalek::VerifyingKey),
+  Rsa(rsa::pkcs1v15::VerifyingKey<Sha256>),
+}
+
+static SHARED_VERIFYING_KEY: OnceLock<Result<VerifyingKey, LicenseError>> = OnceLock::new();
+
+impl VerifyingKey {
+  /// 内置 `SIGNING_PUBLIC_KEY` 的共享公钥，首次调用时解析并缓存
+  pub fn shared() -> Result<&'static VerifyingKey, LicenseError> {
+    SHARED_VERIFYING_KEY
+      .get_or_init(|| Self::from_public_key_pem(SIGNING_PUBLIC_KEY))
+      .as_ref()
+      .map_err(Clone::clone)
+  }
+
+  /// 从 SPKI PEM (`BEGIN PUBLIC KEY`) 加载，自动识别 Ed25519 或 RSA
+  ///
+  /// 解析见 `keys::PublicKey::from_pem`。
+  pub fn from_public_key_pem(pem: &str) -> Result<Self, LicenseError> {
+    Ok(PublicKey::from_pem(pem)?.to_verifying_key())
+  }
+
+  pub fn algorithm(&self) -> SignatureAlgorithm {
+    match self {
+      VerifyingKey::Ed25519(_) => SignatureAlgorithm::Ed25519,
+      VerifyingKey::Rsa(_) => SignatureAlgorithm::RsaPkcs1v15Sha256,
+    }
+  }
+
+  /// 由公钥推导的 key id，见 `keyring::key_id`
+  pub fn key_id(&self) -> Result<String, LicenseError> {
+    let der = match self {
+      VerifyingKey::Ed25519(key) => key.to_public_key_der(),
+      VerifyingKey::Rsa(key) => key.as_ref().to_public_key_der(),
+    }
+    .map_err(|e| LicenseError::KeyLoad(e.to_string()))?;
+    Ok(keyring::key_id(der.as_bytes()))
+  }
+
+  /// 校验 `message` 的签名
+  pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), LicenseError> {
+    match self {
+      VerifyingKey::Ed25519(key) => {
+        let signature = ed25519_dalek::Signature::from_slice(signature)
+          .map_err(|_| LicenseError::InvalidSignature)?;
+        key
+          .verify_strict(message, &signature)
+          .map_err(|_| LicenseError::InvalidSignature)
+      }
+      VerifyingKey::Rsa(key) => {
+        let signature = rsa::pkcs1v15::Signature::try_from(signature)
+          .map_err(|_| LicenseError::InvalidSignature)?;
+        key
+          .verify(message, &signature)
+          .map_err(|_| LicenseError::InvalidSignature)
+      }
+    }
+  }
+}
+
+/// token 是否为签名 license（签名算法的容器或旧 `SIG:` 格式）
+pub fn is_signed(token: &str) -> bool {
+  if token.starts_with(SIGNED_PREFIX) {
+    return true;
+  }
+  Container::is_container(token)
+    && Container::decode(token)
+      .map(|container| container.algorithm.is_signature())
+      .unwrap_or(false)
+}
+
+/// 使用内置的 SIGNING_PUBLIC_KEY 校验签名 license
+pub fn verify_license(token: &str) -> Result<LicensePayload, LicenseError> {
+  verify_license_with(VerifyingKey::shared()?, token)
+}
+
+/// 使用指定公钥校验签名 license
+pub fn verify_license_with(
+  key: &VerifyingKey,
+  token: &str,
+) -> Result<LicensePayload, LicenseError> {
+  if token.is_empty() {
+    return Err(LicenseError::EmptyInput);
+  }
+
+  if Container::is_container(token) {
+    return verify_container(key, &Container::decode(token)?);
+  }
+
+  verify_legacy(key, token)
+}
+
+/// 校验签名容器，签名覆盖容器头部和消息，算法和 key id 都在保护范围内
+pub fn verify_container(
+  key: &VerifyingKey,
+  container: &Container,
+) -> Result<LicensePayload, LicenseError> {
+  if !container.algorithm.is_signature() {
+    return Err(LicenseError::InvalidFormat(
+      "Encrypted license must be decrypted, not verified",
+    ));
+  }
+  if container.algorithm != key.algorithm().container_algorithm() {
+    return Err(LicenseError::UnsupportedAlgorithm(
+      container.algorithm.name().to_string(),
+    ));
+  }
+
+  let (message, signature) = split_signed_payload(&container.payload)?;
+  key.verify(
+    &signing_input(&container.header_bytes()?, message),
+    signature,
+  )?;
+
+  match std::str::from_utf8(message) {
+    Ok(json) => LicensePayload::from_json(json),
+    Err(_) => Err(LicenseError::InvalidUtf8),
+  }
+}
+
+// 旧格式：签名覆盖 `SIG:<alg>:<base64 payload>` 整段，算法名也在签名保护范围内
+fn verify_legacy(key: &VerifyingKey, token: &str) -> Result<LicensePayload, LicenseError> {
+  if !token.starts_with(SIGNED_PREFIX) {
+    return Err(LicenseError::InvalidFormat("Missing SIG: prefix"));
+  }
+
+  let (signed_part, signature_b64) = match token.rsplit_once(':') {
+    Some(parts) => parts,
+    None => return Err(LicenseError::InvalidFormat("Invalid signed format")),
+  };
+
+  // signed_part: "SIG:<alg>:<payload>"
+  let parts: Vec<&str> = signed_part.splitn(3, ':').collect();
+  if parts.len() != 3 {
+    return Err(LicenseError::InvalidFormat("Invalid signed format"));
+  }
+
+  let algorithm = SignatureAlgorithm::from_name(parts[1])?;
+  if algorithm != key.algorithm() {
+    return Err(LicenseError::UnsupportedAlgorithm(parts[1].to_string()));
+  }
+
+  let payload = match BASE64_STANDARD.decode(parts[2]) {
+    Ok(data) => data,
+    Err(_) => return Err(LicenseError::InvalidBase64),
+  };
+  let signature = match BASE64_STANDARD.decode(signature_b64) {
+    Ok(data) => data,
+    Err(_) => return Err(LicenseError::InvalidBase64),
+  };
+
+  key.verify(signed_part.as_bytes(), &signature)?;
+
+  match String::from_utf8(payload) {
+    Ok(json) => LicensePayload::from_json(&json),
+    Err(_) => Err(LicenseError::InvalidUtf8),
+  }
+}
@@ -0,0 +1,343 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+  hex, ActivationResponse, Clock, Fingerprint, Keyring, LicenseError, LicensePayload,
+  RevocationList, SystemClock,
+};
+
+/// 存储文件名
+pub const STORE_FILE_NAME: &str = "license.json";
+
+/// 当前存储格式版本
+pub const STORE_VERSION: u8 = 1;
+
+/// 默认允许变化的指纹组件数，更换主机名或网卡后存储仍然有效
+pub const DEFAULT_FINGERPRINT_TOLERANCE: usize = 1;
+
+/// 本地 license 存储：保存激活后的 license 和激活响应，启动时重新加载
+///
+/// 文件中每个指纹组件各有一个 HMAC-SHA256，密钥由该组件推导。与 license 的机器绑定一样做模糊匹配：
+/// 校验失败的组件不超过允许的数量且至少一个通过，否则视为文件被改动或复制到其他机器。
+/// 有组件变化时按当前指纹重新计算。
+/// 每次加载记录最后一次看到的时间，系统时间比它早（超过允许的偏差）时视为时钟回拨。
+pub struct LicenseStore<C: Clock = SystemClock> {
+  dir: PathBuf,
+  fingerprint: Fingerprint,
+  fingerprint_tolerance: usize,
+  clock: C,
+  clock_skew: i64,
+}
+
+/// 从存储中加载的内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredLicense {
+  /// license 文本（加密或签名）
+  pub license: String,
+  /// 离线激活响应文本
+  pub activation: Option<String>,
+  /// 缓存的吊销列表文本
+  pub revocation_list: Option<String>,
+  /// 保存时间
+  pub saved_at: i64,
+  /// 最后一次看到的时间
+  pub last_seen: i64,
+}
+
+// 文件内容，`macs` 中每个 HMAC 都覆盖其余所有字段
+#[derive(Serialize, Deserialize)]
+struct StoreFile {
+  version: u8,
+  license: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  activation: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  revocation_list: Option<String>,
+  saved_at: i64,
+  last_seen: i64,
+  // 指纹组件名到该组件推导的 HMAC
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  macs: BTreeMap<String, String>,
+}
+
+impl LicenseStore<SystemClock> {
+  /// 存放在 `dir` 下，使用当前机器的指纹和系统时间
+  pub fn new(dir: impl Into<PathBuf>) -> Self {
+    Self::with_clock(dir, SystemClock)
+  }
+}
+
+impl<C: Clock> LicenseStore<C> {
+  pub fn with_clock(dir: impl Into<PathBuf>, clock: C) -> Self {
+    LicenseStore {
+      dir: dir.into(),
+      fingerprint: Fingerprint::current(),
+      fingerprint_tolerance: DEFAULT_FINGERPRINT_TOLERANCE,
+      clock,
+      clock_skew: 0,
+    }
+  }
+
+  /// 使用指定指纹推导 HMAC 密钥，代替当前机器的指纹
+  pub fn with_fingerprint(mut self, fingerprint: Fingerprint) -> Self {
+    self.fingerprint = fingerprint;
+    self
+  }
+
+  /// 允许变化的指纹组件数，默认 `DEFAULT_FINGERPRINT_TOLERANCE`
+  pub fn with_fingerprint_tolerance(mut self, tolerance: usize) -> Self {
+    self.fingerprint_tolerance = tolerance;
+    self
+  }
+
+  /// 判断时钟回拨时允许的偏差（秒），不能为负数
+  pub fn with_clock_skew(mut self, clock_skew: i64) -> Result<Self, LicenseError> {
+    if clock_skew < 0 {
+      return Err(LicenseError::InvalidOptions(
+        "Clock skew cannot be negative",
+      ));
+    }
+    self.clock_skew = clock_skew;
+    Ok(self)
+  }
+
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  /// 存储文件路径
+  pub fn path(&self) -> PathBuf {
+    self.dir.join(STORE_FILE_NAME)
+  }
+
+  /// 保存 license 和激活响应，覆盖之前的内容
+  ///
+  /// 已有存储中的最后时间和吊销列表会保留，重新保存不能绕过时钟回拨检查。
+  pub fn save(
+    &self,
+    license: &str,
+    activation: Option<&str>,
+  ) -> Result<StoredLicense, LicenseError> {
+    let now = self.clock.now();
+    let (last_seen, revocation_list) = match self.read() {
+      Ok(Some((file, _))) => (file.last_seen.max(now), file.revocation_list),
+      _ => (now, None),
+    };
+    let file = StoreFile {
+      version: STORE_VERSION,
+      license: license.to_string(),
+      activation: activation.map(str::to_string),
+      revocation_list,
+      saved_at: now,
+      last_seen,
+      macs: BTreeMap::new(),
+    };
+    self.write(file)
+  }
+
+  /// 加载存储的内容，没有保存过时返回 `None`
+  ///
+  /// 校验 HMAC 并检查时钟回拨，通过后把最后时间更新为当前时间。
+  pub fn load(&self) -> Result<Option<StoredLicense>, LicenseError> {
+    let (file, rekey) = match self.read()? {
+      Some(read) => read,
+      None => return Ok(None),
+    };
+    let now = self.clock.now();
+    if now.saturating_add(self.clock_skew) < file.last_seen {
+      return Err(LicenseError::ClockRollback(file.last_seen));
+    }
+    if now > file.last_seen || rekey {
+      return self
+        .write(StoreFile {
+          last_seen: file.last_seen.max(now),
+          ..file
+        })
+        .map(Some);
+    }
+    Ok(Some(StoredLicense::from(file)))
+  }
+
+  /// 缓存吊销列表，需要先保存 license，返回缓存中最新的列表
+  ///
+  /// 列表先用 `keyring` 校验签名；不比已缓存的新时保留已缓存的，旧列表不能替换新列表。
+  pub fn save_revocation_list(
+    &self,
+    keyring: &Keyring,
+    token: &str,
+  ) -> Result<RevocationList, LicenseError> {
+    let list = RevocationList::verify(keyring, token)?;
+    let mut file = match self.read()? {
+      Some((file, _)) => file,
+      None => {
+        return Err(LicenseError::Storage(
+          "No license has been saved".to_string(),
+        ))
+      }
+    };
+    if let Some(cached) = file
+      .revocation_list
+      .as_deref()
+      .and_then(|cached| RevocationList::verify(keyring, cached).ok())
+    {
+      if cached.issued_at >= list.issued_at {
+        return Ok(cached);
+      }
+    }
+    file.revocation_list = Some(token.trim().to_string());
+    self.write(file)?;
+    Ok(list)
+  }
+
+  /// 删除存储的内容
+  pub fn clear(&self) -> Result<(), LicenseError> {
+    match fs::remove_file(self.path()) {
+      Ok(()) => Ok(()),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+      Err(e) => Err(storage_error(&self.path(), e)),
+    }
+  }
+
+  // 读取并校验文件，同时返回是否需要按当前指纹重新计算 HMAC
+  fn read(&self) -> Result<Option<(StoreFile, bool)>, LicenseError> {
+    let path = self.path();
+    let text = match fs::read_to_string(&path) {
+      Ok(text) => text,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+      Err(e) => return Err(storage_error(&path, e)),
+    };
+    let mut file: StoreFile =
+      serde_json::from_str(&text).map_err(|_| LicenseError::StoreTampered)?;
+    if file.version != STORE_VERSION {
+      return Err(LicenseError::UnsupportedVersion(file.version));
+    }
+
+    // 与 `Fingerprint::matches` 相同的规则
+    let macs = std::mem::take(&mut file.macs);
+    let message = message(&file)?;
+    let current = self.mac_keys();
+    let changed = macs
+      .iter()
+      .filter(|(name, mac)| {
+        let (Some(key), Some(expected)) = (current.get(name.as_str()), hex::decode(mac)) else {
+          return true;
+        };
+        !mac_with(key, &message).is_ok_and(|mac| mac.verify_slice(&expected).is_ok())
+      })
+      .count();
+    if changed > self.fingerprint_tolerance || changed >= macs.len() {
+      return Err(LicenseError::StoreTampered);
+    }
+    let rekey = changed > 0 || !macs.keys().map(String::as_str).eq(current.keys().copied());
+    Ok(Some((file, rekey)))
+  }
+
+  // 先写临时文件再改名，写到一半中断不会留下损坏的存储
+  fn write(&self, mut file: StoreFile) -> Result<StoredLicense, LicenseError> {
+    let message = message(&file)?;
+    file.macs = self
+      .mac_keys()
+      .into_iter()
+      .map(|(name, key)| {
+        let mac = mac_with(&key, &message)?.finalize().into_bytes();
+        Ok((name.to_string(), hex::encode(&mac)))
+      })
+      .collect::<Result<_, LicenseError>>()?;
+    let text =
+      serde_json::to_string_pretty(&file).map_err(|e| LicenseError::Storage(e.to_string()))?;
+
+    fs::create_dir_all(&self.dir).map_err(|e| storage_error(&self.dir, e))?;
+    let path = self.path();
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, text).map_err(|e| storage_error(&temp_path, e))?;
+    fs::rename(&temp_path, &path).map_err(|e| storage_error(&path, e))?;
+    Ok(StoredLicense::from(file))
+  }
+
+  // 每个指纹组件推导一个 HMAC 密钥；取不到任何组件时退化为一个空组件
+  fn mac_keys(&self) -> BTreeMap<&str, [u8; 32]> {
+    let components = self.fingerprint.components();
+    let components: Vec<(&str, &str)> = if components.is_empty() {
+      vec![("", "")]
+    } else {
+      components
+        .iter()
+        .map(|(name, hash)| (name.as_str(), hash.as_str()))
+        .collect()
+    };
+    components
+      .into_iter()
+      .map(|(name, hash)| {
+        let mut key = Sha256::new();
+        key.update(b"bika-license-store\0");
+        key.update(name.as_bytes());
+        key.update(b"\0");
+        key.update(hash.as_bytes());
+        (name, key.finalize().into())
+      })
+      .collect()
+  }
+}
+
+// HMAC 覆盖的内容，`macs` 为空时序列化
+fn message(file: &StoreFile) -> Result<Vec<u8>, LicenseError> {
+  serde_json::to_vec(file).map_err(|e| LicenseError::Storage(e.to_string()))
+}
+
+fn mac_with(key: &[u8; 32], message: &[u8]) -> Result<Hmac<Sha256>, LicenseError> {
+  let mut mac =
+    Hmac::<Sha256>::new_from_slice(key).map_err(|e| LicenseError::Storage(e.to_string()))?;
+  mac.update(message);
+  Ok(mac)
+}
+
+impl From<StoreFile> for StoredLicense {
+  fn from(file: StoreFile) -> Self {
+    StoredLicense {
+      license: file.license,
+      activation: file.activation,
+      revocation_list: file.revocation_list,
+      saved_at: file.saved_at,
+      last_seen: file.last_seen,
+    }
+  }
+}
+
+impl StoredLicense {
+  /// 解码存储的 license，见 `Keyring::decode_license`
+  pub fn decode(&self, keyring: &Keyring) -> Result<LicensePayload, LicenseError> {
+    keyring.decode_license(&self.license)
+  }
+
+  /// 校验存储的激活响应，见 `ActivationResponse::verify`
+  pub fn verify_activation(
+    &self,
+    keyring: &Keyring,
+  ) -> Result<Option<ActivationResponse>, LicenseError> {
+    match &self.activation {
+      Some(token) => ActivationResponse::verify(keyring, token).map(Some),
+      None => Ok(None),
+    }
+  }
+
+  /// 校验缓存的吊销列表，见 `RevocationList::verify`
+  pub fn verify_revocation_list(
+    &self,
+    keyring: &Keyring,
+  ) -> Result<Option<RevocationList>, LicenseError> {
+    match &self.revocation_list {
+      Some(token) => RevocationList::verify(keyring, token).map(Some),
+      None => Ok(None),
+    }
+  }
+}
+
+fn storage_error(path: &Path, error: io::Error) -> LicenseError {
+  LicenseError::Storage(format!("{}: {}", path.display(), error))
+}
@@ -0,0 +1,129 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{LicenseError, LicensePayload};
+
+/// 时间来源，测试时可以注入固定时间
+pub trait Clock {
+  /// 当前 Unix 时间戳（秒）
+  fn now(&self) -> i64;
+}
+
+/// 系统时间
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+  fn now(&self) -> i64 {
+    SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|duration| duration.as_secs() as i64)
+      .unwrap_or(0)
+  }
+}
+
+/// 固定时间，用于离线测试
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub i64);
+
+impl Clock for FixedClock {
+  fn now(&self) -> i64 {
+    self.0
+  }
+}
+
+/// 有效期校验选项，单位均为秒
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValidationOptions {
+  /// 过期后仍然允许使用的宽限期
+  pub grace_period: i64,
+  /// 容忍本地时钟与签发方时钟的偏差
+  pub clock_skew: i64,
+}
+
+/// 校验通过时的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Validity {
+  Valid,
+  /// 已过期但仍在宽限期内，`expires_at` 为原过期时间
+  InGracePeriod {
+    expires_at: i64,
+  },
+}
+
+impl ValidationOptions {
+  /// 宽限期和时钟偏差不能为负数
+  pub fn new(grace_period: i64, clock_skew: i64) -> Result<Self, LicenseError> {
+    if grace_period < 0 {
+      return Err(LicenseError::InvalidOptions(
+        "Grace period cannot be negative",
+      ));
+    }
+    if clock_skew < 0 {
+      return Err(LicenseError::InvalidOptions(
+        "Clock skew cannot be negative",
+      ));
+    }
+    Ok(ValidationOptions {
+      grace_period,
+      clock_skew,
+    })
+  }
+
+  /// 校验 license 在 `now` 时刻是否处于有效期内
+  ///
+  /// 未设置 `not_before` 时以 `issued_at` 作为生效时间。
+  /// 时间运算饱和，`expires_at` 可以用 `i64::MAX` 表示永不过期；直接构造的负数选项按 0 处理。
+  pub fn validate(&self, payload: &LicensePayload, now: i64) -> Result<Validity, LicenseError> {
+    let grace_period = self.grace_period.max(0);
+    let clock_skew = self.clock_skew.max(0);
+
+    let not_before = payload.not_before.unwrap_or(payload.issued_at);
+    if now.saturating_add(clock_skew) < not_before {
+      return Err(LicenseError::NotYetValid(not_before));
+    }
+
+    let now = now.saturating_sub(clock_skew);
+    match payload.expires_at {
+      Some(expires_at) if now >= expires_at.saturating_add(grace_period) => {
+        Err(LicenseError::Expired(expires_at))
+      }
+      Some(expires_at) if now >= expires_at => Ok(Validity::InGracePeriod { expires_at }),
+      _ => Ok(Validity::Valid),
+    }
+  }
+}
+
+/// 使用默认选项（无宽限期、无时钟偏差）校验有效期
+pub fn validate(payload: &LicensePayload, now: i64) -> Result<Validity, LicenseError> {
+  ValidationOptions::default().validate(payload, now)
+}
+
+/// 绑定了时间来源的有效期校验器
+#[derive(Debug, Clone, Default)]
+pub struct Validator<C: Clock = SystemClock> {
+  options: ValidationOptions,
+  clock: C,
+}
+
+impl Validator<SystemClock> {
+  pub fn new(options: ValidationOptions) -> Self {
+    Validator {
+      options,
+      clock: SystemClock,
+    }
+  }
+}
+
+impl<C: Clock> Validator<C> {
+  pub fn with_clock(options: ValidationOptions, clock: C) -> Self {
+    Validator { options, clock }
+  }
+
+  pub fn options(&self) -> &ValidationOptions {
+    &self.options
+  }
+
+  pub fn validate(&self, payload: &LicensePayload) -> Result<Validity, LicenseError> {
+    self.options.validate(payload, self.clock.now())
+  }
+}
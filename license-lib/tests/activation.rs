@@ -0,0 +1,171 @@
+use lib::activation::*;
+use lib::fingerprint::{FingerprintSource, StaticSource};
+use lib::*;
+
+const NOW: i64 = 1_754_611_200;
+
+fn machine(hostname: &str) -> Fingerprint {
+  Fingerprint::collect(&[
+    Box::new(StaticSource::new("machine_id", "4c4c4544003a")) as Box<dyn FingerprintSource>,
+    Box::new(StaticSource::new("hostname", hostname)),
+    Box::new(StaticSource::new("mac", "00:1a:2b:3c:4d:5e")),
+  ])
+}
+
+#[test]
+fn test_request_round_trip() {
+  let request = ActivationRequest::new("acme-2025", "CODE-1234", machine("isolated-01"), NOW);
+  assert_eq!(request.nonce, request.nonce.trim());
+  assert_ne!(
+    request.nonce,
+    ActivationRequest::new("acme-2025", "CODE-1234", machine("isolated-01"), NOW).nonce
+  );
+
+  let token = request.encode().unwrap();
+  assert!(token.starts_with(container::CONTAINER_TEXT_PREFIX));
+  assert_eq!(ActivationRequest::decode(&token).unwrap(), request);
+  // 复制粘贴时带上的换行不影响
+  assert_eq!(
+    ActivationRequest::decode(&format!("{}\n", token)).unwrap(),
+    request
+  );
+
+  assert_eq!(ActivationRequest::decode(""), Err(LicenseError::EmptyInput));
+  assert!(matches!(
+    ActivationRequest::decode("not an activation request"),
+    Err(LicenseError::InvalidFormat(_))
+  ));
+}
+
+#[test]
+fn test_request_tampering_is_detected() {
+  use base64::prelude::*;
+  use lib::container::{split_signed_payload, Container};
+
+  let request = ActivationRequest::new("acme-2025", "CODE-1234", machine("isolated-01"), NOW);
+  let mut container = Container::decode(&request.encode().unwrap()).unwrap();
+
+  // 改动消息中的 license id，签名不再匹配
+  let (message, signature) = split_signed_payload(&container.payload).unwrap();
+  let tampered = String::from_utf8(message.to_vec())
+    .unwrap()
+    .replace("acme-2025", "acme-2099");
+  let mut payload = (tampered.len() as u32).to_be_bytes().to_vec();
+  payload.extend_from_slice(tampered.as_bytes());
+  payload.extend_from_slice(signature);
+  container.payload = payload;
+  assert_eq!(
+    ActivationRequest::decode(&container.encode().unwrap()),
+    Err(LicenseError::InvalidSignature)
+  );
+
+  // 截断
+  let token = request.encode().unwrap();
+  let bytes = BASE64_STANDARD.decode(&token).unwrap();
+  let truncated = BASE64_STANDARD.encode(&bytes[..bytes.len() - 10]);
+  assert!(ActivationRequest::decode(&truncated).is_err());
+}
+
+#[cfg(feature = "issuer")]
+#[test]
+fn test_offline_activation_flow() {
+  use lib::keys::PrivateKey;
+
+  let signing_key = PrivateKey::generate_ed25519().to_signing_key();
+  let mut keyring = Keyring::new();
+  keyring
+    .add_verifying_key(signing_key.verifying_key())
+    .unwrap();
+
+  // 离线机器生成请求
+  let this_machine = machine("isolated-01");
+  let request = ActivationRequest::new("acme-2025", "CODE-1234", this_machine.clone(), NOW);
+  let request_token = request.encode().unwrap();
+
+  // 联网机器确认请求并签名响应
+  let received = ActivationRequest::decode(&request_token).unwrap();
+  let mut response = ActivationResponse::for_request(&received, NOW + 60);
+  response.expires_at = Some(NOW + 365 * 24 * 3600);
+  let response_token = response.sign(&signing_key).unwrap();
+
+  // 回到离线机器安装
+  let installed =
+    ActivationResponse::install(&keyring, &response_token, &request, &this_machine, 0).unwrap();
+  assert_eq!(installed, response);
+  assert!(!installed.is_expired(NOW + 3600));
+  assert!(installed.is_expired(NOW + 366 * 24 * 3600));
+
+  // 之后启动时重新校验
+  let reloaded = ActivationResponse::verify(&keyring, &response_token).unwrap();
+  assert_eq!(reloaded.check_machine(&this_machine, 0), Ok(()));
+  assert_eq!(
+    reloaded.check_machine(&machine("other-host"), 0),
+    Err(LicenseError::FingerprintMismatch)
+  );
+  assert_eq!(reloaded.check_machine(&machine("other-host"), 1), Ok(()));
+
+  // 响应只能安装到发起请求的那次激活
+  let other_request = ActivationRequest::new("acme-2025", "CODE-1234", this_machine.clone(), NOW);
+  assert_eq!(
+    ActivationResponse::install(&keyring, &response_token, &other_request, &this_machine, 0),
+    Err(LicenseError::ActivationMismatch(
+      "Nonce does not match the request"
+    ))
+  );
+  let mut wrong_license = request.clone();
+  wrong_license.license_id = "other".to_string();
+  assert_eq!(
+    ActivationResponse::install(&keyring, &response_token, &wrong_license, &this_machine, 0)
+      .unwrap_err()
+      .code(),
+    "LICENSE_ACTIVATION_MISMATCH"
+  );
+  assert_eq!(
+    ActivationResponse::install(&keyring, &response_token, &request, &machine("laptop"), 0),
+    Err(LicenseError::FingerprintMismatch)
+  );
+
+  // 其他密钥签的响应
+  let other_key = PrivateKey::generate_ed25519().to_signing_key();
+  assert!(matches!(
+    ActivationResponse::verify(&keyring, &response.sign(&other_key).unwrap()),
+    Err(LicenseError::UnknownKeyId(_))
+  ));
+  assert_eq!(
+    ActivationResponse::verify_with(
+      &signing_key.verifying_key(),
+      &response.sign(&other_key).unwrap()
+    ),
+    Err(LicenseError::InvalidSignature)
+  );
+}
+
+#[cfg(feature = "issuer")]
+#[test]
+fn test_license_and_activation_are_not_interchangeable() {
+  use lib::keys::PrivateKey;
+
+  let signing_key = PrivateKey::generate_ed25519().to_signing_key();
+  let verifying_key = signing_key.verifying_key();
+
+  let license = signing_key
+    .sign_payload(&LicensePayload::new("Acme", NOW))
+    .unwrap();
+  assert_eq!(
+    ActivationResponse::verify_with(&verifying_key, &license),
+    Err(LicenseError::InvalidFormat(
+      "Unexpected activation message type"
+    ))
+  );
+
+  let request = ActivationRequest::new("acme-2025", "CODE-1234", machine("isolated-01"), NOW);
+  let response = ActivationResponse::for_request(&request, NOW)
+    .sign(&signing_key)
+    .unwrap();
+  assert!(matches!(
+    verify_license_with(&verifying_key, &response),
+    Err(LicenseError::InvalidPayload(_))
+  ));
+  // 请求不能当作响应
+  assert!(ActivationResponse::verify_with(&verifying_key, &request.encode().unwrap()).is_err());
+}
@@ -36,10 +36,11 @@ fn test_various_message_lengths() {
 
     let encrypted = public_key
       .encrypt(&mut rng, Pkcs1v15Encrypt, message.as_bytes())
-      .expect(&format!("Should encrypt {} chars", length));
+      .unwrap_or_else(|_| panic!("Should encrypt {} chars", length));
 
     let base64_encrypted = BASE64_STANDARD.encode(&encrypted);
-    let decrypted = decrypt(&base64_encrypted).expect(&format!("Should decrypt {} chars", length));
+    let decrypted =
+      decrypt(&base64_encrypted).unwrap_or_else(|_| panic!("Should decrypt {} chars", length));
 
     assert_eq!(decrypted, message);
     assert_eq!(decrypted.len(), length);
@@ -54,7 +55,7 @@ fn test_input_length_validation() {
   let too_long_input = "A".repeat(450);
   let result = decrypt(&too_long_input);
   assert!(result.is_err());
-  assert!(result.unwrap_err().contains("Input too long"));
+  assert_eq!(result.unwrap_err(), LicenseError::InputTooLong);
 }
 
 #[test]
@@ -67,7 +68,7 @@ fn test_encrypted_data_size_validation() {
 
   let result = decrypt(&wrong_size_base64);
   assert!(result.is_err());
-  assert!(result.unwrap_err().contains("Invalid encrypted data size"));
+  assert_eq!(result.unwrap_err(), LicenseError::InvalidBlockSize);
 
   // 测试另一个错误大小
   let another_wrong_size_data = vec![0u8; 257]; // 257 字节
@@ -75,7 +76,7 @@ fn test_encrypted_data_size_validation() {
 
   let result = decrypt(&another_wrong_size_base64);
   assert!(result.is_err());
-  assert!(result.unwrap_err().contains("Invalid encrypted data size"));
+  assert_eq!(result.unwrap_err(), LicenseError::InvalidBlockSize);
 }
 
 #[test]
@@ -78,7 +78,6 @@ fn test_long_message_simulation() {
 
   // 手动分块
   let chunks: Vec<&str> = message
-    .as_str()
     .as_bytes()
     .chunks(max_chunk_size)
     .map(|chunk| std::str::from_utf8(chunk).unwrap())
@@ -0,0 +1,240 @@
+use ed25519_dalek::Signer;
+use lib::container::*;
+use lib::*;
+use rsa::rand_core::OsRng;
+use rsa::{Oaep, Pkcs1v15Encrypt, RsaPublicKey};
+use sha2::Sha256;
+
+fn public_key() -> RsaPublicKey {
+  RsaPublicKey::from(&get_private_key())
+}
+
+// 与 license-server 相同的签发路径
+#[cfg(feature = "issuer")]
+fn seal(message: &str) -> String {
+  issuer::Encryptor::new(public_key())
+    .unwrap()
+    .with_key_id("")
+    .encrypt(message)
+    .unwrap()
+}
+
+#[test]
+fn test_container_roundtrip() {
+  let container = Container::new(Algorithm::Ed25519, "2025-08", vec![1, 2, 3]);
+  let text = container.encode().unwrap();
+
+  assert!(text.starts_with(CONTAINER_TEXT_PREFIX));
+  assert!(Container::is_container(&text));
+
+  let decoded = Container::decode(&text).unwrap();
+  assert_eq!(decoded, container);
+  assert_eq!(decoded.version, CONTAINER_VERSION);
+  assert_eq!(decoded.key_id, "2025-08");
+
+  // 二进制布局：magic | version | algorithm | key id 长度 | key id | payload
+  let bytes = container.to_bytes().unwrap();
+  assert_eq!(&bytes[..4], CONTAINER_MAGIC);
+  assert_eq!(bytes[4], 1);
+  assert_eq!(bytes[5], Algorithm::Ed25519.id());
+  assert_eq!(bytes[6], 7);
+  assert_eq!(&bytes[14..], &[1, 2, 3]);
+}
+
+#[test]
+fn test_unsupported_version_and_algorithm() {
+  let mut bytes = Container::new(Algorithm::RsaPkcs1v15, "", vec![0])
+    .to_bytes()
+    .unwrap();
+
+  bytes[4] = 2;
+  assert_eq!(
+    Container::from_bytes(&bytes),
+    Err(LicenseError::UnsupportedVersion(2))
+  );
+
+  bytes[4] = 1;
+  bytes[5] = 99;
+  assert!(matches!(
+    Container::from_bytes(&bytes),
+    Err(LicenseError::UnsupportedAlgorithm(_))
+  ));
+}
+
+#[test]
+fn test_malformed_container() {
+  assert!(matches!(
+    Container::from_bytes(b"XXXX\x01\x01\x00"),
+    Err(LicenseError::InvalidFormat(_))
+  ));
+  assert!(matches!(
+    Container::from_bytes(b"BKLC\x01\x01"),
+    Err(LicenseError::InvalidFormat(_))
+  ));
+  // key id 长度超出实际数据
+  assert!(matches!(
+    Container::from_bytes(b"BKLC\x01\x01\x09abc"),
+    Err(LicenseError::InvalidFormat(_))
+  ));
+  assert!(matches!(
+    Container::new(Algorithm::RsaPkcs1v15, "k".repeat(256), vec![]).encode(),
+    Err(LicenseError::InvalidFormat(_))
+  ));
+  assert_eq!(
+    Container::decode("QktMQ!!!"),
+    Err(LicenseError::InvalidBase64)
+  );
+}
+
+#[test]
+fn test_try_decode() {
+  let container = Container::new(Algorithm::Ed25519, "2025-08", vec![1, 2, 3]);
+  let text = container.encode().unwrap();
+  assert_eq!(Container::try_decode(&text), Ok(Some(container)));
+
+  // 不是容器格式，由调用方按旧格式处理
+  assert_eq!(Container::try_decode("CHUNK:1:abc"), Ok(None));
+  // 与容器前缀相同但魔数不符的旧格式密文
+  assert_eq!(Container::try_decode("QktMQAAA"), Ok(None));
+
+  // 是容器但解析失败
+  assert_eq!(
+    Container::try_decode("QktMQ!!!"),
+    Err(LicenseError::InvalidBase64)
+  );
+  assert_eq!(
+    Container::try_decode(
+      &Container::new(Algorithm::Ed25519, "", vec![])
+        .encode()
+        .unwrap()[..8]
+    ),
+    Err(LicenseError::InvalidFormat("Truncated container header"))
+  );
+}
+
+#[test]
+#[cfg(feature = "issuer")]
+fn test_decrypt_hybrid_container() {
+  let message = "混合加密容器 ".repeat(1000);
+  let token = seal(&message);
+  assert_eq!(decrypt(&token).unwrap(), message);
+
+  // 头部在 AAD 中，修改算法以外的字段也会导致解密失败
+  let mut container = Container::decode(&token).unwrap();
+  container.key_id = "tampered".to_string();
+  assert_eq!(
+    decrypt(&container.encode().unwrap()),
+    Err(LicenseError::DecryptionFailed)
+  );
+}
+
+#[test]
+fn test_decrypt_rsa_block_container() {
+  // PKCS#1 v1.5 分块放在容器中，不再需要 CHUNK 头
+  let public_key = public_key();
+  let message = "B".repeat(500);
+  let mut payload = Vec::new();
+  for chunk in message.as_bytes().chunks(245) {
+    payload.extend(
+      public_key
+        .encrypt(&mut OsRng, Pkcs1v15Encrypt, chunk)
+        .unwrap(),
+    );
+  }
+  let token = Container::new(Algorithm::RsaPkcs1v15, "", payload)
+    .encode()
+    .unwrap();
+  assert_eq!(decrypt(&token).unwrap(), message);
+
+  let token = Container::new(Algorithm::RsaPkcs1v15, "", vec![0; 100])
+    .encode()
+    .unwrap();
+  assert_eq!(decrypt(&token), Err(LicenseError::InvalidBlockSize));
+
+  // 块数超过上限时不做任何 RSA 运算直接拒绝
+  for algorithm in [Algorithm::RsaPkcs1v15, Algorithm::RsaOaepSha256] {
+    let token = Container::new(algorithm, "", vec![0; (MAX_BLOCKS + 1) * 256])
+      .encode()
+      .unwrap();
+    assert_eq!(
+      decrypt(&token),
+      Err(LicenseError::InvalidFormat("Too many blocks"))
+    );
+  }
+}
+
+#[test]
+fn test_decrypt_rsa_oaep_container() {
+  // OAEP(SHA-256) 每块明文最多 256 - 66 = 190 字节，label 绑定头部、块序号和前一块
+  let public_key = public_key();
+  let message = "O".repeat(500);
+  let mut container = Container::new(Algorithm::RsaOaepSha256, "", Vec::new());
+  let header = container.header_bytes().unwrap();
+  let mut previous = Vec::new();
+  for (index, chunk) in message.as_bytes().chunks(190).enumerate() {
+    let label = oaep_block_label(&header, index, 3, &previous);
+    previous = public_key
+      .encrypt(&mut OsRng, Oaep::new_with_label::<Sha256, _>(label), chunk)
+      .unwrap();
+    container.payload.extend(&previous);
+  }
+  let payload = container.payload.clone();
+  let token = container.encode().unwrap();
+  assert_eq!(decrypt(&token).unwrap(), message);
+
+  // 没有 label 的 OAEP 块无法解密
+  let unlabeled = public_key
+    .encrypt(&mut OsRng, Oaep::new::<Sha256>(), b"seats=1000")
+    .unwrap();
+  let token = Container::new(Algorithm::RsaOaepSha256, "", unlabeled)
+    .encode()
+    .unwrap();
+  assert_eq!(decrypt(&token), Err(LicenseError::InvalidPadding));
+  assert_eq!(Algorithm::from_id(5), Ok(Algorithm::RsaOaepSha256));
+
+  // 算法由头部决定，OAEP 密文不能当作 PKCS#1 v1.5 解密
+  let token = Container::new(Algorithm::RsaPkcs1v15, "", payload)
+    .encode()
+    .unwrap();
+  assert_eq!(decrypt(&token), Err(LicenseError::InvalidPadding));
+}
+
+#[test]
+fn test_verify_signed_container() {
+  let signing_key = ed25519_dalek::SigningKey::from_bytes(&[5u8; 32]);
+  let verifying_key = VerifyingKey::Ed25519(signing_key.verifying_key());
+  let payload = LicensePayload::new("Container Co.", 1_754_611_200);
+  let message = payload.to_json().unwrap();
+
+  let mut container = Container::new(Algorithm::Ed25519, "sig-1", Vec::new());
+  let header = container.header_bytes().unwrap();
+  let signature = signing_key.sign(&signing_input(&header, message.as_bytes()));
+  container.payload = signed_payload(message.as_bytes(), &signature.to_bytes());
+  let token = container.encode().unwrap();
+
+  assert!(signature::is_signed(&token));
+  assert_eq!(
+    verify_license_with(&verifying_key, &token).unwrap(),
+    payload
+  );
+
+  // key id 在签名范围内
+  let mut tampered = Container::decode(&token).unwrap();
+  tampered.key_id = "sig-2".to_string();
+  assert_eq!(
+    verify_license_with(&verifying_key, &tampered.encode().unwrap()),
+    Err(LicenseError::InvalidSignature)
+  );
+
+  // 签名容器不能被当作加密 license 解密
+  assert!(matches!(
+    decrypt(&token),
+    Err(LicenseError::InvalidFormat(_))
+  ));
+  // 加密容器不能被当作签名 license 校验
+  #[cfg(feature = "issuer")]
+  assert!(matches!(
+    verify_license_with(&verifying_key, &seal("x")),
+    Err(LicenseError::InvalidFormat(_))
+  ));
+}
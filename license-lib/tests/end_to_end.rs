@@ -1,6 +1,4 @@
 // 端到端测试：使用license-server的encrypt和license-lib的decrypt
-use std::path::Path;
-use std::process::Command;
 
 #[test]
 fn test_end_to_end_short_message() {
@@ -80,7 +78,7 @@ fn test_various_content_types() {
     "=".repeat(50),
     "张三（北京科技有限公司）",
     "2025-08-08",
-    vec!["基础功能", "高级功能", "企业功能"].join(", "),
+    ["基础功能", "高级功能", "企业功能"].join(", "),
     "v2.1.0 支持长消息加密"
   );
   test_message_roundtrip(&license_text);
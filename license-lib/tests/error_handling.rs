@@ -6,7 +6,7 @@ fn test_decrypt_with_invalid_base64() {
   // 测试无效的base64输入应该返回错误
   let result = decrypt("invalid_base64!");
   assert!(result.is_err());
-  assert!(result.unwrap_err().contains("Invalid base64 encoding"));
+  assert_eq!(result.unwrap_err(), LicenseError::InvalidBase64);
 }
 
 #[test]
@@ -15,7 +15,7 @@ fn test_decrypt_with_invalid_encrypted_data() {
   let invalid_encrypted = BASE64_STANDARD.encode(b"this is not encrypted data");
   let result = decrypt(&invalid_encrypted);
   assert!(result.is_err());
-  assert!(result.unwrap_err().contains("Invalid encrypted data size"));
+  assert_eq!(result.unwrap_err(), LicenseError::InvalidBlockSize);
 }
 
 #[test]
@@ -23,7 +23,7 @@ fn test_decrypt_with_empty_string() {
   // 测试空字符串输入
   let result = decrypt("");
   assert!(result.is_err());
-  assert!(result.unwrap_err().contains("Input cannot be empty"));
+  assert_eq!(result.unwrap_err(), LicenseError::EmptyInput);
 }
 
 #[test]
@@ -31,5 +31,51 @@ fn test_decrypt_with_malformed_base64() {
   // 测试格式错误的base64
   let result = decrypt("SGVsbG8gV29ybGQ==="); // 过多的 padding
   assert!(result.is_err());
-  assert!(result.unwrap_err().contains("Invalid base64 encoding"));
+  assert_eq!(result.unwrap_err(), LicenseError::InvalidBase64);
+}
+
+#[test]
+fn test_chunk_header_errors() {
+  // 分块头部错误应返回 InvalidChunkHeader，并保留原因
+  assert_eq!(
+    decrypt("CHUNK:2:onlyoneblock").unwrap_err(),
+    LicenseError::InvalidChunkHeader("Chunk count mismatch")
+  );
+  assert_eq!(
+    decrypt("CHUNK:abc:data").unwrap_err(),
+    LicenseError::InvalidChunkHeader("Invalid chunk count")
+  );
+  assert_eq!(
+    decrypt("CHUNK:1:!!!").unwrap_err(),
+    LicenseError::InvalidBase64
+  );
+}
+
+#[test]
+fn test_error_codes_are_stable() {
+  // 错误码是对外契约，不能随意修改
+  assert_eq!(LicenseError::EmptyInput.code(), "LICENSE_EMPTY_INPUT");
+  assert_eq!(LicenseError::InvalidBase64.code(), "LICENSE_BAD_BASE64");
+  assert_eq!(
+    LicenseError::DecryptionFailed.code(),
+    "LICENSE_DECRYPT_FAILED"
+  );
+  assert_eq!(LicenseError::InvalidPadding.code(), "LICENSE_BAD_PADDING");
+  assert_eq!(
+    LicenseError::PlaintextTooLong.code(),
+    "LICENSE_PLAINTEXT_TOO_LONG"
+  );
+  assert_eq!(
+    LicenseError::InvalidChunkHeader("Chunk count mismatch").code(),
+    "LICENSE_BAD_CHUNK_HEADER"
+  );
+
+  // 长度正确但填充无效的密文块
+  assert_eq!(
+    decrypt(&BASE64_STANDARD.encode([1u8; 256])).unwrap_err(),
+    LicenseError::InvalidPadding
+  );
+
+  // Display 保持与旧版字符串错误一致
+  assert_eq!(LicenseError::InputTooLong.to_string(), "Input too long");
 }
@@ -0,0 +1,123 @@
+use lib::fingerprint::*;
+use lib::*;
+
+fn machine(machine_id: &str, hostname: &str, mac: &str, cpu: &str) -> Fingerprint {
+  Fingerprint::collect(&[
+    Box::new(StaticSource::new("machine_id", machine_id)) as Box<dyn FingerprintSource>,
+    Box::new(StaticSource::new("hostname", hostname)),
+    Box::new(StaticSource::new("mac", mac)),
+    Box::new(StaticSource::new("cpu", cpu)),
+  ])
+}
+
+fn office_pc() -> Fingerprint {
+  machine(
+    "4c4c4544003a",
+    "office-pc",
+    "00:1a:2b:3c:4d:5e",
+    "GenuineIntel|i7|8",
+  )
+}
+
+#[test]
+fn test_fingerprint_is_stable_and_hashed() {
+  let fingerprint = office_pc();
+  assert_eq!(fingerprint, office_pc());
+  assert_eq!(fingerprint.id(), office_pc().id());
+  assert_eq!(fingerprint.id().len(), 32);
+  assert_eq!(fingerprint.components().len(), 4);
+
+  // 只保存哈希，原始值不会出现在序列化结果中
+  let json = serde_json::to_string(&fingerprint).unwrap();
+  assert!(!json.contains("office-pc"));
+  assert!(!json.contains("00:1a:2b"));
+  assert_eq!(
+    serde_json::from_str::<Fingerprint>(&json).unwrap(),
+    fingerprint
+  );
+
+  // 规范化：大小写和首尾空白不影响结果
+  assert_eq!(
+    Fingerprint::new().with_component("hostname", " OFFICE-PC\n"),
+    Fingerprint::new().with_component("hostname", "office-pc")
+  );
+  // 组件名参与哈希
+  assert_ne!(
+    Fingerprint::new()
+      .with_component("hostname", "x")
+      .components()["hostname"],
+    Fingerprint::new().with_component("cpu", "x").components()["cpu"]
+  );
+  assert!(Fingerprint::new().with_component("mac", "  ").is_empty());
+}
+
+#[test]
+fn test_fuzzy_matching() {
+  let licensed = office_pc();
+  assert_eq!(licensed.changed_components(&office_pc()), 0);
+  assert!(licensed.matches(&office_pc(), 0));
+
+  // 换了网卡
+  let new_nic = machine(
+    "4c4c4544003a",
+    "office-pc",
+    "00:ff:ff:ff:ff:ff",
+    "GenuineIntel|i7|8",
+  );
+  assert_eq!(licensed.changed_components(&new_nic), 1);
+  assert!(!licensed.matches(&new_nic, 0));
+  assert!(licensed.matches(&new_nic, 1));
+
+  // 当前机器缺少某个组件也算变化
+  let mut partial = office_pc().components().clone();
+  partial.remove("cpu");
+  let partial = Fingerprint::from_components(partial);
+  assert_eq!(licensed.changed_components(&partial), 1);
+
+  // 完全不同的机器，即使容忍度很大也不匹配
+  let other = machine("ffff", "other", "aa:bb:cc:dd:ee:ff", "AuthenticAMD|epyc|64");
+  assert_eq!(licensed.changed_components(&other), 4);
+  assert!(!licensed.matches(&other, 10));
+}
+
+#[test]
+fn test_payload_binding() {
+  let mut payload = LicensePayload::new("Node Locked Ltd", 1_754_611_200);
+  assert!(!payload.is_node_locked());
+  assert_eq!(payload.check_fingerprint(&office_pc(), 0), Ok(()));
+
+  let other = machine("ffff", "other", "aa:bb:cc:dd:ee:ff", "AuthenticAMD|epyc|64");
+  payload.fingerprints = vec![other.clone(), office_pc()];
+  assert!(payload.is_node_locked());
+  assert_eq!(payload.check_fingerprint(&office_pc(), 0), Ok(()));
+  assert_eq!(payload.check_fingerprint(&other, 0), Ok(()));
+
+  let laptop = machine("1234", "laptop", "11:22:33:44:55:66", "GenuineIntel|i5|4");
+  assert_eq!(
+    payload.check_fingerprint(&laptop, 2),
+    Err(LicenseError::FingerprintMismatch)
+  );
+  assert_eq!(
+    LicenseError::FingerprintMismatch.code(),
+    "LICENSE_FINGERPRINT_MISMATCH"
+  );
+
+  // 指纹随 payload 一起序列化
+  let json = payload.to_json().unwrap();
+  assert!(json.contains("\"fingerprints\""));
+  assert_eq!(LicensePayload::from_json(&json).unwrap(), payload);
+  assert!(!LicensePayload::new("x", 0)
+    .to_json()
+    .unwrap()
+    .contains("fingerprints"));
+}
+
+#[test]
+fn test_current_fingerprint() {
+  // 默认来源在不同环境下可用的组件不同，但同一台机器上结果稳定
+  let current = Fingerprint::current();
+  assert_eq!(current, Fingerprint::current());
+  if !current.is_empty() {
+    assert!(current.matches(&Fingerprint::current(), 0));
+  }
+}
@@ -0,0 +1,187 @@
+#![cfg(feature = "issuer")]
+
+use lib::container::{Algorithm, Container};
+use lib::issuer::{Encryptor, SigningKey};
+use lib::*;
+use rsa::pkcs1::EncodeRsaPublicKey;
+use rsa::pkcs8::{EncodePrivateKey, EncodePublicKey, LineEnding};
+use rsa::RsaPublicKey;
+
+fn encryptor() -> Encryptor {
+  Encryptor::new(RsaPublicKey::from(&get_private_key())).unwrap()
+}
+
+fn sample_payload() -> LicensePayload {
+  let mut payload = LicensePayload::new("Offline Tools Ltd", 1_754_611_200);
+  payload.expires_at = Some(1_786_147_200);
+  payload.features = vec!["api_access".to_string()];
+  payload
+}
+
+#[test]
+fn test_encrypt_roundtrip() {
+  // 签发端加密，内置私钥解密
+  let encryptor = encryptor();
+  for message in ["", "Hello, World!", &"长消息".repeat(5000)] {
+    let token = encryptor.encrypt(message).unwrap();
+    let container = Container::decode(&token).unwrap();
+    assert_eq!(container.algorithm, Algorithm::HybridRsaOaepAes256Gcm);
+    assert_eq!(decrypt(&token).unwrap(), message);
+  }
+
+  let token = encryptor.encrypt_rsa_oaep(&"O".repeat(500)).unwrap();
+  let container = Container::decode(&token).unwrap();
+  assert_eq!(container.algorithm, Algorithm::RsaOaepSha256);
+  assert_eq!(container.payload.len(), 3 * 256);
+  assert_eq!(decrypt(&token).unwrap(), "O".repeat(500));
+}
+
+#[test]
+fn test_rsa_oaep_blocks_are_bound() {
+  let encryptor = encryptor();
+  let message = format!("{}{}{}", "A".repeat(190), "B".repeat(190), "C".repeat(10));
+  let token = encryptor.encrypt_rsa_oaep(&message).unwrap();
+  let container = Container::decode(&token).unwrap();
+  let blocks: Vec<&[u8]> = container.payload.chunks(256).collect();
+  assert_eq!(blocks.len(), 3);
+
+  let with_payload = |payload: Vec<u8>| {
+    let mut tampered = container.clone();
+    tampered.payload = payload;
+    decrypt(&tampered.encode().unwrap())
+  };
+  // 交换块的顺序
+  assert_eq!(
+    with_payload([blocks[1], blocks[0], blocks[2]].concat()),
+    Err(LicenseError::InvalidPadding)
+  );
+  // 删除最后一块
+  assert_eq!(
+    with_payload([blocks[0], blocks[1]].concat()),
+    Err(LicenseError::InvalidPadding)
+  );
+  // 拼接另一个容器同一位置的块
+  let other = Container::decode(&encryptor.encrypt_rsa_oaep(&"Z".repeat(390)).unwrap()).unwrap();
+  assert_eq!(
+    with_payload([blocks[0], blocks[1], &other.payload[512..]].concat()),
+    Err(LicenseError::InvalidPadding)
+  );
+  // 修改头部
+  let mut tampered = container.clone();
+  tampered.key_id = "other".to_string();
+  assert_eq!(
+    decrypt(&tampered.encode().unwrap()),
+    Err(LicenseError::InvalidPadding)
+  );
+
+  assert_eq!(decrypt(&token).unwrap(), message);
+}
+
+#[test]
+fn test_encryptor_key_id_matches_decryptor() {
+  let encryptor = encryptor();
+  assert_eq!(
+    encryptor.key_id(),
+    Decryptor::shared().unwrap().key_id().unwrap()
+  );
+
+  // 带 key id 的容器可以被钥匙串按 id 选中
+  let token = encryptor.encrypt("by key id").unwrap();
+  assert_eq!(
+    Container::decode(&token).unwrap().key_id,
+    encryptor.key_id()
+  );
+  let keyring = Keyring::with_default_keys().unwrap();
+  assert_eq!(keyring.decrypt(&token).unwrap(), "by key id");
+}
+
+#[test]
+fn test_encryptor_from_pem() {
+  let public_key = RsaPublicKey::from(&get_private_key());
+  let spki = public_key.to_public_key_pem(LineEnding::LF).unwrap();
+  let pkcs1 = public_key.to_pkcs1_pem(LineEnding::LF).unwrap();
+
+  let from_spki = Encryptor::from_pem(&spki).unwrap();
+  let from_pkcs1 = Encryptor::from_pem(&pkcs1).unwrap();
+  assert_eq!(from_spki.key_id(), from_pkcs1.key_id());
+  assert_eq!(from_spki.public_key(), &public_key);
+
+  assert!(matches!(
+    Encryptor::from_pem("not a key"),
+    Err(LicenseError::KeyLoad(_))
+  ));
+}
+
+#[test]
+fn test_encrypt_payload() {
+  let payload = sample_payload();
+  let token = encryptor().encrypt_payload(&payload).unwrap();
+  assert_eq!(decode_license(&token).unwrap(), payload);
+}
+
+#[test]
+fn test_encrypt_too_long() {
+  let message = "x".repeat(1024 * 1024);
+  assert_eq!(
+    encryptor().encrypt(&message),
+    Err(LicenseError::InputTooLong)
+  );
+
+  // RSA-OAEP 直接加密的块数有上限，更长的内容应使用混合加密
+  let message = "x".repeat(190 * 100 + 1);
+  assert!(matches!(
+    encryptor().encrypt_rsa_oaep(&message),
+    Err(LicenseError::EncryptionFailed(_))
+  ));
+  assert!(encryptor().encrypt_rsa_oaep(&"x".repeat(190 * 100)).is_ok());
+}
+
+#[test]
+fn test_sign_ed25519() {
+  let key = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+  let pem = key.to_pkcs8_pem(LineEnding::LF).unwrap();
+  let signing_key = SigningKey::from_pkcs8_pem(&pem).unwrap();
+  assert_eq!(signing_key.algorithm(), Algorithm::Ed25519);
+
+  let payload = sample_payload();
+  let token = signing_key.sign_payload(&payload).unwrap();
+  assert!(signature::is_signed(&token));
+
+  let verifying_key = signing_key.verifying_key();
+  assert_eq!(
+    verify_license_with(&verifying_key, &token).unwrap(),
+    payload
+  );
+  assert_eq!(
+    Container::decode(&token).unwrap().key_id,
+    verifying_key.key_id().unwrap()
+  );
+}
+
+#[test]
+fn test_sign_rsa() {
+  let pem = get_private_key().to_pkcs8_pem(LineEnding::LF).unwrap();
+  let signing_key = SigningKey::from_pkcs8_pem(&pem).unwrap();
+  assert_eq!(signing_key.algorithm(), Algorithm::RsaPkcs1v15Sha256);
+
+  let token = signing_key.sign("rsa signed").unwrap();
+  let public_pem = RsaPublicKey::from(&get_private_key())
+    .to_public_key_pem(LineEnding::LF)
+    .unwrap();
+  let verifying_key = VerifyingKey::from_public_key_pem(&public_pem).unwrap();
+  assert_eq!(
+    signing_key.key_id().unwrap(),
+    verifying_key.key_id().unwrap()
+  );
+
+  // 签名内容不是 LicensePayload 时校验签名通过但解析失败
+  assert!(matches!(
+    verify_license_with(&verifying_key, &token),
+    Err(LicenseError::InvalidPayload(_))
+  ));
+
+  assert!(matches!(
+    SigningKey::from_pkcs8_pem("not a key"),
+    Err(LicenseError::KeyLoad(_))
+  ));
+}
@@ -0,0 +1,212 @@
+use base64::prelude::*;
+use ed25519_dalek::Signer;
+use lib::container::*;
+use lib::*;
+use rsa::rand_core::OsRng;
+use rsa::{Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey};
+
+// 与 license-server 相同的签发路径，头部带指定的 key id
+#[cfg(feature = "issuer")]
+fn seal(private_key: &RsaPrivateKey, message: &str, key_id: &str) -> String {
+  issuer::Encryptor::new(RsaPublicKey::from(private_key))
+    .unwrap()
+    .with_key_id(key_id)
+    .encrypt(message)
+    .unwrap()
+}
+
+// 旧格式单块密文，没有 key id
+fn encrypt_legacy(private_key: &RsaPrivateKey, message: &str) -> String {
+  let encrypted = RsaPublicKey::from(private_key)
+    .encrypt(&mut OsRng, Pkcs1v15Encrypt, message.as_bytes())
+    .unwrap();
+  BASE64_STANDARD.encode(encrypted)
+}
+
+fn sign_ed25519(key: &ed25519_dalek::SigningKey, key_id: &str, message: &str) -> String {
+  let mut container = Container::new(Algorithm::Ed25519, key_id, Vec::new());
+  let header = container.header_bytes().unwrap();
+  let signature = key.sign(&signing_input(&header, message.as_bytes()));
+  container.payload = signed_payload(message.as_bytes(), &signature.to_bytes());
+  container.encode().unwrap()
+}
+
+#[test]
+fn test_key_id_derived_from_public_key() {
+  let decryptor = Decryptor::default_key().unwrap();
+  let key_id = decryptor.key_id().unwrap();
+
+  // 16 个十六进制字符，同一把密钥结果稳定
+  assert_eq!(key_id.len(), 16);
+  assert!(key_id.chars().all(|c| c.is_ascii_hexdigit()));
+  assert_eq!(key_id, Decryptor::default_key().unwrap().key_id().unwrap());
+
+  let other = Decryptor::new(RsaPrivateKey::new(&mut OsRng, 2048).unwrap());
+  assert_ne!(other.key_id().unwrap(), key_id);
+}
+
+#[test]
+#[cfg(feature = "issuer")]
+fn test_key_rotation_by_key_id() {
+  // 轮换期间新旧两把密钥同时存在
+  let old_key = get_private_key();
+  let new_key = RsaPrivateKey::new(&mut OsRng, 2048).unwrap();
+
+  let mut keyring = Keyring::new();
+  keyring
+    .add_decryptor_with_id("2024", Decryptor::new(old_key.clone()))
+    .add_decryptor_with_id("2025", Decryptor::new(new_key.clone()));
+  assert_eq!(keyring.key_ids(), vec!["2024", "2025"]);
+
+  let old_license = seal(&old_key, "issued with old key", "2024");
+  let new_license = seal(&new_key, "issued with new key", "2025");
+  assert_eq!(
+    keyring.decrypt(&old_license).unwrap(),
+    "issued with old key"
+  );
+  assert_eq!(
+    keyring.decrypt(&new_license).unwrap(),
+    "issued with new key"
+  );
+
+  // key id 指向错误的密钥时不会回退尝试其他密钥
+  let mismatched = seal(&new_key, "wrong id", "2024");
+  assert_eq!(
+    keyring.decrypt(&mismatched),
+    Err(LicenseError::InvalidPadding)
+  );
+
+  // 旧密钥下线后不再接受
+  assert!(keyring.remove("2024"));
+  assert!(!keyring.remove("2024"));
+  assert_eq!(
+    keyring.decrypt(&old_license),
+    Err(LicenseError::UnknownKeyId("2024".to_string()))
+  );
+}
+
+#[test]
+#[cfg(feature = "issuer")]
+fn test_unknown_key_id() {
+  let keyring = Keyring::with_default_keys().unwrap();
+  let license = seal(&get_private_key(), "hello", "retired");
+
+  let error = keyring.decrypt(&license).unwrap_err();
+  assert_eq!(error, LicenseError::UnknownKeyId("retired".to_string()));
+  assert_eq!(error.code(), "LICENSE_UNKNOWN_KEY");
+}
+
+#[test]
+fn test_legacy_license_tries_all_keys() {
+  // 旧格式没有 key id，依次尝试钥匙串中的所有密钥
+  let other_key = RsaPrivateKey::new(&mut OsRng, 2048).unwrap();
+  let mut keyring = Keyring::new();
+  keyring
+    .add_decryptor(Decryptor::new(other_key.clone()))
+    .unwrap()
+    .add_decryptor(Decryptor::default_key().unwrap())
+    .unwrap();
+
+  let legacy = encrypt_legacy(&get_private_key(), "legacy license");
+  assert_eq!(keyring.decrypt(&legacy).unwrap(), "legacy license");
+
+  // 空 key id 的容器同样依次尝试
+  #[cfg(feature = "issuer")]
+  {
+    let anonymous = seal(&get_private_key(), "anonymous", "");
+    assert_eq!(keyring.decrypt(&anonymous).unwrap(), "anonymous");
+  }
+
+  // 所有密钥都不匹配
+  let unrelated = encrypt_legacy(&RsaPrivateKey::new(&mut OsRng, 2048).unwrap(), "x");
+  assert_eq!(
+    keyring.decrypt(&unrelated),
+    Err(LicenseError::InvalidPadding)
+  );
+
+  // 格式错误不会被当作密钥不匹配
+  assert_eq!(
+    keyring.decrypt("not base64!"),
+    Err(LicenseError::InvalidBase64)
+  );
+}
+
+#[test]
+#[cfg(feature = "issuer")]
+fn test_default_keys_derived_ids() {
+  let keyring = Keyring::with_default_keys().unwrap();
+  let key_id = Decryptor::default_key().unwrap().key_id().unwrap();
+  assert!(keyring.decryptor(&key_id).is_some());
+
+  let license = seal(&get_private_key(), "with derived id", &key_id);
+  assert_eq!(keyring.decrypt(&license).unwrap(), "with derived id");
+
+  let empty = Keyring::new();
+  assert!(matches!(
+    empty.decrypt(&license),
+    Err(LicenseError::UnknownKeyId(_))
+  ));
+  assert!(matches!(
+    empty.decrypt(&encrypt_legacy(&get_private_key(), "x")),
+    Err(LicenseError::KeyLoad(_))
+  ));
+}
+
+#[test]
+fn test_verify_with_keyring() {
+  let old_key = ed25519_dalek::SigningKey::from_bytes(&[1u8; 32]);
+  let new_key = ed25519_dalek::SigningKey::from_bytes(&[2u8; 32]);
+
+  let mut keyring = Keyring::new();
+  keyring
+    .add_verifying_key(VerifyingKey::Ed25519(old_key.verifying_key()))
+    .unwrap()
+    .add_verifying_key(VerifyingKey::Ed25519(new_key.verifying_key()))
+    .unwrap();
+
+  let new_id = VerifyingKey::Ed25519(new_key.verifying_key())
+    .key_id()
+    .unwrap();
+  let message = LicensePayload::new("Rotated Co.", 1_754_611_200)
+    .to_json()
+    .unwrap();
+
+  let token = sign_ed25519(&new_key, &new_id, &message);
+  let payload = keyring.decode_license(&token).unwrap();
+  assert_eq!(payload.licensee, "Rotated Co.");
+
+  // 未带 key id 的签名依次尝试
+  let anonymous = sign_ed25519(&old_key, "", &message);
+  assert!(keyring.verify(&anonymous).is_ok());
+
+  // 用旧密钥签名却声称是新 key id
+  let forged = sign_ed25519(&old_key, &new_id, &message);
+  assert_eq!(keyring.verify(&forged), Err(LicenseError::InvalidSignature));
+
+  let unknown = sign_ed25519(&new_key, "retired", &message);
+  assert_eq!(
+    keyring.verify(&unknown),
+    Err(LicenseError::UnknownKeyId("retired".to_string()))
+  );
+}
+
+#[test]
+fn test_add_pem() {
+  // 公钥用于校验签名，私钥用于解密
+  let mut keyring = Keyring::new();
+  keyring
+    .add_pem(PRIVATE_KEY)
+    .unwrap()
+    .add_pem(signature::SIGNING_PUBLIC_KEY)
+    .unwrap();
+
+  let decryptor_id = Decryptor::shared().unwrap().key_id().unwrap();
+  let verifying_id = VerifyingKey::shared().unwrap().key_id().unwrap();
+  assert!(keyring.decryptor(&decryptor_id).is_some());
+  assert!(keyring.verifying_key(&verifying_id).is_some());
+
+  assert!(matches!(
+    keyring.add_pem("not a key"),
+    Err(LicenseError::KeyLoad(_))
+  ));
+}
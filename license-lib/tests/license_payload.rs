@@ -0,0 +1,124 @@
+use base64::prelude::*;
+use lib::*;
+use rsa::{rand_core::OsRng, Pkcs1v15Encrypt, RsaPublicKey};
+use serde_json::json;
+
+// 模拟 license-server 的 encrypt：短消息单块，长消息分块
+fn encrypt(message: &str) -> String {
+  let public_key = RsaPublicKey::from(&get_private_key());
+  let blocks: Vec<String> = message
+    .as_bytes()
+    .chunks(245)
+    .map(|chunk| {
+      let encrypted = public_key
+        .encrypt(&mut OsRng, Pkcs1v15Encrypt, chunk)
+        .unwrap();
+      BASE64_STANDARD.encode(encrypted)
+    })
+    .collect();
+  if blocks.len() == 1 {
+    blocks[0].clone()
+  } else {
+    format!("CHUNK:{}:{}", blocks.len(), blocks.join("|"))
+  }
+}
+
+fn sample_payload() -> LicensePayload {
+  let mut payload = LicensePayload::new("Beijing Tech Co., Ltd", 1_754_611_200);
+  payload.expires_at = Some(1_786_147_200);
+  payload.not_before = Some(1_754_611_200);
+  payload.max_seats = Some(100);
+  payload.edition = Some("enterprise".to_string());
+  payload.features = vec!["api_access".to_string(), "audit_logs".to_string()];
+  payload
+    .claims
+    .insert("license_key".to_string(), json!("PROF-2025-ABCD-EFGH-IJKL"));
+  payload
+}
+
+#[test]
+fn test_payload_json_roundtrip() {
+  let payload = sample_payload();
+  let json = payload.to_json().unwrap();
+  assert_eq!(LicensePayload::from_json(&json).unwrap(), payload);
+}
+
+#[test]
+fn test_payload_optional_fields() {
+  // 只有必填字段时，其余字段取默认值
+  let payload =
+    LicensePayload::from_json(r#"{"licensee": "张三", "issued_at": 1754611200}"#).unwrap();
+  assert_eq!(payload.licensee, "张三");
+  assert_eq!(payload.expires_at, None);
+  assert_eq!(payload.max_seats, None);
+  assert!(payload.features.is_empty());
+  assert!(payload.claims.is_empty());
+
+  // 未设置的可选字段不写入 JSON
+  let json = payload.to_json().unwrap();
+  assert!(!json.contains("expires_at"));
+  assert!(!json.contains("claims"));
+}
+
+#[test]
+fn test_payload_features_and_claims() {
+  let payload = sample_payload();
+  assert!(payload.has_feature("api_access"));
+  assert!(!payload.has_feature("data_export"));
+  assert_eq!(
+    payload.claim("license_key"),
+    Some(&json!("PROF-2025-ABCD-EFGH-IJKL"))
+  );
+}
+
+#[test]
+fn test_invalid_payload() {
+  assert!(matches!(
+    LicensePayload::from_json("not json"),
+    Err(LicenseError::InvalidPayload(_))
+  ));
+  // 缺少 licensee
+  assert!(matches!(
+    LicensePayload::from_json(r#"{"issued_at": 1}"#),
+    Err(LicenseError::InvalidPayload(_))
+  ));
+}
+
+#[test]
+fn test_decode_encrypted_license() {
+  // 长 JSON 走分块格式，解密后直接得到结构化内容
+  let payload = sample_payload();
+  let token = encrypt(&payload.to_json().unwrap());
+
+  assert_eq!(decode_license(&token).unwrap(), payload);
+  assert_eq!(
+    Decryptor::default_key()
+      .unwrap()
+      .decode_license(&token)
+      .unwrap(),
+    payload
+  );
+
+  // 解密成功但不是 JSON
+  let token = encrypt("plain text license");
+  assert!(matches!(
+    decode_license(&token),
+    Err(LicenseError::InvalidPayload(_))
+  ));
+}
+
+#[test]
+fn test_require_features() {
+  let payload = sample_payload();
+  assert_eq!(payload.require_features(&["api_access"]), Ok(()));
+  assert_eq!(payload.require_features::<&str>(&[]), Ok(()));
+
+  let error = payload
+    .require_features(&["api_access".to_string(), "data_export".to_string()])
+    .unwrap_err();
+  assert_eq!(
+    error,
+    LicenseError::MissingFeature("data_export".to_string())
+  );
+  assert_eq!(error.code(), "LICENSE_MISSING_FEATURE");
+}
@@ -0,0 +1,194 @@
+use lib::revocation::*;
+use lib::*;
+
+const NOW: i64 = 1_754_611_200;
+
+fn sample_list() -> RevocationList {
+  let mut list = RevocationList::new(NOW);
+  list.next_update = Some(NOW + 86400);
+  list.licenses.push(RevokedLicense {
+    license_id: "acme-2025".to_string(),
+    revoked_at: NOW - 3600,
+    reason: Some("refunded".to_string()),
+  });
+  list
+}
+
+fn payload(license_id: Option<&str>) -> LicensePayload {
+  let mut payload = LicensePayload::new("Acme", NOW - 86400);
+  payload.license_id = license_id.map(str::to_string);
+  payload
+}
+
+#[test]
+fn test_check_payload() {
+  let list = sample_list();
+  assert_eq!(
+    list.check(&payload(Some("acme-2025"))),
+    Err(LicenseError::Revoked(NOW - 3600))
+  );
+  assert_eq!(LicenseError::Revoked(NOW).code(), "LICENSE_REVOKED");
+  assert_eq!(list.check(&payload(Some("acme-2026"))), Ok(()));
+  // 没有 license id 的旧 license 无法按 id 吊销
+  assert_eq!(list.check(&payload(None)), Ok(()));
+  assert_eq!(
+    list.revoked_license("acme-2025").unwrap().reason.as_deref(),
+    Some("refunded")
+  );
+
+  assert!(!list.is_stale(NOW + 86399));
+  assert!(list.is_stale(NOW + 86400));
+  assert!(!RevocationList::new(NOW).is_stale(i64::MAX));
+
+  // 旧格式没有 key id
+  assert_eq!(list.check_key("SIG:abc"), Ok(()));
+}
+
+#[test]
+fn test_license_id_round_trip() {
+  let json = payload(Some("acme-2025")).to_json().unwrap();
+  assert!(json.contains(r#""license_id":"acme-2025""#));
+  assert_eq!(
+    LicensePayload::from_json(&json)
+      .unwrap()
+      .license_id
+      .as_deref(),
+    Some("acme-2025")
+  );
+  assert!(!payload(None).to_json().unwrap().contains("license_id"));
+}
+
+#[cfg(feature = "issuer")]
+#[test]
+fn test_signed_revocation_list() {
+  use lib::keys::PrivateKey;
+
+  let signing_key = PrivateKey::generate_ed25519().to_signing_key();
+  let mut keyring = Keyring::new();
+  keyring
+    .add_verifying_key(signing_key.verifying_key())
+    .unwrap();
+
+  let token = sample_list().sign(&signing_key).unwrap();
+  assert_eq!(
+    RevocationList::verify(&keyring, &token).unwrap(),
+    sample_list()
+  );
+  assert_eq!(
+    RevocationList::verify_with(&signing_key.verifying_key(), &format!("{}\n", token)).unwrap(),
+    sample_list()
+  );
+
+  // 吊销列表、license 和激活响应不能互相替代
+  let license = signing_key
+    .sign_payload(&payload(Some("acme-2025")))
+    .unwrap();
+  assert_eq!(
+    RevocationList::verify(&keyring, &license),
+    Err(LicenseError::InvalidFormat(
+      "Unexpected revocation list type"
+    ))
+  );
+  assert!(matches!(
+    keyring.verify(&token),
+    Err(LicenseError::InvalidPayload(_))
+  ));
+  assert!(ActivationResponse::verify(&keyring, &token).is_err());
+
+  // 其他密钥签的列表
+  let other_key = PrivateKey::generate_ed25519().to_signing_key();
+  assert!(matches!(
+    RevocationList::verify(&keyring, &sample_list().sign(&other_key).unwrap()),
+    Err(LicenseError::UnknownKeyId(_))
+  ));
+  assert!(matches!(
+    RevocationList::verify(&keyring, "not a list"),
+    Err(LicenseError::InvalidFormat(_))
+  ));
+
+  // 解码时检查 license id
+  let list = RevocationList::verify(&keyring, &token).unwrap();
+  assert_eq!(
+    list.decode_license(&keyring, &license),
+    Err(LicenseError::Revoked(NOW - 3600))
+  );
+  let other = signing_key
+    .sign_payload(&payload(Some("acme-2026")))
+    .unwrap();
+  assert_eq!(
+    list
+      .decode_license(&keyring, &other)
+      .unwrap()
+      .license_id
+      .as_deref(),
+    Some("acme-2026")
+  );
+
+  // 吊销签发密钥后，它签发的 license 全部失效
+  let mut list = list;
+  list.keys.push(RevokedKey {
+    key_id: signing_key.key_id().unwrap(),
+    revoked_at: NOW,
+    reason: Some("key compromised".to_string()),
+  });
+  assert_eq!(list.check_key(&other), Err(LicenseError::Revoked(NOW)));
+  assert_eq!(
+    list.decode_license(&keyring, &other),
+    Err(LicenseError::Revoked(NOW))
+  );
+}
+
+#[cfg(feature = "issuer")]
+#[test]
+fn test_cached_revocation_list() {
+  use lib::keys::PrivateKey;
+
+  let dir = std::env::temp_dir().join(format!("license-crl-{}", std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  let store = LicenseStore::with_clock(&dir, FixedClock(NOW))
+    .with_fingerprint(Fingerprint::new().with_component("machine_id", "4c4c4544003a"));
+
+  let signing_key = PrivateKey::generate_ed25519().to_signing_key();
+  let mut keyring = Keyring::new();
+  keyring
+    .add_verifying_key(signing_key.verifying_key())
+    .unwrap();
+  let older = RevocationList::new(NOW - 86400).sign(&signing_key).unwrap();
+  let newer = sample_list().sign(&signing_key).unwrap();
+
+  // 先保存 license
+  assert!(matches!(
+    store.save_revocation_list(&keyring, &newer),
+    Err(LicenseError::Storage(_))
+  ));
+  store.save("license-token", None).unwrap();
+
+  assert_eq!(
+    store.save_revocation_list(&keyring, &newer).unwrap(),
+    sample_list()
+  );
+  // 旧列表不能替换新列表
+  assert_eq!(
+    store.save_revocation_list(&keyring, &older).unwrap(),
+    sample_list()
+  );
+  // 签名错误的列表不缓存
+  let other_key = PrivateKey::generate_ed25519().to_signing_key();
+  assert!(store
+    .save_revocation_list(
+      &keyring,
+      &RevocationList::new(NOW + 1).sign(&other_key).unwrap()
+    )
+    .is_err());
+
+  // 重新保存 license 保留缓存的列表
+  store.save("license-token-2", None).unwrap();
+  let stored = store.load().unwrap().unwrap();
+  assert_eq!(stored.revocation_list.as_deref(), Some(newer.as_str()));
+  assert_eq!(
+    stored.verify_revocation_list(&keyring).unwrap(),
+    Some(sample_list())
+  );
+
+  store.clear().unwrap();
+}
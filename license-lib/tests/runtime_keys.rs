@@ -0,0 +1,118 @@
+use base64::prelude::*;
+use lib::*;
+use rsa::pkcs1::{EncodeRsaPrivateKey, LineEnding};
+use rsa::pkcs8::EncodePrivateKey;
+use rsa::{rand_core::OsRng, Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey};
+
+fn encrypt_with(private_key: &RsaPrivateKey, message: &str) -> String {
+  let public_key = RsaPublicKey::from(private_key);
+  let encrypted = public_key
+    .encrypt(&mut OsRng, Pkcs1v15Encrypt, message.as_bytes())
+    .unwrap();
+  BASE64_STANDARD.encode(encrypted)
+}
+
+#[test]
+fn test_default_key_matches_decrypt() {
+  // decrypt() 与 Decryptor::default_key() 行为一致
+  let encrypted = encrypt_with(&get_private_key(), "default key");
+  let decryptor = Decryptor::default_key().expect("Default key should load");
+
+  assert_eq!(decryptor.decrypt(&encrypted).unwrap(), "default key");
+  assert_eq!(decrypt(&encrypted).unwrap(), "default key");
+}
+
+#[test]
+fn test_runtime_key_from_pem_and_der() {
+  // 每个部署使用自己的密钥对
+  let private_key = RsaPrivateKey::new(&mut OsRng, 2048).unwrap();
+  let encrypted = encrypt_with(&private_key, "own deployment key");
+
+  let pkcs8_pem = private_key.to_pkcs8_pem(LineEnding::LF).unwrap();
+  let pkcs1_pem = private_key.to_pkcs1_pem(LineEnding::LF).unwrap();
+  let pkcs8_der = private_key.to_pkcs8_der().unwrap();
+  let pkcs1_der = private_key.to_pkcs1_der().unwrap();
+
+  let decryptors = vec![
+    Decryptor::from_pem(&pkcs8_pem).unwrap(),
+    Decryptor::from_pem(&pkcs1_pem).unwrap(),
+    Decryptor::from_der(pkcs8_der.as_bytes()).unwrap(),
+    Decryptor::from_der(pkcs1_der.as_bytes()).unwrap(),
+    Decryptor::from_bytes(pkcs8_pem.as_bytes()).unwrap(),
+    Decryptor::from_bytes(pkcs1_der.as_bytes()).unwrap(),
+  ];
+  for decryptor in decryptors {
+    assert_eq!(decryptor.decrypt(&encrypted).unwrap(), "own deployment key");
+  }
+
+  // 内置密钥无法解密其他部署的 license
+  assert_eq!(
+    decrypt(&encrypted).unwrap_err(),
+    LicenseError::InvalidPadding
+  );
+}
+
+#[test]
+fn test_runtime_key_from_file_and_env() {
+  let path = std::env::temp_dir().join(format!("license-lib-key-{}.pem", std::process::id()));
+  std::fs::write(&path, PRIVATE_KEY).unwrap();
+  let encrypted = encrypt_with(&get_private_key(), "from file");
+
+  let decryptor = Decryptor::from_file(&path).expect("Key file should load");
+  assert_eq!(decryptor.decrypt(&encrypted).unwrap(), "from file");
+  std::fs::remove_file(&path).unwrap();
+
+  std::env::set_var("LICENSE_LIB_TEST_PRIVATE_KEY", PRIVATE_KEY);
+  let decryptor = Decryptor::from_env("LICENSE_LIB_TEST_PRIVATE_KEY").expect("Env key should load");
+  assert_eq!(decryptor.decrypt(&encrypted).unwrap(), "from file");
+}
+
+#[test]
+fn test_invalid_runtime_key() {
+  // 无效密钥返回 KeyLoad 错误而不是 panic
+  assert!(matches!(
+    Decryptor::from_pem("not a key"),
+    Err(LicenseError::KeyLoad(_))
+  ));
+  assert!(matches!(
+    Decryptor::from_der(b"garbage"),
+    Err(LicenseError::KeyLoad(_))
+  ));
+  assert!(matches!(
+    Decryptor::from_file("/nonexistent/license.pem"),
+    Err(LicenseError::KeyLoad(_))
+  ));
+  assert!(matches!(
+    Decryptor::from_env("LICENSE_LIB_TEST_MISSING_KEY"),
+    Err(LicenseError::KeyLoad(_))
+  ));
+}
+
+#[test]
+fn test_shared_keys_are_cached() {
+  // 共享解密器只解析一次，多次调用返回同一个实例
+  let first = Decryptor::shared().unwrap();
+  let second = Decryptor::shared().unwrap();
+  assert!(std::ptr::eq(first, second));
+  assert_eq!(first.private_key(), &get_private_key());
+
+  let key = VerifyingKey::shared().unwrap();
+  assert!(std::ptr::eq(key, VerifyingKey::shared().unwrap()));
+
+  // 多线程并发使用同一个实例
+  let encrypted = encrypt_with(&get_private_key(), "shared key");
+  let handles: Vec<_> = (0..4)
+    .map(|_| {
+      let encrypted = encrypted.clone();
+      std::thread::spawn(move || {
+        let decryptor = Decryptor::shared().unwrap() as *const Decryptor as usize;
+        (decryptor, decrypt(&encrypted).unwrap())
+      })
+    })
+    .collect();
+  for handle in handles {
+    let (decryptor, text) = handle.join().unwrap();
+    assert_eq!(decryptor, first as *const Decryptor as usize);
+    assert_eq!(text, "shared key");
+  }
+}
@@ -0,0 +1,157 @@
+use base64::prelude::*;
+use lib::signature::SIGNING_PUBLIC_KEY;
+use lib::*;
+use rsa::pkcs8::EncodePublicKey;
+use rsa::signature::{SignatureEncoding, Signer};
+use sha2::Sha256;
+
+fn ed25519_key() -> ed25519_dalek::SigningKey {
+  ed25519_dalek::SigningKey::from_bytes(&[7u8; 32])
+}
+
+// 模拟服务端签名：SIG:<alg>:<base64 payload>:<base64 signature>
+fn sign_ed25519(key: &ed25519_dalek::SigningKey, payload: &str) -> String {
+  let signed_part = format!("SIG:ED25519:{}", BASE64_STANDARD.encode(payload));
+  let signature = key.sign(signed_part.as_bytes());
+  format!(
+    "{}:{}",
+    signed_part,
+    BASE64_STANDARD.encode(signature.to_bytes())
+  )
+}
+
+fn sign_rs256(payload: &str) -> String {
+  let key = rsa::pkcs1v15::SigningKey::<Sha256>::new(get_private_key());
+  let signed_part = format!("SIG:RS256:{}", BASE64_STANDARD.encode(payload));
+  let signature = key.sign(signed_part.as_bytes());
+  format!(
+    "{}:{}",
+    signed_part,
+    BASE64_STANDARD.encode(signature.to_bytes())
+  )
+}
+
+fn payload_json(licensee: &str) -> String {
+  LicensePayload::new(licensee, 1_754_611_200)
+    .to_json()
+    .unwrap()
+}
+
+fn ed25519_verifying_key() -> VerifyingKey {
+  VerifyingKey::Ed25519(ed25519_key().verifying_key())
+}
+
+#[test]
+fn test_verify_ed25519_license() {
+  let payload = payload_json("Beijing Tech Co., Ltd");
+  let token = sign_ed25519(&ed25519_key(), &payload);
+
+  let verified = verify_license_with(&ed25519_verifying_key(), &token).expect("Should verify");
+  assert_eq!(verified.licensee, "Beijing Tech Co., Ltd");
+  assert_eq!(verified.issued_at, 1_754_611_200);
+}
+
+#[test]
+fn test_verify_rsa_license() {
+  let payload = payload_json("RSA 签名的 license");
+  let token = sign_rs256(&payload);
+
+  let public_key_pem = get_private_key()
+    .to_public_key()
+    .to_public_key_pem(Default::default())
+    .unwrap();
+  let key = VerifyingKey::from_public_key_pem(&public_key_pem).unwrap();
+  assert!(matches!(key, VerifyingKey::Rsa(_)));
+
+  let verified = verify_license_with(&key, &token).expect("Should verify");
+  assert_eq!(verified.licensee, "RSA 签名的 license");
+}
+
+#[test]
+fn test_tampered_license_rejected() {
+  let token = sign_ed25519(&ed25519_key(), &payload_json("Tech Co."));
+
+  // 替换 payload，保留原签名
+  let signature = token.rsplit_once(':').unwrap().1;
+  let forged = format!(
+    "SIG:ED25519:{}:{}",
+    BASE64_STANDARD.encode(payload_json("Other Co.")),
+    signature
+  );
+  assert_eq!(
+    verify_license_with(&ed25519_verifying_key(), &forged).unwrap_err(),
+    LicenseError::InvalidSignature
+  );
+
+  // 其他密钥签名的 license 不能通过
+  let other_key = ed25519_dalek::SigningKey::from_bytes(&[9u8; 32]);
+  let token = sign_ed25519(&other_key, &payload_json("Tech Co."));
+  assert_eq!(
+    verify_license_with(&ed25519_verifying_key(), &token).unwrap_err(),
+    LicenseError::InvalidSignature
+  );
+}
+
+#[test]
+fn test_embedded_public_key() {
+  // 内置公钥可以正常加载
+  let key = VerifyingKey::from_public_key_pem(SIGNING_PUBLIC_KEY).unwrap();
+  assert!(matches!(key, VerifyingKey::Ed25519(_)));
+
+  // 测试密钥签名的 license 不能通过内置公钥校验
+  let token = sign_ed25519(&ed25519_key(), &payload_json("hello"));
+  assert_eq!(
+    verify_license(&token).unwrap_err(),
+    LicenseError::InvalidSignature
+  );
+}
+
+#[test]
+fn test_algorithm_mismatch_rejected() {
+  // RS256 token 不能用 Ed25519 公钥校验
+  let token = sign_rs256(&payload_json("hello"));
+  assert_eq!(
+    verify_license_with(&ed25519_verifying_key(), &token).unwrap_err(),
+    LicenseError::UnsupportedAlgorithm("RS256".to_string())
+  );
+
+  assert_eq!(
+    verify_license_with(&ed25519_verifying_key(), "SIG:HS256:aGk=:aGk=").unwrap_err(),
+    LicenseError::UnsupportedAlgorithm("HS256".to_string())
+  );
+}
+
+#[test]
+fn test_malformed_signed_license() {
+  let key = ed25519_verifying_key();
+  assert_eq!(
+    verify_license_with(&key, "").unwrap_err(),
+    LicenseError::EmptyInput
+  );
+  assert!(matches!(
+    verify_license_with(&key, "CHUNK:1:abc").unwrap_err(),
+    LicenseError::InvalidFormat(_)
+  ));
+  assert!(matches!(
+    verify_license_with(&key, "SIG:ED25519").unwrap_err(),
+    LicenseError::InvalidFormat(_)
+  ));
+  assert_eq!(
+    verify_license_with(&key, "SIG:ED25519:!!!:aGk=").unwrap_err(),
+    LicenseError::InvalidBase64
+  );
+  assert_eq!(
+    verify_license_with(&key, "SIG:ED25519:aGk=:aGk=").unwrap_err(),
+    LicenseError::InvalidSignature
+  );
+}
+
+#[test]
+fn test_signed_payload_must_be_json() {
+  // 签名正确但内容不是 LicensePayload 时返回 InvalidPayload
+  let token = sign_ed25519(&ed25519_key(), "not json");
+  assert!(matches!(
+    verify_license_with(&ed25519_verifying_key(), &token).unwrap_err(),
+    LicenseError::InvalidPayload(_)
+  ));
+}
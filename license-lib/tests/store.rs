@@ -0,0 +1,181 @@
+use std::cell::Cell;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use lib::store::STORE_FILE_NAME;
+use lib::*;
+
+const NOW: i64 = 1_754_611_200;
+
+// 可调整的时钟，模拟时间前进和回拨
+#[derive(Clone)]
+struct TestClock(Rc<Cell<i64>>);
+
+impl Clock for TestClock {
+  fn now(&self) -> i64 {
+    self.0.get()
+  }
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("license-store-{}-{}", name, std::process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  dir
+}
+
+fn office_pc() -> Fingerprint {
+  Fingerprint::new()
+    .with_component("machine_id", "4c4c4544003a")
+    .with_component("hostname", "office-pc")
+}
+
+fn store(dir: &PathBuf, time: &Rc<Cell<i64>>) -> LicenseStore<TestClock> {
+  LicenseStore::with_clock(dir, TestClock(time.clone())).with_fingerprint(office_pc())
+}
+
+#[test]
+fn test_save_and_reload() {
+  let dir = temp_dir("reload");
+  let time = Rc::new(Cell::new(NOW));
+  let store = store(&dir, &time);
+  assert_eq!(store.load(), Ok(None));
+
+  let license = "license-token";
+  let saved = store.save(license, Some("activation")).unwrap();
+  assert_eq!(saved.saved_at, NOW);
+  assert!(dir.join(STORE_FILE_NAME).exists());
+
+  // 重启后重新加载，最后时间随之前进
+  time.set(NOW + 3600);
+  let loaded = store.load().unwrap().unwrap();
+  assert_eq!(loaded.license, license);
+  assert_eq!(loaded.activation.as_deref(), Some("activation"));
+  assert_eq!(loaded.saved_at, NOW);
+  assert_eq!(loaded.last_seen, NOW + 3600);
+
+  store.clear().unwrap();
+  assert_eq!(store.load(), Ok(None));
+  store.clear().unwrap();
+}
+
+#[cfg(feature = "issuer")]
+#[test]
+fn test_decode_stored_license() {
+  use lib::issuer::Encryptor;
+  use lib::keys::PrivateKey;
+
+  let dir = temp_dir("decode");
+  let time = Rc::new(Cell::new(NOW));
+  let store = store(&dir, &time);
+
+  let public_key = PrivateKey::Rsa(get_private_key()).public_key();
+  let license = Encryptor::from_pem(&public_key.to_spki_pem().unwrap())
+    .unwrap()
+    .encrypt_payload(&LicensePayload::new("Stored Co.", NOW))
+    .unwrap();
+  store.save(&license, None).unwrap();
+
+  let loaded = store.load().unwrap().unwrap();
+  let keyring = Keyring::with_default_keys().unwrap();
+  assert_eq!(loaded.decode(&keyring).unwrap().licensee, "Stored Co.");
+  assert_eq!(loaded.verify_activation(&keyring), Ok(None));
+}
+
+#[test]
+fn test_tamper_detection() {
+  let dir = temp_dir("tamper");
+  let time = Rc::new(Cell::new(NOW));
+  let store = store(&dir, &time);
+  store.save("license-token", None).unwrap();
+
+  // 改动文件内容
+  let path = dir.join(STORE_FILE_NAME);
+  let original = fs::read_to_string(&path).unwrap();
+  fs::write(&path, original.replace("license-token", "forged-token")).unwrap();
+  assert_eq!(store.load(), Err(LicenseError::StoreTampered));
+
+  // 改回最后时间以绕过回拨检查
+  fs::write(&path, original.replace(&NOW.to_string(), "0")).unwrap();
+  assert_eq!(store.load(), Err(LicenseError::StoreTampered));
+
+  fs::write(&path, "not json").unwrap();
+  assert_eq!(store.load(), Err(LicenseError::StoreTampered));
+
+  // 复制到其他机器
+  fs::write(&path, &original).unwrap();
+  let laptop = Fingerprint::new().with_component("machine_id", "1234");
+  assert_eq!(
+    LicenseStore::with_clock(&dir, TestClock(time.clone()))
+      .with_fingerprint(laptop)
+      .load(),
+    Err(LicenseError::StoreTampered)
+  );
+  assert_eq!(store.load().unwrap().unwrap().license, "license-token");
+}
+
+#[test]
+fn test_clock_rollback() {
+  let dir = temp_dir("rollback");
+  let time = Rc::new(Cell::new(NOW));
+  let store = store(&dir, &time);
+  store.save("license-token", None).unwrap();
+
+  time.set(NOW + 86400);
+  store.load().unwrap();
+
+  // 时间回拨到上次看到之前
+  time.set(NOW + 3600);
+  let error = store.load().unwrap_err();
+  assert_eq!(error, LicenseError::ClockRollback(NOW + 86400));
+  assert_eq!(error.code(), "LICENSE_CLOCK_ROLLBACK");
+
+  // 重新保存不会重置最后时间
+  let saved = store.save("license-token", None).unwrap();
+  assert_eq!(saved.last_seen, NOW + 86400);
+  assert!(store.load().is_err());
+
+  // 允许一定的偏差
+  let lenient = self::store(&dir, &time).with_clock_skew(86400).unwrap();
+  assert_eq!(lenient.load().unwrap().unwrap().last_seen, NOW + 86400);
+
+  time.set(NOW + 86400 * 2);
+  assert_eq!(store.load().unwrap().unwrap().last_seen, NOW + 86400 * 2);
+
+  // 偏差不能为负数，过大的偏差饱和计算
+  assert_eq!(
+    self::store(&dir, &time).with_clock_skew(-1).err(),
+    Some(LicenseError::InvalidOptions(
+      "Clock skew cannot be negative"
+    ))
+  );
+  let unlimited = self::store(&dir, &time).with_clock_skew(i64::MAX).unwrap();
+  time.set(NOW);
+  assert!(unlimited.load().is_ok());
+}
+
+#[test]
+fn test_fingerprint_component_changed() {
+  let dir = temp_dir("component");
+  let time = Rc::new(Cell::new(NOW));
+  store(&dir, &time).save("license-token", None).unwrap();
+
+  // 改了主机名，与 license 的机器绑定一样允许一个组件变化
+  let renamed = office_pc().with_component("hostname", "office-pc-2");
+  let store = LicenseStore::with_clock(&dir, TestClock(time.clone())).with_fingerprint(renamed);
+  assert_eq!(store.load().unwrap().unwrap().license, "license-token");
+
+  // 加载后按新指纹重新计算，之后再换网卡也不会累积成两个组件变化
+  let rewired = Fingerprint::new()
+    .with_component("machine_id", "4c4c4544003a")
+    .with_component("hostname", "office-pc-2")
+    .with_component("mac", "00:11:22:33:44:55");
+  let store = LicenseStore::with_clock(&dir, TestClock(time.clone())).with_fingerprint(rewired);
+  assert_eq!(store.load().unwrap().unwrap().license, "license-token");
+
+  // 不允许变化时拒绝
+  let strict = LicenseStore::with_clock(&dir, TestClock(time.clone()))
+    .with_fingerprint(office_pc())
+    .with_fingerprint_tolerance(0);
+  assert_eq!(strict.load(), Err(LicenseError::StoreTampered));
+}
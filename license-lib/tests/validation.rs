@@ -0,0 +1,192 @@
+use lib::*;
+
+const DAY: i64 = 24 * 60 * 60;
+const ISSUED_AT: i64 = 1_754_611_200; // 2025-08-08
+
+fn payload(expires_in_days: Option<i64>) -> LicensePayload {
+  let mut payload = LicensePayload::new("Beijing Tech Co., Ltd", ISSUED_AT);
+  payload.expires_at = expires_in_days.map(|days| ISSUED_AT + days * DAY);
+  payload
+}
+
+#[test]
+fn test_valid_license() {
+  let payload = payload(Some(365));
+  assert_eq!(validate(&payload, ISSUED_AT), Ok(Validity::Valid));
+  assert_eq!(
+    validate(&payload, ISSUED_AT + 100 * DAY),
+    Ok(Validity::Valid)
+  );
+
+  // 没有过期时间即永久有效
+  let forever = self::payload(None);
+  assert_eq!(
+    validate(&forever, ISSUED_AT + 10_000 * DAY),
+    Ok(Validity::Valid)
+  );
+}
+
+#[test]
+fn test_expired_license() {
+  let payload = payload(Some(365));
+  let expires_at = ISSUED_AT + 365 * DAY;
+
+  // 过期时间点本身即视为过期
+  assert_eq!(
+    validate(&payload, expires_at),
+    Err(LicenseError::Expired(expires_at))
+  );
+  assert_eq!(
+    validate(&payload, expires_at + DAY),
+    Err(LicenseError::Expired(expires_at))
+  );
+  assert_eq!(validate(&payload, expires_at - 1), Ok(Validity::Valid));
+}
+
+#[test]
+fn test_not_yet_valid_license() {
+  let mut payload = payload(Some(365));
+  payload.not_before = Some(ISSUED_AT + 30 * DAY);
+
+  assert_eq!(
+    validate(&payload, ISSUED_AT),
+    Err(LicenseError::NotYetValid(ISSUED_AT + 30 * DAY))
+  );
+  assert_eq!(
+    validate(&payload, ISSUED_AT + 30 * DAY),
+    Ok(Validity::Valid)
+  );
+
+  // 未设置 not_before 时，签发时间之前也不可用
+  let payload = self::payload(Some(365));
+  assert_eq!(
+    validate(&payload, ISSUED_AT - 1),
+    Err(LicenseError::NotYetValid(ISSUED_AT))
+  );
+}
+
+#[test]
+fn test_grace_period() {
+  let payload = payload(Some(365));
+  let expires_at = ISSUED_AT + 365 * DAY;
+  let options = ValidationOptions {
+    grace_period: 7 * DAY,
+    clock_skew: 0,
+  };
+
+  assert_eq!(
+    options.validate(&payload, expires_at + 3 * DAY),
+    Ok(Validity::InGracePeriod { expires_at })
+  );
+  assert_eq!(
+    options.validate(&payload, expires_at + 7 * DAY),
+    Err(LicenseError::Expired(expires_at))
+  );
+}
+
+#[test]
+fn test_clock_skew_tolerance() {
+  let payload = payload(Some(365));
+  let expires_at = ISSUED_AT + 365 * DAY;
+  let options = ValidationOptions {
+    grace_period: 0,
+    clock_skew: 300,
+  };
+
+  // 本地时钟慢了几分钟，刚签发的 license 仍然可用
+  assert_eq!(
+    options.validate(&payload, ISSUED_AT - 200),
+    Ok(Validity::Valid)
+  );
+  assert_eq!(
+    options.validate(&payload, ISSUED_AT - 301),
+    Err(LicenseError::NotYetValid(ISSUED_AT))
+  );
+
+  // 本地时钟快了几分钟，不会提前过期
+  assert_eq!(
+    options.validate(&payload, expires_at + 200),
+    Ok(Validity::Valid)
+  );
+  assert_eq!(
+    options.validate(&payload, expires_at + 300),
+    Err(LicenseError::Expired(expires_at))
+  );
+}
+
+#[test]
+fn test_validator_with_injected_clock() {
+  let payload = payload(Some(30));
+  let options = ValidationOptions::default();
+
+  let validator = Validator::with_clock(options, FixedClock(ISSUED_AT + DAY));
+  assert_eq!(validator.validate(&payload), Ok(Validity::Valid));
+
+  let validator = Validator::with_clock(options, FixedClock(ISSUED_AT + 31 * DAY));
+  assert_eq!(
+    validator.validate(&payload),
+    Err(LicenseError::Expired(ISSUED_AT + 30 * DAY))
+  );
+
+  // 系统时钟：2025-08-08 签发、30 天有效的 license 早已过期
+  let validator = Validator::new(options);
+  assert!(validator.validate(&payload).is_err());
+  assert!(SystemClock.now() > ISSUED_AT);
+}
+
+#[test]
+fn test_validation_error_codes() {
+  assert_eq!(LicenseError::Expired(0).code(), "LICENSE_EXPIRED");
+  assert_eq!(LicenseError::NotYetValid(0).code(), "LICENSE_NOT_YET_VALID");
+}
+
+#[test]
+fn test_extreme_times_do_not_overflow() {
+  let options = ValidationOptions::new(30 * DAY, 5 * 60).unwrap();
+
+  // i64::MAX 表示永不过期
+  let mut payload = payload(None);
+  payload.expires_at = Some(i64::MAX);
+  assert_eq!(options.validate(&payload, i64::MAX), Ok(Validity::Valid));
+  assert_eq!(
+    validate(&payload, i64::MAX),
+    Err(LicenseError::Expired(i64::MAX))
+  );
+
+  payload.expires_at = Some(ISSUED_AT);
+  assert_eq!(
+    options.validate(&payload, i64::MIN),
+    Err(LicenseError::NotYetValid(ISSUED_AT))
+  );
+  payload.issued_at = i64::MIN;
+  assert_eq!(
+    options.validate(&payload, i64::MAX),
+    Err(LicenseError::Expired(ISSUED_AT))
+  );
+}
+
+#[test]
+fn test_negative_options_are_rejected() {
+  assert_eq!(
+    ValidationOptions::new(-1, 0),
+    Err(LicenseError::InvalidOptions(
+      "Grace period cannot be negative"
+    ))
+  );
+  assert_eq!(
+    ValidationOptions::new(0, -1).unwrap_err().code(),
+    "LICENSE_BAD_OPTIONS"
+  );
+
+  // 直接构造的负数选项按 0 处理
+  let options = ValidationOptions {
+    grace_period: -DAY,
+    clock_skew: -DAY,
+  };
+  let payload = payload(Some(1));
+  assert_eq!(options.validate(&payload, ISSUED_AT), Ok(Validity::Valid));
+  assert_eq!(
+    options.validate(&payload, ISSUED_AT + DAY),
+    Err(LicenseError::Expired(ISSUED_AT + DAY))
+  );
+}
@@ -0,0 +1,10 @@
+-- 管理接口的 API key，只保存 SHA-256，明文只在创建时返回一次
+-- scopes 为逗号分隔的 read、issue、revoke、admin
+CREATE TABLE IF NOT EXISTS ApiKey (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  name TEXT NOT NULL UNIQUE,
+  keyHash TEXT NOT NULL UNIQUE,
+  scopes TEXT NOT NULL,
+  createdAt INTEGER NOT NULL,
+  revokedAt INTEGER
+);
@@ -0,0 +1,66 @@
+-- LicenseCode 之前只在 D1 控制台手工建表：name、code、createdAt（文本）、activated
+-- 新数据库先按旧结构建表，再和已有数据库一起重建为新结构
+
+CREATE TABLE IF NOT EXISTS LicenseCode (
+  name TEXT NOT NULL,
+  code TEXT NOT NULL,
+  createdAt TEXT,
+  activated INTEGER NOT NULL DEFAULT 0
+);
+
+-- 时间均为 Unix 秒；status 为 active、suspended、revoked
+-- createdBy 为创建它的 API key 名称
+CREATE TABLE LicenseCode_new (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  name TEXT NOT NULL,
+  code TEXT NOT NULL,
+  createdAt INTEGER NOT NULL,
+  updatedAt INTEGER NOT NULL,
+  activated INTEGER NOT NULL DEFAULT 0,
+  status TEXT NOT NULL DEFAULT 'active' CHECK (status IN ('active', 'suspended', 'revoked')),
+  expiredAt INTEGER,
+  remark TEXT,
+  createdBy TEXT
+);
+
+-- 旧的 createdAt 可能是秒、毫秒或日期文本，都转成 Unix 秒，无法识别时取迁移时间
+INSERT INTO LicenseCode_new (name, code, createdAt, updatedAt, activated)
+SELECT
+  name,
+  code,
+  created_at,
+  created_at,
+  COALESCE(activated, 0)
+FROM (
+  SELECT
+    name,
+    code,
+    activated,
+    COALESCE(
+      CASE
+        WHEN createdAt GLOB '[0-9]*' AND createdAt NOT GLOB '*[^0-9]*' AND length(createdAt) > 11
+          THEN CAST(createdAt AS INTEGER) / 1000
+        WHEN createdAt GLOB '[0-9]*' AND createdAt NOT GLOB '*[^0-9]*' THEN CAST(createdAt AS INTEGER)
+        ELSE CAST(strftime('%s', createdAt) AS INTEGER)
+      END,
+      CAST(strftime('%s', 'now') AS INTEGER)
+    ) AS created_at
+  FROM LicenseCode
+  ORDER BY rowid
+);
+
+DROP TABLE LicenseCode;
+ALTER TABLE LicenseCode_new RENAME TO LicenseCode;
+
+-- 旧表没有唯一约束，可能有重复的 name 或 code，直接建唯一索引会让迁移失败
+-- 每组重复保留最早的一行，其余行改名为 `<原值>#<id>` 并在 remark 中注明，不删除数据，由管理员之后处理
+UPDATE LicenseCode
+SET name = name || '#' || id, remark = 'Duplicate name renamed by migration 0002'
+WHERE id NOT IN (SELECT MIN(id) FROM LicenseCode GROUP BY name);
+UPDATE LicenseCode
+SET code = code || '#' || id,
+  remark = COALESCE(remark || '; ', '') || 'Duplicate code renamed by migration 0002'
+WHERE id NOT IN (SELECT MIN(id) FROM LicenseCode GROUP BY code);
+
+CREATE UNIQUE INDEX LicenseCode_name ON LicenseCode (name);
+CREATE UNIQUE INDEX LicenseCode_code ON LicenseCode (code);
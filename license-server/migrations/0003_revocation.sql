@@ -0,0 +1,27 @@
+-- 吊销 license 时记录时间和原因，status 同时改为 revoked
+ALTER TABLE LicenseCode ADD COLUMN revokedAt INTEGER;
+ALTER TABLE LicenseCode ADD COLUMN revokeReason TEXT;
+
+-- 吊销的签发密钥，keyId 为 license 容器头部的 key id，用它签发的 license 全部失效
+CREATE TABLE IF NOT EXISTS RevokedKey (
+  keyId TEXT PRIMARY KEY,
+  revokedAt INTEGER NOT NULL,
+  reason TEXT
+);
+
+-- 吊销是最终状态：/crl 按 status = 'revoked' 生成，删除或恢复吊销的 license 会让它从新的吊销列表中消失，
+-- 用新列表替换缓存的客户端随之不再拒绝它，因此在数据库中禁止这两种修改
+-- name 即吊销列表中的 license id，同样不能修改
+CREATE TRIGGER IF NOT EXISTS LicenseCode_revoked_delete
+BEFORE DELETE ON LicenseCode
+WHEN OLD.status = 'revoked'
+BEGIN
+  SELECT RAISE(ABORT, 'License has been revoked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS LicenseCode_revoked_update
+BEFORE UPDATE OF status, name ON LicenseCode
+WHEN OLD.status = 'revoked' AND (NEW.status != 'revoked' OR NEW.name != OLD.name)
+BEGIN
+  SELECT RAISE(ABORT, 'License has been revoked');
+END;
@@ -0,0 +1,17 @@
+-- 每个 license 的激活数上限，NULL 为不限
+ALTER TABLE LicenseCode ADD COLUMN maxActivations INTEGER;
+
+-- 激活记录，一台机器占用一个席位，同一台机器重复激活不再计数
+-- fingerprint 为客户端 Fingerprint::id()，旧客户端不上传时为 NULL，每次激活都占用席位
+-- 迁移前的激活只有 activated 计数，没有记录，无法单独释放
+CREATE TABLE IF NOT EXISTS Activation (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  licenseId INTEGER NOT NULL REFERENCES LicenseCode (id) ON DELETE CASCADE,
+  fingerprint TEXT,
+  ip TEXT,
+  userAgent TEXT,
+  createdAt INTEGER NOT NULL,
+  lastSeenAt INTEGER NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS Activation_license_fingerprint ON Activation (licenseId, fingerprint);
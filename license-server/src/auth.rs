@@ -0,0 +1,158 @@
+use base64::prelude::*;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// 管理 API key 的前缀，方便在日志或配置中识别
+pub const API_KEY_PREFIX: &str = "blk_";
+
+// API key 的权限范围，admin 包含其他所有权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+  // 查询 license
+  Read,
+  // 新建、修改 license，加密、签名
+  Issue,
+  // 删除、吊销 license
+  Revoke,
+  // 管理 API key
+  Admin,
+}
+
+impl Scope {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Scope::Read => "read",
+      Scope::Issue => "issue",
+      Scope::Revoke => "revoke",
+      Scope::Admin => "admin",
+    }
+  }
+
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name.trim() {
+      "read" => Some(Scope::Read),
+      "issue" => Some(Scope::Issue),
+      "revoke" => Some(Scope::Revoke),
+      "admin" => Some(Scope::Admin),
+      _ => None,
+    }
+  }
+}
+
+// 逗号分隔的权限列表，如 "read,issue"
+pub fn parse_scopes(text: &str) -> Result<Vec<Scope>, String> {
+  text
+    .split(',')
+    .filter(|name| !name.trim().is_empty())
+    .map(|name| Scope::from_name(name).ok_or_else(|| format!("Unknown scope: {}", name.trim())))
+    .collect()
+}
+
+pub fn format_scopes(scopes: &[Scope]) -> String {
+  scopes.iter().map(Scope::name).collect::<Vec<_>>().join(",")
+}
+
+// 数据库存档，只保存 key 的 SHA-256，明文只在创建时返回一次
+#[derive(Deserialize, Serialize)]
+#[allow(non_snake_case)]
+pub struct ApiKey {
+  pub name: String,
+  // 只在 SQL 中按哈希查找，代码里不读取
+  #[serde(skip_serializing)]
+  #[allow(dead_code)]
+  pub keyHash: String,
+  pub scopes: String,
+  // Unix 秒
+  pub createdAt: i64,
+  pub revokedAt: Option<i64>,
+}
+
+// 认证失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthError {
+  // 没有 Authorization: Bearer 头
+  Missing,
+  // key 不存在
+  Invalid,
+  // key 已吊销
+  Revoked,
+  // key 没有要求的权限
+  Forbidden(Scope),
+}
+
+impl AuthError {
+  pub fn code(&self) -> &'static str {
+    match self {
+      AuthError::Missing => "AUTH_MISSING",
+      AuthError::Invalid => "AUTH_INVALID",
+      AuthError::Revoked => "AUTH_REVOKED",
+      AuthError::Forbidden(_) => "AUTH_FORBIDDEN",
+    }
+  }
+
+  pub fn status(&self) -> u16 {
+    match self {
+      AuthError::Forbidden(_) => 403,
+      _ => 401,
+    }
+  }
+
+  pub fn message(&self) -> String {
+    match self {
+      AuthError::Missing => "Missing Authorization: Bearer header".to_string(),
+      AuthError::Invalid => "Invalid API key".to_string(),
+      AuthError::Revoked => "API key has been revoked".to_string(),
+      AuthError::Forbidden(scope) => format!("API key lacks scope: {}", scope.name()),
+    }
+  }
+}
+
+// 生成新的 API key：前缀 + 32 字节随机数的 base64url
+pub fn generate_api_key() -> String {
+  let mut bytes = [0u8; 32];
+  OsRng.fill_bytes(&mut bytes);
+  format!("{}{}", API_KEY_PREFIX, BASE64_URL_SAFE_NO_PAD.encode(bytes))
+}
+
+// 数据库中保存的 key 哈希，SHA-256 的十六进制
+pub fn hash_api_key(key: &str) -> String {
+  lib::hex::encode(&Sha256::digest(key.as_bytes()))
+}
+
+// 取出 `Authorization: Bearer <key>` 中的 key
+pub fn bearer_token(header: Option<&str>) -> Option<&str> {
+  let (scheme, token) = header?.trim().split_once(' ')?;
+  if !scheme.eq_ignore_ascii_case("bearer") {
+    return None;
+  }
+  let token = token.trim();
+  (!token.is_empty()).then_some(token)
+}
+
+// 检查 key 未吊销且有要求的权限
+pub fn check_key(key: &ApiKey, required: Scope) -> Result<(), AuthError> {
+  if key.revokedAt.is_some() {
+    return Err(AuthError::Revoked);
+  }
+  let scopes = parse_scopes(&key.scopes).map_err(|_| AuthError::Invalid)?;
+  if scopes
+    .iter()
+    .any(|scope| *scope == Scope::Admin || *scope == required)
+  {
+    Ok(())
+  } else {
+    Err(AuthError::Forbidden(required))
+  }
+}
+
+// 比较哈希时不因提前返回泄露匹配的长度
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+  a.len() == b.len()
+    && a
+      .bytes()
+      .zip(b.bytes())
+      .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+      == 0
+}
@@ -1,9 +1,9 @@
-use base64::prelude::*;
-use rsa::pkcs8::DecodePublicKey;
-use rsa::{rand_core::OsRng, traits::PublicKeyParts, Pkcs1v15Encrypt, RsaPublicKey};
+use lib::issuer::{Encryptor, SigningKey};
+use lib::{ActivationRequest, ActivationResponse, RevocationList};
+use std::sync::OnceLock;
 
 // 定义 RSA 公钥（公钥可以多个，通常从私钥生成）
-// 公钥用于加密，不放客户端
+// 公钥用于加密，不放客户端；容器头部带上由公钥推导的 key id，客户端 Keyring 据此选择私钥
 pub const PUBLIC_KEY: &str = r#"-----BEGIN PUBLIC KEY-----
 MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEA8lT2ZEzIope4BlJQEl+4
 w5g2sUqyyuwNG/zu/iH/vGcf5tgq1zZmWj0+NztZ3LiYzCZwpbYBEpC6QDDvA9Xp
@@ -15,73 +15,63 @@ fwIDAQAB
 -----END PUBLIC KEY-----
 "#;
 
-fn get_public_key() -> RsaPublicKey {
-  RsaPublicKey::from_public_key_pem(PUBLIC_KEY).unwrap()
-}
+static SHARED_ENCRYPTOR: OnceLock<Encryptor> = OnceLock::new();
 
-// 动态计算最大消息长度
-fn get_max_message_length() -> usize {
-  let public_key = get_public_key();
-  // PKCS1v15 填充：最大明文长度 = 密钥长度(字节) - 11
-  public_key.size() - 11
+// 内置 PUBLIC_KEY 的共享加密器，首次使用时解析；加密实现在 lib::issuer
+pub fn shared_encryptor() -> &'static Encryptor {
+  SHARED_ENCRYPTOR
+    .get_or_init(|| Encryptor::from_pem(PUBLIC_KEY).expect("PUBLIC_KEY should be valid"))
 }
 
+// 使用内置 PUBLIC_KEY 混合加密，见 lib::issuer::Encryptor::encrypt
 pub fn encrypt(message: &str) -> Result<String, String> {
-  println!("Encrypting message: {}", message);
-
-  let max_length = get_max_message_length();
-
-  // If message fits in single block, use original format for backward compatibility
-  if message.len() <= max_length {
-    return encrypt_single_block(message);
-  }
-
-  // Use chunked format for longer messages
-  encrypt_chunked(message)
+  shared_encryptor()
+    .encrypt(message)
+    .map_err(|e| e.to_string())
 }
 
-fn encrypt_single_block(message: &str) -> Result<String, String> {
-  let public_key = get_public_key();
-  let mut rng = OsRng;
-
-  match public_key.encrypt(&mut rng, Pkcs1v15Encrypt, message.as_bytes()) {
-    Ok(encrypted_message) => Ok(BASE64_STANDARD.encode(encrypted_message)),
-    Err(e) => Err(format!("Encryption failed: {}", e)),
-  }
+// 使用内置 PUBLIC_KEY 做 RSA-OAEP 加密，见 lib::issuer::Encryptor::encrypt_rsa_oaep
+pub fn encrypt_rsa_oaep(message: &str) -> Result<String, String> {
+  shared_encryptor()
+    .encrypt_rsa_oaep(message)
+    .map_err(|e| e.to_string())
 }
 
-fn encrypt_chunked(message: &str) -> Result<String, String> {
-  let max_length = get_max_message_length();
-  let public_key = get_public_key();
-  let mut rng = OsRng;
-
-  // Split message into chunks
-  let message_bytes = message.as_bytes();
-  let chunks: Vec<&[u8]> = message_bytes.chunks(max_length).collect();
-  let chunk_count = chunks.len();
-
-  if chunk_count > 100 {
-    // Reasonable limit
-    return Err(format!(
-      "Message too long: {} chunks, maximum 100 allowed",
-      chunk_count
-    ));
-  }
-
-  let mut encrypted_chunks = Vec::new();
+// 使用签名私钥（PKCS#8 PEM，Ed25519 或 RSA）对 license 签名
+// 私钥来自 worker secret LICENSE_SIGNING_KEY，不进代码仓库
+// 见 lib::issuer::SigningKey::sign
+pub fn sign(message: &str, signing_key_pem: &str) -> Result<String, String> {
+  let signing_key = match SigningKey::from_pkcs8_pem(signing_key_pem) {
+    Ok(signing_key) => signing_key,
+    Err(e) => return Err(format!("Invalid signing key: {}", e)),
+  };
+  signing_key.sign(message).map_err(|e| e.to_string())
+}
 
-  for chunk in chunks {
-    match public_key.encrypt(&mut rng, Pkcs1v15Encrypt, chunk) {
-      Ok(encrypted_chunk) => {
-        encrypted_chunks.push(BASE64_STANDARD.encode(encrypted_chunk));
-      }
-      Err(e) => {
-        return Err(format!("Chunk encryption failed: {}", e));
-      }
-    }
-  }
+// 离线激活：确认激活请求并用签名私钥签名激活响应
+// 见 lib::activation::ActivationResponse::sign
+pub fn sign_activation(
+  request: &ActivationRequest,
+  now: i64,
+  signing_key_pem: &str,
+) -> Result<String, String> {
+  let signing_key = match SigningKey::from_pkcs8_pem(signing_key_pem) {
+    Ok(signing_key) => signing_key,
+    Err(e) => return Err(format!("Invalid signing key: {}", e)),
+  };
+  ActivationResponse::for_request(request, now)
+    .sign(&signing_key)
+    .map_err(|e| e.to_string())
+}
 
-  // Format: CHUNK:count:block1|block2|...
-  let result = format!("CHUNK:{}:{}", chunk_count, encrypted_chunks.join("|"));
-  Ok(result)
+// 吊销列表：用签名私钥签名，见 lib::revocation::RevocationList::sign
+pub fn sign_revocation_list(
+  list: &RevocationList,
+  signing_key_pem: &str,
+) -> Result<String, String> {
+  let signing_key = match SigningKey::from_pkcs8_pem(signing_key_pem) {
+    Ok(signing_key) => signing_key,
+    Err(e) => return Err(format!("Invalid signing key: {}", e)),
+  };
+  list.sign(&signing_key).map_err(|e| e.to_string())
 }
@@ -2,41 +2,191 @@ use serde::{Deserialize, Serialize};
 use worker::*;
 // use log::{info};
 
-use lib::decrypt;
+use lib::{decrypt, ActivationRequest, LicenseError};
+
+mod auth;
+use auth::{
+  bearer_token, check_key, constant_time_eq, format_scopes, generate_api_key, hash_api_key,
+  parse_scopes, ApiKey, AuthError, Scope,
+};
 
 mod crypto;
-use crypto::encrypt;
+use crypto::{encrypt, encrypt_rsa_oaep, sign, sign_activation, sign_revocation_list};
+
+mod license;
+use license::{
+  revocation_list, ActivateLicenseRequest, Activation, ActivationOutcome, CreateLicenseRequest,
+  DeactivateLicenseRequest, LicenseCode, PublicLicense, RevokeLicenseRequest, RevokedKeyRecord,
+  UpdateLicenseRequest, ACTIVATION_STATEMENTS, DEACTIVATION_STATEMENTS, REVOKED_LICENSE_ERROR,
+};
 
 #[cfg(test)]
 mod tests;
 macro_rules! log {
     ($($t:tt)*) => (web_sys::console::log_1(&format!($($t)*).into()))
 }
-// 数据库存档
-#[derive(Deserialize, Serialize)]
-struct LicenseCode {
+// 新建 API key 的请求body
+#[derive(Deserialize)]
+struct CreateApiKeyRequest {
+  name: String,
+  // 逗号分隔，如 "read,issue"
+  scopes: String,
+}
+
+// 新建 API key 的响应，明文 key 只在这里返回一次
+#[derive(Serialize)]
+struct CreatedApiKey {
   name: String,
-  code: String,
-  #[allow(non_snake_case)]
-  createdAt: String,
-  activated: u32,
-  // expiredAt: Option<String>,
-  // remark: Option<String>,
-  // createdBy: Option<String>,
+  scopes: String,
+  key: String,
 }
 
 // 请求加密body
 #[derive(Deserialize, Serialize)]
 struct EncryptRequest {
   s: String,
+  // 加密算法："hybrid"（默认，RSA-OAEP + AES-256-GCM）或 "rsa-oaep"（仅 RSA-OAEP）
+  // PKCS#1 v1.5 已不再用于签发
+  #[serde(default)]
+  algorithm: Option<String>,
+  // 旧客户端用 true 请求 PKCS#1 v1.5 格式，现在返回 400，不再静默改用其他格式
+  #[serde(default)]
+  legacy: bool,
+}
+
+// 解密错误响应体
+#[derive(Serialize)]
+struct ErrorBody {
+  code: &'static str,
+  message: String,
+}
+
+// 将 LicenseError 转为带稳定错误码的 JSON 响应
+fn license_error_response(error: LicenseError) -> Result<Response> {
+  let body = ErrorBody {
+    code: error.code(),
+    message: format!("Decryption error: {}", error),
+  };
+  Ok(Response::from_json(&body)?.with_status(400))
+}
+
+// 管理接口认证：`Authorization: Bearer <API key>`
+// key 来自 ApiKey 表（只存 SHA-256），或 secret LICENSE_ADMIN_API_KEY（拥有全部权限，用于创建第一个 key）
+// 通过时返回 None，否则返回 401/403 响应
+async fn authorize(req: &Request, env: &Env, scope: Scope) -> Result<Option<Response>> {
+  Ok(authenticate(req, env, scope).await?.err())
+}
+
+// 同 `authorize`，通过时返回 key 的名称，secret LICENSE_ADMIN_API_KEY 的名称为 admin
+async fn authenticate(
+  req: &Request,
+  env: &Env,
+  scope: Scope,
+) -> Result<std::result::Result<String, Response>> {
+  let header = req.headers().get("Authorization")?;
+  let token = match bearer_token(header.as_deref()) {
+    Some(token) => token,
+    None => return auth_error_response(AuthError::Missing).map(Err),
+  };
+  let key_hash = hash_api_key(token);
+
+  if let Ok(secret) = env.secret("LICENSE_ADMIN_API_KEY") {
+    if constant_time_eq(&key_hash, &hash_api_key(&secret.to_string())) {
+      return Ok(Ok(Scope::Admin.name().to_string()));
+    }
+  }
+
+  let d1 = env.d1("license-server")?;
+  let statement = d1.prepare("SELECT * FROM ApiKey WHERE keyHash = ?1");
+  let query = statement.bind(&[key_hash.into()])?;
+  match query.first::<ApiKey>(None).await? {
+    Some(key) => match check_key(&key, scope) {
+      Ok(()) => Ok(Ok(key.name)),
+      Err(error) => auth_error_response(error).map(Err),
+    },
+    None => auth_error_response(AuthError::Invalid).map(Err),
+  }
 }
 
-// 检查LICENSE_SERVER_ACCESS_KEY是否正确
-fn check_access(license_server_access_key: &str) -> bool {
-  if license_server_access_key == "WMDkgEGbKHkCFm6KK" {
-    return true;
+fn auth_error_response(error: AuthError) -> Result<Response> {
+  let body = ErrorBody {
+    code: error.code(),
+    message: error.message(),
+  };
+  let mut response = Response::from_json(&body)?.with_status(error.status());
+  if error.status() == 401 {
+    response.headers_mut().set("WWW-Authenticate", "Bearer")?;
+  }
+  Ok(response)
+}
+
+fn now_seconds() -> i64 {
+  (Date::now().as_millis() / 1000) as i64
+}
+
+// 可选字段绑定为 NULL
+fn nullable<T: Into<wasm_bindgen::JsValue>>(value: Option<T>) -> wasm_bindgen::JsValue {
+  value.map_or(wasm_bindgen::JsValue::NULL, Into::into)
+}
+
+// 新建 API key 违反 name 唯一约束时返回 409
+fn api_key_write_error(error: Error) -> Result<Response> {
+  if error.to_string().contains("UNIQUE constraint failed") {
+    Response::error("API key name already exists", 409)
+  } else {
+    Err(error)
+  }
+}
+
+// 新建、修改 license 违反 name/code 唯一索引时返回 409
+// 修改或删除吊销的 license 被 migrations/0003_revocation.sql 的触发器拒绝时同样返回 409
+fn license_write_error(error: Error) -> Result<Response> {
+  let message = error.to_string();
+  if message.contains("UNIQUE constraint failed") {
+    Response::error("License name or code already exists", 409)
+  } else if message.contains(REVOKED_LICENSE_ERROR) {
+    Response::error(REVOKED_LICENSE_ERROR, 409)
+  } else {
+    Err(error)
+  }
+}
+
+// 为机器占用一个席位，记录 IP 和 User-Agent，语句见 license::ACTIVATION_STATEMENTS
+// 已激活的机器只更新激活记录，新机器在席位已满时返回 403，通过时返回激活后的 license
+async fn activate_seat(
+  d1: &D1Database,
+  req: &Request,
+  thing: &LicenseCode,
+  fingerprint: Option<&str>,
+  now: i64,
+) -> Result<std::result::Result<LicenseCode, Response>> {
+  let params = [
+    (thing.id as f64).into(),
+    nullable(fingerprint),
+    (now as f64).into(),
+    nullable(req.headers().get("CF-Connecting-IP")?),
+    nullable(req.headers().get("User-Agent")?),
+  ];
+  let statements = ACTIVATION_STATEMENTS
+    .iter()
+    .map(|(sql, count)| d1.prepare(*sql).bind(&params[..*count]))
+    .collect::<Result<Vec<_>>>()?;
+  let results = d1.batch(statements).await?;
+
+  let refreshed = results[0].results::<Activation>()?.len();
+  let claimed = results[1].results::<LicenseCode>()?.len();
+  let thing = match results[3].results::<LicenseCode>()?.pop() {
+    Some(thing) => thing,
+    None => return Response::error("Not found", 404).map(Err),
+  };
+  match ActivationOutcome::from_batch(refreshed, claimed, thing) {
+    ActivationOutcome::Activated(thing) | ActivationOutcome::Reactivated(thing) => Ok(Ok(thing)),
+    // 查询之后 license 被吊销、暂停或过期时返回对应原因
+    ActivationOutcome::LimitReached(thing) => match thing.check_usable(now) {
+      Err(reason) => Response::error(reason, 403).map(Err),
+      Ok(()) => Response::error("Activation limit reached", 403).map(Err),
+    },
   }
-  return false;
 }
 
 #[event(fetch, respond_with_errors)]
@@ -50,79 +200,126 @@ async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
             "Encrypted: {}, Decrypted: {}",
             encrypted, decrypted
           )),
-          Err(e) => Response::error(&format!("Decryption error: {}", e), 400),
+          Err(e) => license_error_response(e),
         },
-        Err(e) => Response::error(&format!("Encryption error: {}", e), 400),
+        Err(e) => Response::error(format!("Encryption error: {}", e), 400),
       }
     })
     // 获取所有code
-    .get_async("/:key/licenses/:limit/:offset", |_req, ctx| async move {
-      let key = ctx.param("key").unwrap();
-      if !check_access(key) {
-        return Response::error("Access Denied", 403);
+    .get_async("/licenses/:limit/:offset", |req, ctx| async move {
+      if let Some(denied) = authorize(&req, &ctx.env, Scope::Read).await? {
+        return Ok(denied);
       }
       let limit = ctx.param("limit").unwrap();
       let offset = ctx.param("offset").unwrap();
 
       let d1 = ctx.env.d1("license-server")?;
-      let statement = d1.prepare("SELECT * FROM LicenseCode LIMIT ?1 OFFSET ?2");
+      let statement = d1.prepare("SELECT * FROM LicenseCode ORDER BY id LIMIT ?1 OFFSET ?2");
       let query = statement.bind(&[limit.into(), offset.into()])?;
       let result = query.all().await?;
       Response::from_json(&result.results::<LicenseCode>().unwrap())
     })
     // 只负责加密字符串，解密函数在客户端rust lib
-    .post_async("/:key/encrypt", |mut req, ctx| async move {
-      let key = ctx.param("key").unwrap();
-      if !check_access(key) {
-        return Response::error("Access Denied", 403);
+    .post_async("/encrypt", |mut req, ctx| async move {
+      if let Some(denied) = authorize(&req, &ctx.env, Scope::Issue).await? {
+        return Ok(denied);
       }
       let body = req.json::<EncryptRequest>().await?;
       log!("Start encrypt, {}", body.s);
-      match encrypt(&body.s) {
+      if body.legacy {
+        return Response::error(
+          "PKCS#1 v1.5 encryption is no longer issued, use algorithm \"hybrid\" or \"rsa-oaep\"",
+          400,
+        );
+      }
+      let encrypted = match body.algorithm.as_deref() {
+        None | Some("hybrid") => encrypt(&body.s),
+        Some("rsa-oaep") => encrypt_rsa_oaep(&body.s),
+        Some(other) => Err(format!("Unsupported algorithm: {}", other)),
+      };
+      match encrypted {
         Ok(encrypted) => Response::ok(encrypted),
-        Err(e) => Response::error(&format!("Encryption error: {}", e), 400),
+        Err(e) => Response::error(format!("Encryption error: {}", e), 400),
       }
     })
-    // 新建一个code
-    .post_async("/:key/license", |mut req, ctx| async move {
-      let key = ctx.param("key").unwrap();
-      if !check_access(key) {
-        return Response::error("Access Denied", 403);
+    // 签名字符串，签名私钥来自 secret LICENSE_SIGNING_KEY，校验函数在客户端rust lib
+    .post_async("/sign", |mut req, ctx| async move {
+      if let Some(denied) = authorize(&req, &ctx.env, Scope::Issue).await? {
+        return Ok(denied);
+      }
+      let body = req.json::<EncryptRequest>().await?;
+      let signing_key = ctx.env.secret("LICENSE_SIGNING_KEY")?.to_string();
+      match sign(&body.s, &signing_key) {
+        Ok(signed) => Response::ok(signed),
+        // 服务端签名私钥的问题，不是请求的错误
+        Err(e) => Response::error(format!("Signing error: {}", e), 500),
       }
-      let body = req.json::<LicenseCode>().await?;
+    })
+    // 新建一个code，返回新建的记录
+    .post_async("/license", |mut req, ctx| async move {
+      let created_by = match authenticate(&req, &ctx.env, Scope::Issue).await? {
+        Ok(name) => name,
+        Err(denied) => return Ok(denied),
+      };
+      let body = req.json::<CreateLicenseRequest>().await?;
+      let now = now_seconds() as f64;
       let d1 = ctx.env.d1("license-server")?;
-      let statement = d1.prepare(format!(
-        "INSERT INTO LicenseCode (name, code, createdAt, activated) VALUES (?1, ?2, ?3, 0)"
-      ));
-      let query = statement.bind(&[body.name.into(), body.code.into(), body.createdAt.into()])?;
-      query.run().await?;
-      Response::ok("Success")
+      let statement = d1.prepare(
+        "INSERT INTO LicenseCode \
+         (name, code, createdAt, updatedAt, expiredAt, remark, createdBy, maxActivations) \
+         VALUES (?1, ?2, ?3, ?3, ?4, ?5, ?6, ?7) RETURNING *",
+      );
+      let query = statement.bind(&[
+        body.name.into(),
+        body.code.into(),
+        now.into(),
+        nullable(body.expiredAt.map(|expired_at| expired_at as f64)),
+        nullable(body.remark),
+        created_by.into(),
+        nullable(body.maxActivations),
+      ])?;
+      match query.first::<LicenseCode>(None).await {
+        Ok(Some(thing)) => Ok(Response::from_json(&thing)?.with_status(201)),
+        Ok(None) => Response::error("Insert failed", 500),
+        Err(e) => license_write_error(e),
+      }
     })
-    // 更新一个license
-    .patch_async("/:key/license", |mut req, ctx| async move {
-      let key = ctx.param("key").unwrap();
-      if !check_access(key) {
-        return Response::error("Access Denied", 403);
+    // 更新一个license，按 name 查找，省略的字段保持不变
+    // 激活计数只由激活、释放席位的 batch 修改，这里不能更改
+    // status 改为 revoked 时同 /license/:name/revoke，记录吊销时间；吊销后不能再改为其他状态
+    .patch_async("/license", |mut req, ctx| async move {
+      if let Some(denied) = authorize(&req, &ctx.env, Scope::Issue).await? {
+        return Ok(denied);
       }
-      let body = req.json::<LicenseCode>().await?;
+      let body = req.json::<UpdateLicenseRequest>().await?;
       let d1 = ctx.env.d1("license-server")?;
       let statement = d1.prepare(
-        "UPDATE LicenseCode SET code = ?1, activated = ?2, createdAt = ?3 WHERE name = ?4",
+        "UPDATE LicenseCode SET code = COALESCE(?1, code), \
+         status = COALESCE(?2, status), expiredAt = COALESCE(?3, expiredAt), \
+         remark = COALESCE(?4, remark), updatedAt = ?5, \
+         revokedAt = CASE WHEN ?2 = 'revoked' THEN COALESCE(revokedAt, ?5) ELSE revokedAt END, \
+         maxActivations = COALESCE(?7, maxActivations) \
+         WHERE name = ?6 RETURNING *",
       );
       let query = statement.bind(&[
-        body.code.into(),
-        body.activated.into(),
-        body.createdAt.into(),
+        nullable(body.code),
+        nullable(body.status.map(|status| status.name())),
+        nullable(body.expiredAt.map(|expired_at| expired_at as f64)),
+        nullable(body.remark),
+        (now_seconds() as f64).into(),
         body.name.into(),
+        nullable(body.maxActivations),
       ])?;
-      query.run().await?;
-      Response::ok("Success")
+      match query.first::<LicenseCode>(None).await {
+        Ok(Some(thing)) => Response::from_json(&thing),
+        Ok(None) => Response::error("Not found", 404),
+        Err(e) => license_write_error(e),
+      }
     })
     // 获取一个code的信息，通过code倒查
-    .post_async("/:key/license/code", |mut req, ctx| async move {
-      let key = ctx.param("key").unwrap();
-      if !check_access(key) {
-        return Response::error("Access Denied", 403);
+    .post_async("/license/code", |mut req, ctx| async move {
+      if let Some(denied) = authorize(&req, &ctx.env, Scope::Read).await? {
+        return Ok(denied);
       }
       let code = req.text().await?;
       // let code = ctx.param("code").unwrap();
@@ -137,10 +334,9 @@ async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
       }
     })
     // 获取一个code的信息，纯粹查看，作用不大
-    .get_async("/:key/license/:name", |_, ctx| async move {
-      let key = ctx.param("key").unwrap();
-      if !check_access(key) {
-        return Response::error("Access Denied", 403);
+    .get_async("/license/:name", |req, ctx| async move {
+      if let Some(denied) = authorize(&req, &ctx.env, Scope::Read).await? {
+        return Ok(denied);
       }
       let name = ctx.param("name").unwrap();
       let d1 = ctx.env.d1("license-server")?;
@@ -152,11 +348,11 @@ async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
         None => Response::error("Not found", 404),
       }
     })
-    // 激活code的信息，通常是服务端激活使用，客户传输 LicenseCode 整个JSON进行匹配
-    // 这个接口是public的，无需access key
+    // 激活code的信息，通常是服务端激活使用，客户传输 name + code 进行匹配
+    // 带 fingerprint 时同一台机器重复激活不占用新席位，席位已满返回 403
+    // 这个接口是public的，无需 API key
     .post_async("/license/activate", |mut req, ctx| async move {
-      // 要求对方要完整构造上来
-      let body = req.json::<LicenseCode>().await?;
+      let body = req.json::<ActivateLicenseRequest>().await?;
       let name = body.name;
 
       let d1 = ctx.env.d1("license-server")?;
@@ -166,30 +362,257 @@ async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
 
       match result {
         Some(thing) => {
-          // 找到了，校验 activated +1
-          let update_statement =
-            d1.prepare("UPDATE LicenseCode SET activated = ?1 WHERE name = ?2");
-          let update_query =
-            update_statement.bind(&[(thing.activated + 1).into(), (&thing.name).into()])?;
-          update_query.run().await?;
-
-          Response::from_json(&thing)
+          // 暂停、吊销或过期的 license 不能激活
+          let now = now_seconds();
+          if let Err(reason) = thing.check_usable(now) {
+            return Response::error(reason, 403);
+          }
+          // 返回激活后的公开字段，不含内部字段
+          let fingerprint = body.fingerprint.as_deref();
+          match activate_seat(&d1, &req, &thing, fingerprint, now).await? {
+            Ok(thing) => Response::from_json(&PublicLicense::from(&thing)),
+            Err(denied) => Ok(denied),
+          }
+        }
+        None => Response::error("Not found", 404),
+      }
+    })
+    // 离线激活：body 为客户端生成的激活请求文本，返回签名的激活响应文本
+    // 与 /license/activate 一样按 name + code 查找，按请求中的机器指纹占用席位
+    .post_async("/license/activate/offline", |mut req, ctx| async move {
+      let request = match ActivationRequest::decode(&req.text().await?) {
+        Ok(request) => request,
+        Err(e) => return license_error_response(e),
+      };
+
+      let d1 = ctx.env.d1("license-server")?;
+      let statement = d1.prepare("SELECT * FROM LicenseCode WHERE name = ?1 AND code = ?2");
+      let query = statement.bind(&[
+        request.license_id.as_str().into(),
+        request.code.as_str().into(),
+      ])?;
+      let thing = match query.first::<LicenseCode>(None).await? {
+        Some(thing) => thing,
+        None => return Response::error("Not found", 404),
+      };
+      let now = now_seconds();
+      if let Err(reason) = thing.check_usable(now) {
+        return Response::error(reason, 403);
+      }
+
+      let signing_key = ctx.env.secret("LICENSE_SIGNING_KEY")?.to_string();
+      let response = match sign_activation(&request, now, &signing_key) {
+        Ok(response) => response,
+        // 服务端签名私钥的问题，不是请求的错误
+        Err(e) => return Response::error(format!("Signing error: {}", e), 500),
+      };
+
+      let fingerprint = request.fingerprint.id();
+      if let Err(denied) = activate_seat(&d1, &req, &thing, Some(&fingerprint), now).await? {
+        return Ok(denied);
+      }
+
+      Response::ok(response)
+    })
+    // 释放一个席位，客户端卸载或换机器时调用，body 为 name + code + fingerprint
+    // 这个接口是public的，无需 API key
+    .post_async("/license/deactivate", |mut req, ctx| async move {
+      let body = req.json::<DeactivateLicenseRequest>().await?;
+
+      let d1 = ctx.env.d1("license-server")?;
+      let statement = d1.prepare("SELECT * FROM LicenseCode WHERE name = ?1 AND code = ?2");
+      let query = statement.bind(&[body.name.into(), body.code.into()])?;
+      let thing = match query.first::<LicenseCode>(None).await? {
+        Some(thing) => thing,
+        None => return Response::error("Not found", 404),
+      };
+
+      // 语句见 license::DEACTIVATION_STATEMENTS
+      let params: [wasm_bindgen::JsValue; 3] = [
+        (thing.id as f64).into(),
+        body.fingerprint.into(),
+        (now_seconds() as f64).into(),
+      ];
+      let statements = DEACTIVATION_STATEMENTS
+        .iter()
+        .map(|(sql, count)| d1.prepare(*sql).bind(&params[..*count]))
+        .collect::<Result<Vec<_>>>()?;
+      let results = d1.batch(statements).await?;
+      match results[1].results::<LicenseCode>()?.pop() {
+        Some(thing) => Response::from_json(&PublicLicense::from(&thing)),
+        None => Response::error("Activation not found", 404),
+      }
+    })
+    // 列出一个license的激活记录
+    .get_async("/license/:name/activations", |req, ctx| async move {
+      if let Some(denied) = authorize(&req, &ctx.env, Scope::Read).await? {
+        return Ok(denied);
+      }
+      let name = ctx.param("name").unwrap();
+      let d1 = ctx.env.d1("license-server")?;
+      let statement = d1.prepare(
+        "SELECT Activation.* FROM Activation \
+         JOIN LicenseCode ON LicenseCode.id = Activation.licenseId \
+         WHERE LicenseCode.name = ?1 ORDER BY Activation.id",
+      );
+      let query = statement.bind(&[name.into()])?;
+      let result = query.all().await?;
+      Response::from_json(&result.results::<Activation>()?)
+    })
+    // 列出 API key，不返回哈希
+    .get_async("/admin/api-keys", |req, ctx| async move {
+      if let Some(denied) = authorize(&req, &ctx.env, Scope::Admin).await? {
+        return Ok(denied);
+      }
+      let d1 = ctx.env.d1("license-server")?;
+      let statement = d1.prepare("SELECT * FROM ApiKey ORDER BY createdAt");
+      let result = statement.all().await?;
+      Response::from_json(&result.results::<ApiKey>()?)
+    })
+    // 新建 API key
+    .post_async("/admin/api-keys", |mut req, ctx| async move {
+      if let Some(denied) = authorize(&req, &ctx.env, Scope::Admin).await? {
+        return Ok(denied);
+      }
+      let body = req.json::<CreateApiKeyRequest>().await?;
+      let scopes = match parse_scopes(&body.scopes) {
+        Ok(scopes) if !scopes.is_empty() => format_scopes(&scopes),
+        Ok(_) => return Response::error("At least one scope is required", 400),
+        Err(e) => return Response::error(&e, 400),
+      };
+      let key = generate_api_key();
+
+      let d1 = ctx.env.d1("license-server")?;
+      let statement =
+        d1.prepare("INSERT INTO ApiKey (name, keyHash, scopes, createdAt) VALUES (?1, ?2, ?3, ?4)");
+      let query = statement.bind(&[
+        body.name.as_str().into(),
+        hash_api_key(&key).into(),
+        scopes.as_str().into(),
+        (now_seconds() as f64).into(),
+      ])?;
+      if let Err(e) = query.run().await {
+        return api_key_write_error(e);
+      }
+
+      Ok(
+        Response::from_json(&CreatedApiKey {
+          name: body.name,
+          scopes,
+          key,
+        })?
+        .with_status(201),
+      )
+    })
+    // 吊销 API key，保留记录
+    .delete_async("/admin/api-keys/:name", |req, ctx| async move {
+      if let Some(denied) = authorize(&req, &ctx.env, Scope::Admin).await? {
+        return Ok(denied);
+      }
+      let name = ctx.param("name").unwrap();
+      let d1 = ctx.env.d1("license-server")?;
+      let statement =
+        d1.prepare("UPDATE ApiKey SET revokedAt = ?1 WHERE name = ?2 AND revokedAt IS NULL");
+      let query = statement.bind(&[(now_seconds() as f64).into(), name.into()])?;
+      query.run().await?;
+      Response::ok("Success")
+    })
+    // 吊销一个license，body 可选 {"reason": "..."}，重复吊销保留第一次的时间
+    // 吊销后出现在 /crl 中，客户端据此拒绝已签发的 license
+    .post_async("/license/:name/revoke", |mut req, ctx| async move {
+      if let Some(denied) = authorize(&req, &ctx.env, Scope::Revoke).await? {
+        return Ok(denied);
+      }
+      let name = ctx.param("name").unwrap().to_string();
+      let text = req.text().await?;
+      let body = if text.trim().is_empty() {
+        RevokeLicenseRequest::default()
+      } else {
+        match serde_json::from_str::<RevokeLicenseRequest>(&text) {
+          Ok(body) => body,
+          Err(e) => return Response::error(format!("Invalid body: {}", e), 400),
         }
+      };
+      let d1 = ctx.env.d1("license-server")?;
+      let statement = d1.prepare(
+        "UPDATE LicenseCode SET status = 'revoked', revokedAt = COALESCE(revokedAt, ?1), \
+         revokeReason = COALESCE(?2, revokeReason), updatedAt = ?1 WHERE name = ?3 RETURNING *",
+      );
+      let query = statement.bind(&[
+        (now_seconds() as f64).into(),
+        nullable(body.reason),
+        name.into(),
+      ])?;
+      match query.first::<LicenseCode>(None).await? {
+        Some(thing) => Response::from_json(&thing),
         None => Response::error("Not found", 404),
       }
     })
+    // 吊销列表：签名的吊销 license 和密钥，客户端定期拉取并缓存
+    // 这个接口是public的，无需 API key
+    .get_async("/crl", |_req, ctx| async move {
+      let d1 = ctx.env.d1("license-server")?;
+      let licenses = d1
+        .prepare("SELECT * FROM LicenseCode WHERE status = 'revoked' ORDER BY id")
+        .all()
+        .await?
+        .results::<LicenseCode>()?;
+      let keys = d1
+        .prepare("SELECT * FROM RevokedKey ORDER BY revokedAt")
+        .all()
+        .await?
+        .results::<RevokedKeyRecord>()?;
+
+      let list = revocation_list(&licenses, &keys, now_seconds());
+      let signing_key = ctx.env.secret("LICENSE_SIGNING_KEY")?.to_string();
+      match sign_revocation_list(&list, &signing_key) {
+        Ok(signed) => {
+          let mut response = Response::ok(signed)?;
+          response
+            .headers_mut()
+            .set("Cache-Control", "public, max-age=3600")?;
+          Ok(response)
+        }
+        Err(e) => Response::error(format!("Signing error: {}", e), 500),
+      }
+    })
+    // 吊销签发密钥，body 为 {"keyId": "...", "reason": "..."}，用它签发的 license 全部失效
+    .post_async("/admin/revoked-keys", |mut req, ctx| async move {
+      if let Some(denied) = authorize(&req, &ctx.env, Scope::Admin).await? {
+        return Ok(denied);
+      }
+      let mut body = req.json::<RevokedKeyRecord>().await?;
+      body.revokedAt = now_seconds();
+      let d1 = ctx.env.d1("license-server")?;
+      let statement = d1.prepare(
+        "INSERT INTO RevokedKey (keyId, revokedAt, reason) VALUES (?1, ?2, ?3) \
+         ON CONFLICT (keyId) DO UPDATE SET reason = COALESCE(excluded.reason, reason) RETURNING *",
+      );
+      let query = statement.bind(&[
+        body.keyId.as_str().into(),
+        (body.revokedAt as f64).into(),
+        nullable(body.reason),
+      ])?;
+      match query.first::<RevokedKeyRecord>(None).await? {
+        Some(key) => Response::from_json(&key),
+        None => Response::error("Insert failed", 500),
+      }
+    })
     // 删除一个code
-    .delete_async("/:key/license/:id", |_, ctx| async move {
-      let key = ctx.param("key").unwrap();
-      if !check_access(key) {
-        return Response::error("Access Denied", 403);
+    // 只删除记录，已签发的 license 仍然有效，需要让它失效时用 /license/:name/revoke
+    // 吊销的 license 不能删除，否则会从吊销列表中消失
+    .delete_async("/license/:id", |req, ctx| async move {
+      if let Some(denied) = authorize(&req, &ctx.env, Scope::Revoke).await? {
+        return Ok(denied);
       }
       let id = ctx.param("id").unwrap();
       let d1 = ctx.env.d1("license-server")?;
       let statement = d1.prepare("DELETE FROM LicenseCode WHERE name = ?1");
       let query = statement.bind(&[id.into()])?;
-      query.run().await?;
-      Response::ok("Success")
+      match query.run().await {
+        Ok(_) => Response::ok("Success"),
+        Err(e) => license_write_error(e),
+      }
     })
     .run(req, env)
     .await
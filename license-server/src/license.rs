@@ -0,0 +1,305 @@
+use lib::revocation::{RevocationList, RevokedKey, RevokedLicense};
+use serde::{Deserialize, Serialize};
+
+// 吊销列表的建议更新间隔（秒）
+pub const REVOCATION_LIST_TTL: i64 = 24 * 60 * 60;
+
+// 修改或删除吊销的 license 时触发器的错误信息，见 migrations/0003_revocation.sql
+pub const REVOKED_LICENSE_ERROR: &str = "License has been revoked";
+
+// license 状态，只有 active 的 license 可以激活
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LicenseStatus {
+  #[default]
+  Active,
+  // 暂停，可以恢复
+  Suspended,
+  // 吊销，不再恢复
+  Revoked,
+}
+
+impl LicenseStatus {
+  pub fn name(&self) -> &'static str {
+    match self {
+      LicenseStatus::Active => "active",
+      LicenseStatus::Suspended => "suspended",
+      LicenseStatus::Revoked => "revoked",
+    }
+  }
+}
+
+// 数据库存档，表结构见 migrations/0002_license_code.sql、0003_revocation.sql、0004_activation.sql
+// 时间均为 Unix 秒
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[allow(non_snake_case)]
+pub struct LicenseCode {
+  pub id: i64,
+  pub name: String,
+  pub code: String,
+  pub createdAt: i64,
+  pub updatedAt: i64,
+  pub activated: u32,
+  pub status: LicenseStatus,
+  pub expiredAt: Option<i64>,
+  pub remark: Option<String>,
+  // 创建它的 API key 名称
+  pub createdBy: Option<String>,
+  pub revokedAt: Option<i64>,
+  pub revokeReason: Option<String>,
+  // 激活数上限，None 为不限
+  pub maxActivations: Option<u32>,
+}
+
+impl LicenseCode {
+  // 检查 license 在 `now` 是否可以激活
+  pub fn check_usable(&self, now: i64) -> Result<(), &'static str> {
+    match self.status {
+      LicenseStatus::Active => {}
+      LicenseStatus::Suspended => return Err("License is suspended"),
+      LicenseStatus::Revoked => return Err("License has been revoked"),
+    }
+    if self.expiredAt.is_some_and(|expired_at| now >= expired_at) {
+      return Err("License has expired");
+    }
+    Ok(())
+  }
+}
+
+// 公开接口（/license/activate、/license/deactivate）返回的内容
+// 不含 remark、createdBy、吊销原因等内部字段；时间沿用旧 LicenseCode JSON 的文本格式（UTC ISO 8601）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[allow(non_snake_case)]
+pub struct PublicLicense {
+  pub name: String,
+  pub code: String,
+  pub createdAt: String,
+  pub activated: u32,
+  pub status: LicenseStatus,
+  pub expiredAt: Option<String>,
+  pub maxActivations: Option<u32>,
+}
+
+impl From<&LicenseCode> for PublicLicense {
+  fn from(thing: &LicenseCode) -> Self {
+    PublicLicense {
+      name: thing.name.clone(),
+      code: thing.code.clone(),
+      createdAt: iso8601(thing.createdAt),
+      activated: thing.activated,
+      status: thing.status,
+      expiredAt: thing.expiredAt.map(iso8601),
+      maxActivations: thing.maxActivations,
+    }
+  }
+}
+
+// Unix 秒转为 UTC ISO 8601 文本，如 2025-08-08T00:00:00Z
+pub fn iso8601(seconds: i64) -> String {
+  // 公历日期换算，见 http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+  let days = seconds.div_euclid(86400) + 719_468;
+  let time = seconds.rem_euclid(86400);
+  let era = days.div_euclid(146_097);
+  let day_of_era = days.rem_euclid(146_097);
+  let year_of_era =
+    (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+  let month = if month_index < 10 {
+    month_index + 3
+  } else {
+    month_index - 9
+  };
+  let year = year_of_era + era * 400 + i64::from(month <= 2);
+  format!(
+    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+    year,
+    month,
+    day,
+    time / 3600,
+    time % 3600 / 60,
+    time % 60
+  )
+}
+
+// 激活记录，一台机器占用一个席位
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[allow(non_snake_case)]
+pub struct Activation {
+  pub id: i64,
+  pub licenseId: i64,
+  // 客户端 Fingerprint::id()，旧客户端为 None
+  pub fingerprint: Option<String>,
+  pub ip: Option<String>,
+  pub userAgent: Option<String>,
+  pub createdAt: i64,
+  // 最近一次激活的时间，同一台机器重复激活时更新
+  pub lastSeenAt: i64,
+}
+
+// 激活在一个 D1 batch（事务）中依次执行以下语句，计数只在 SQL 中增减，并发激活不会丢失计数或超出上限
+// 参数：?1 license id，?2 fingerprint，?3 当前时间，?4 IP，?5 User-Agent，每条语句只绑定前 n 个
+pub const ACTIVATION_STATEMENTS: [(&str, usize); 4] = [
+  // 已激活的机器：license 仍可用时更新激活记录，返回非空表示重复激活
+  (
+    "UPDATE Activation SET ip = ?4, userAgent = ?5, lastSeenAt = ?3 \
+     WHERE licenseId = ?1 AND fingerprint = ?2 \
+     AND EXISTS (SELECT 1 FROM LicenseCode WHERE id = ?1 AND status = 'active' \
+     AND (expiredAt IS NULL OR expiredAt > ?3)) \
+     RETURNING *",
+    5,
+  ),
+  // 新机器：license 仍可用且有空闲席位时占用一个，返回非空表示占用成功
+  // 查询后被吊销、暂停或过期的 license 在这里占不到席位
+  (
+    "UPDATE LicenseCode SET activated = activated + 1, updatedAt = ?3 \
+     WHERE id = ?1 AND status = 'active' AND (expiredAt IS NULL OR expiredAt > ?3) \
+     AND (maxActivations IS NULL OR activated < maxActivations) \
+     AND NOT EXISTS (SELECT 1 FROM Activation WHERE licenseId = ?1 AND fingerprint = ?2) \
+     RETURNING *",
+    3,
+  ),
+  // 上一条占用了席位时插入激活记录
+  (
+    "INSERT INTO Activation (licenseId, fingerprint, ip, userAgent, createdAt, lastSeenAt) \
+     SELECT ?1, ?2, ?4, ?5, ?3, ?3 WHERE changes() = 1",
+    5,
+  ),
+  // 激活后的 license
+  ("SELECT * FROM LicenseCode WHERE id = ?1", 1),
+];
+
+// 释放席位，同样在一个 batch 中执行，参数：?1 license id，?2 fingerprint，?3 当前时间
+pub const DEACTIVATION_STATEMENTS: [(&str, usize); 2] = [
+  // 删除激活记录，返回空表示这台机器没有激活
+  (
+    "DELETE FROM Activation WHERE licenseId = ?1 AND fingerprint = ?2 RETURNING *",
+    2,
+  ),
+  // 删除了记录时释放席位，返回释放后的 license
+  (
+    "UPDATE LicenseCode SET activated = activated - 1, updatedAt = ?3 \
+     WHERE id = ?1 AND changes() = 1 RETURNING *",
+    3,
+  ),
+];
+
+// 激活 batch 的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActivationOutcome {
+  // 占用了新席位
+  Activated(LicenseCode),
+  // 已激活的机器，不占用新席位
+  Reactivated(LicenseCode),
+  // 席位已满，或 license 已不可用（见 `LicenseCode::check_usable`）
+  LimitReached(LicenseCode),
+}
+
+impl ActivationOutcome {
+  // 由激活 batch 各语句返回的行数和最终的 license 得出结果
+  pub fn from_batch(refreshed: usize, claimed: usize, thing: LicenseCode) -> Self {
+    if refreshed > 0 {
+      ActivationOutcome::Reactivated(thing)
+    } else if claimed > 0 {
+      ActivationOutcome::Activated(thing)
+    } else {
+      ActivationOutcome::LimitReached(thing)
+    }
+  }
+}
+
+// 新建 license 的请求body，id、时间和 createdBy 由服务端填写
+#[derive(Deserialize)]
+#[allow(non_snake_case)]
+pub struct CreateLicenseRequest {
+  pub name: String,
+  pub code: String,
+  #[serde(default)]
+  pub expiredAt: Option<i64>,
+  #[serde(default)]
+  pub remark: Option<String>,
+  #[serde(default)]
+  pub maxActivations: Option<u32>,
+}
+
+// 更新 license 的请求body，按 name 查找，省略的字段保持不变
+#[derive(Deserialize)]
+#[allow(non_snake_case)]
+pub struct UpdateLicenseRequest {
+  pub name: String,
+  #[serde(default)]
+  pub code: Option<String>,
+  #[serde(default)]
+  pub status: Option<LicenseStatus>,
+  #[serde(default)]
+  pub expiredAt: Option<i64>,
+  #[serde(default)]
+  pub remark: Option<String>,
+  #[serde(default)]
+  pub maxActivations: Option<u32>,
+}
+
+// 吊销 license 的请求body，可以为空
+#[derive(Deserialize, Default)]
+pub struct RevokeLicenseRequest {
+  #[serde(default)]
+  pub reason: Option<String>,
+}
+
+// 吊销的签发密钥，也是吊销密钥的请求body，revokedAt 由服务端填写
+#[derive(Deserialize, Serialize)]
+#[allow(non_snake_case)]
+pub struct RevokedKeyRecord {
+  pub keyId: String,
+  #[serde(default)]
+  pub revokedAt: i64,
+  #[serde(default)]
+  pub reason: Option<String>,
+}
+
+// 由吊销的 license 和密钥生成吊销列表，license 按 name 匹配 LicensePayload::license_id
+pub fn revocation_list(
+  licenses: &[LicenseCode],
+  keys: &[RevokedKeyRecord],
+  now: i64,
+) -> RevocationList {
+  let mut list = RevocationList::new(now);
+  list.next_update = Some(now + REVOCATION_LIST_TTL);
+  list.licenses = licenses
+    .iter()
+    .filter(|thing| thing.status == LicenseStatus::Revoked)
+    .map(|thing| RevokedLicense {
+      license_id: thing.name.clone(),
+      revoked_at: thing.revokedAt.unwrap_or(thing.updatedAt),
+      reason: thing.revokeReason.clone(),
+    })
+    .collect();
+  list.keys = keys
+    .iter()
+    .map(|key| RevokedKey {
+      key_id: key.keyId.clone(),
+      revoked_at: key.revokedAt,
+      reason: key.reason.clone(),
+    })
+    .collect();
+  list
+}
+
+// 激活请求body，客户端可以继续上传整个 LicenseCode JSON，多余字段忽略
+// fingerprint 为机器指纹 id，同一台机器重复激活不占用新席位，旧客户端不上传
+#[derive(Deserialize)]
+pub struct ActivateLicenseRequest {
+  pub name: String,
+  pub code: String,
+  #[serde(default)]
+  pub fingerprint: Option<String>,
+}
+
+// 释放席位的请求body，客户端用 name + code 证明持有 license
+#[derive(Deserialize)]
+pub struct DeactivateLicenseRequest {
+  pub name: String,
+  pub code: String,
+  pub fingerprint: String,
+}
@@ -1,247 +1,278 @@
-#[cfg(test)]
-mod tests {
-  use crate::crypto::{encrypt, PUBLIC_KEY};
-  use lib::{decrypt, get_private_key};
-  use rsa::pkcs8::{EncodePublicKey, LineEnding};
-
-  /**
-   * 从Private Key里获取 Public Key
-   */
-  fn generate_rsa_public_key_str_from_private_key_str() -> String {
-    // 将私钥字符串解析为 private key
-    let private_key = get_private_key();
-
-    let new_public_key = private_key.to_public_key();
-    return new_public_key.to_public_key_pem(LineEnding::LF).unwrap();
+use crate::crypto::{encrypt, encrypt_rsa_oaep, shared_encryptor, PUBLIC_KEY};
+use base64::prelude::*;
+use lib::keys::PrivateKey;
+use lib::{decrypt, get_private_key};
+use rsa::{rand_core::OsRng, traits::PublicKeyParts, Pkcs1v15Encrypt};
+
+// 旧格式：单块或 CHUNK 分块的 PKCS#1 v1.5
+// 存在 padding oracle 风险，服务端不再签发，这里生成旧 license 验证客户端兼容
+fn encrypt_legacy(message: &str) -> Result<String, String> {
+  let public_key = shared_encryptor().public_key();
+  // PKCS1v15 填充：最大明文长度 = 密钥长度(字节) - 11
+  let max_length = public_key.size() - 11;
+
+  // If message fits in single block, use original format for backward compatibility
+  if message.len() <= max_length {
+    return match public_key.encrypt(&mut OsRng, Pkcs1v15Encrypt, message.as_bytes()) {
+      Ok(encrypted_message) => Ok(BASE64_STANDARD.encode(encrypted_message)),
+      Err(e) => Err(format!("Encryption failed: {}", e)),
+    };
   }
 
-  #[test]
-  fn it_works() {
-    // 测试pub key正确性
-    let result = generate_rsa_public_key_str_from_private_key_str();
-    assert_eq!(result, PUBLIC_KEY);
+  // Use chunked format for longer messages
+  let chunks: Vec<&[u8]> = message.as_bytes().chunks(max_length).collect();
+  if chunks.len() > 100 {
+    // Reasonable limit
+    return Err(format!(
+      "Message too long: {} chunks, maximum 100 allowed",
+      chunks.len()
+    ));
+  }
 
-    // 加密、解密
-    let encrypted = encrypt("{'abc': 123}").expect("Encryption should succeed");
-    let decrypted = decrypt(&encrypted).expect("Decryption should succeed");
-    assert_eq!(decrypted, "{'abc': 123}");
+  let mut encrypted_chunks = Vec::new();
+  for chunk in chunks {
+    match public_key.encrypt(&mut OsRng, Pkcs1v15Encrypt, chunk) {
+      Ok(encrypted_chunk) => encrypted_chunks.push(BASE64_STANDARD.encode(encrypted_chunk)),
+      Err(e) => return Err(format!("Chunk encryption failed: {}", e)),
+    }
   }
 
-  #[test]
-  fn test_determine_actual_max_length() {
-    // 分析公钥的实际参数
-    use rsa::pkcs8::DecodePublicKey;
-    use rsa::{traits::PublicKeyParts, RsaPublicKey};
+  // Format: CHUNK:count:block1|block2|...
+  Ok(format!(
+    "CHUNK:{}:{}",
+    encrypted_chunks.len(),
+    encrypted_chunks.join("|")
+  ))
+}
 
-    let public_key = RsaPublicKey::from_public_key_pem(PUBLIC_KEY).unwrap();
-    let key_size_bits = public_key.size() * 8; // size() 返回字节数
-    let key_size_bytes = public_key.size();
+#[test]
+fn it_works() {
+  // 测试pub key正确性：从内置私钥推导的公钥与服务端公钥一致
+  let result = PrivateKey::Rsa(get_private_key())
+    .public_key()
+    .to_spki_pem()
+    .unwrap();
+  assert_eq!(result, PUBLIC_KEY);
 
-    println!(
-      "RSA Key size: {} bits ({} bytes)",
-      key_size_bits, key_size_bytes
-    );
+  // 加密、解密
+  let encrypted = encrypt("{'abc': 123}").expect("Encryption should succeed");
+  let decrypted = decrypt(&encrypted).expect("Decryption should succeed");
+  assert_eq!(decrypted, "{'abc': 123}");
+}
 
-    // PKCS1v15 填充的理论最大明文长度 = 密钥长度(字节) - 11
-    let theoretical_max = key_size_bytes - 11;
-    println!("Theoretical max message length: {} bytes", theoretical_max);
+#[test]
+fn test_determine_actual_max_length() {
+  // 分析公钥的实际参数
+  use rsa::pkcs8::DecodePublicKey;
+  use rsa::{traits::PublicKeyParts, RsaPublicKey};
 
-    // 实际测试不同长度的消息
-    for length in (theoretical_max - 5)..=(theoretical_max + 5) {
-      let test_message = "A".repeat(length);
-      match encrypt(&test_message) {
-        Ok(_) => println!("✓ Length {} bytes: SUCCESS", length),
-        Err(e) => println!("✗ Length {} bytes: FAILED - {}", length, e),
-      }
-    }
+  let public_key = RsaPublicKey::from_public_key_pem(PUBLIC_KEY).unwrap();
+  let key_size_bits = public_key.size() * 8; // size() 返回字节数
+  let key_size_bytes = public_key.size();
 
-    // 验证理论值是否正确
-    assert_eq!(
-      theoretical_max, 245,
-      "Expected 2048-bit RSA key with 245 byte max"
-    );
-  }
+  println!(
+    "RSA Key size: {} bits ({} bytes)",
+    key_size_bits, key_size_bytes
+  );
 
-  #[test]
-  fn test_encrypt_normal_message() {
-    // 测试正常长度的消息
-    let message = "This is a normal length message for testing";
-    let result = encrypt(message);
-    assert!(result.is_ok(), "Normal message should encrypt successfully");
+  // PKCS1v15 填充的理论最大明文长度 = 密钥长度(字节) - 11
+  let theoretical_max = key_size_bytes - 11;
+  println!("Theoretical max message length: {} bytes", theoretical_max);
 
-    if let Ok(encrypted) = result {
-      let decrypted = decrypt(&encrypted).expect("Decryption should succeed");
-      assert_eq!(decrypted, message);
+  // 实际测试不同长度的消息
+  for length in (theoretical_max - 5)..=(theoretical_max + 5) {
+    let test_message = "A".repeat(length);
+    match encrypt(&test_message) {
+      Ok(_) => println!("✓ Length {} bytes: SUCCESS", length),
+      Err(e) => println!("✗ Length {} bytes: FAILED - {}", length, e),
     }
   }
 
-  #[test]
-  fn test_encrypt_max_length_message() {
-    // 测试最大长度的消息 (245 字节)
-    let message = "a".repeat(245);
-    let result = encrypt(&message);
-    assert!(
-      result.is_ok(),
-      "Message at max length should encrypt successfully"
-    );
+  // 验证理论值是否正确
+  assert_eq!(
+    theoretical_max, 245,
+    "Expected 2048-bit RSA key with 245 byte max"
+  );
+}
 
-    if let Ok(encrypted) = result {
-      let decrypted = decrypt(&encrypted).expect("Decryption should succeed");
-      assert_eq!(decrypted, message);
-    }
+#[test]
+fn test_encrypt_normal_message() {
+  // 测试正常长度的消息
+  let message = "This is a normal length message for testing";
+  let result = encrypt(message);
+  assert!(result.is_ok(), "Normal message should encrypt successfully");
+
+  if let Ok(encrypted) = result {
+    let decrypted = decrypt(&encrypted).expect("Decryption should succeed");
+    assert_eq!(decrypted, message);
   }
+}
 
-  #[test]
-  fn test_encrypt_long_message_chunked() {
-    // 测试超过单块最大长度的消息 (246 字节) - 现在应该成功并使用分块格式
-    let message = "a".repeat(246);
-    let result = encrypt(&message);
+#[test]
+fn test_encrypt_max_length_message() {
+  // 测试最大长度的消息 (245 字节)
+  let message = "a".repeat(245);
+  let result = encrypt(&message);
+  assert!(
+    result.is_ok(),
+    "Message at max length should encrypt successfully"
+  );
+
+  if let Ok(encrypted) = result {
+    let decrypted = decrypt(&encrypted).expect("Decryption should succeed");
+    assert_eq!(decrypted, message);
+  }
+}
+
+#[test]
+fn test_encrypt_long_message_chunked() {
+  // 测试超过单块最大长度的消息 (246 字节) - 现在应该成功并使用分块格式
+  let message = "a".repeat(246);
+  let result = encrypt_legacy(&message);
+  assert!(
+    result.is_ok(),
+    "Long message should encrypt successfully using chunked format"
+  );
+
+  if let Ok(encrypted) = result {
+    // 长消息应该使用分块格式
     assert!(
-      result.is_ok(),
-      "Long message should encrypt successfully using chunked format"
+      encrypted.starts_with("CHUNK:"),
+      "Long message should use chunked format"
     );
 
-    if let Ok(encrypted) = result {
-      // 长消息应该使用分块格式
-      assert!(
-        encrypted.starts_with("CHUNK:"),
-        "Long message should use chunked format"
-      );
-
-      // 解密验证
-      let decrypted = decrypt(&encrypted).expect("Decryption should succeed");
-      assert_eq!(decrypted, message);
-    }
+    // 解密验证
+    let decrypted = decrypt(&encrypted).expect("Decryption should succeed");
+    assert_eq!(decrypted, message);
   }
+}
 
-  #[test]
-  fn test_encrypt_empty_message() {
-    // 测试空消息
-    let message = "";
-    let result = encrypt(message);
-    assert!(result.is_ok(), "Empty message should encrypt successfully");
+#[test]
+fn test_encrypt_empty_message() {
+  // 测试空消息
+  let message = "";
+  let result = encrypt(message);
+  assert!(result.is_ok(), "Empty message should encrypt successfully");
 
-    if let Ok(encrypted) = result {
-      let decrypted = decrypt(&encrypted).expect("Decryption should succeed");
-      assert_eq!(decrypted, message);
-    }
+  if let Ok(encrypted) = result {
+    let decrypted = decrypt(&encrypted).expect("Decryption should succeed");
+    assert_eq!(decrypted, message);
   }
+}
 
-  #[test]
-  fn test_encrypt_boundary_messages() {
-    // 测试边界附近的消息长度 - 现在所有长度都应该成功
-    for len in [243, 244, 245, 246, 247] {
-      let message = "x".repeat(len);
-      let result = encrypt(&message);
-
-      assert!(result.is_ok(), "Message of length {} should succeed", len);
-      if let Ok(encrypted) = result {
-        // 检查格式
-        if len <= 245 {
-          assert!(
-            !encrypted.starts_with("CHUNK:"),
-            "Short message (len={}) should use traditional format",
-            len
-          );
-        } else {
-          assert!(
-            encrypted.starts_with("CHUNK:"),
-            "Long message (len={}) should use chunked format",
-            len
-          );
-        }
+#[test]
+fn test_encrypt_boundary_messages() {
+  // 测试边界附近的消息长度 - 现在所有长度都应该成功
+  for len in [243, 244, 245, 246, 247] {
+    let message = "x".repeat(len);
+    let result = encrypt_legacy(&message);
 
-        let decrypted =
-          decrypt(&encrypted).expect(&format!("Decryption should succeed for length {}", len));
-        assert_eq!(decrypted, message);
+    assert!(result.is_ok(), "Message of length {} should succeed", len);
+    if let Ok(encrypted) = result {
+      // 检查格式
+      if len <= 245 {
+        assert!(
+          !encrypted.starts_with("CHUNK:"),
+          "Short message (len={}) should use traditional format",
+          len
+        );
+      } else {
+        assert!(
+          encrypted.starts_with("CHUNK:"),
+          "Long message (len={}) should use chunked format",
+          len
+        );
       }
+
+      let decrypted = decrypt(&encrypted)
+        .unwrap_or_else(|_| panic!("Decryption should succeed for length {}", len));
+      assert_eq!(decrypted, message);
     }
   }
+}
 
-  #[test]
-  fn test_encrypt_very_long_message() {
-    // 测试非常长的消息（需要多个块）
-    let message = "B".repeat(1000); // 需要5个块
-    let result = encrypt(&message);
+#[test]
+fn test_encrypt_very_long_message() {
+  // 测试非常长的消息（需要多个块）
+  let message = "B".repeat(1000); // 需要5个块
+  let result = encrypt_legacy(&message);
+  assert!(
+    result.is_ok(),
+    "Very long message should encrypt successfully"
+  );
+
+  if let Ok(encrypted) = result {
     assert!(
-      result.is_ok(),
-      "Very long message should encrypt successfully"
+      encrypted.starts_with("CHUNK:"),
+      "Very long message should use chunked format"
     );
 
-    if let Ok(encrypted) = result {
-      assert!(
-        encrypted.starts_with("CHUNK:"),
-        "Very long message should use chunked format"
-      );
-
-      // 解析格式验证
-      let parts: Vec<&str> = encrypted.splitn(3, ':').collect();
-      assert_eq!(parts.len(), 3);
-      assert_eq!(parts[0], "CHUNK");
+    // 解析格式验证
+    let parts: Vec<&str> = encrypted.splitn(3, ':').collect();
+    assert_eq!(parts.len(), 3);
+    assert_eq!(parts[0], "CHUNK");
 
-      let chunk_count: usize = parts[1].parse().expect("Should be valid number");
-      assert_eq!(
-        chunk_count, 5,
-        "1000 chars should need 5 chunks (1000/245=4.08, round up to 5)"
-      );
+    let chunk_count: usize = parts[1].parse().expect("Should be valid number");
+    assert_eq!(
+      chunk_count, 5,
+      "1000 chars should need 5 chunks (1000/245=4.08, round up to 5)"
+    );
 
-      let blocks: Vec<&str> = parts[2].split('|').collect();
-      assert_eq!(blocks.len(), chunk_count);
+    let blocks: Vec<&str> = parts[2].split('|').collect();
+    assert_eq!(blocks.len(), chunk_count);
 
-      // 解密验证
-      let decrypted = decrypt(&encrypted).expect("Decryption should succeed");
-      assert_eq!(decrypted, message);
-    }
+    // 解密验证
+    let decrypted = decrypt(&encrypted).expect("Decryption should succeed");
+    assert_eq!(decrypted, message);
   }
+}
 
-  #[test]
-  fn test_encrypt_unicode_messages() {
-    // 测试Unicode字符的加密
-    let long_unicode_msg = format!("长Unicode消息: {}", "🎯中文🎯".repeat(100));
+#[test]
+fn test_encrypt_unicode_messages() {
+  // 测试Unicode字符的加密
+  let long_unicode_msg = format!("长Unicode消息: {}", "🎯中文🎯".repeat(100));
 
-    let test_cases = vec![
-      ("中文测试", "测试中文字符的加密解密"),
-      ("emoji_test", "Hello 🌍 World! 测试emoji字符 🚀🎉"),
-      (
-        "mixed_content",
-        "Mixed content: English + 中文 + русский + العربية + 🌟",
-      ),
-      ("long_unicode", long_unicode_msg.as_str()), // 超过245字节
-    ];
+  let test_cases = vec![
+    ("中文测试", "测试中文字符的加密解密"),
+    ("emoji_test", "Hello 🌍 World! 测试emoji字符 🚀🎉"),
+    (
+      "mixed_content",
+      "Mixed content: English + 中文 + русский + العربية + 🌟",
+    ),
+    ("long_unicode", long_unicode_msg.as_str()), // 超过245字节
+  ];
 
-    for (name, message) in test_cases {
-      let result = encrypt(message);
-      assert!(
-        result.is_ok(),
-        "Unicode message '{}' should encrypt successfully",
-        name
-      );
+  for (name, message) in test_cases {
+    let result = encrypt(message);
+    assert!(
+      result.is_ok(),
+      "Unicode message '{}' should encrypt successfully",
+      name
+    );
 
-      if let Ok(encrypted) = result {
-        let decrypted = decrypt(&encrypted).expect(&format!(
-          "Unicode message '{}' should decrypt successfully",
-          name
-        ));
-        assert_eq!(decrypted, message);
-
-        println!(
-          "✓ Unicode test '{}': {} bytes -> format: {}",
-          name,
-          message.len(),
-          if encrypted.starts_with("CHUNK:") {
-            "chunked"
-          } else {
-            "single"
-          }
-        );
-      }
+    if let Ok(encrypted) = result {
+      let decrypted = decrypt(&encrypted)
+        .unwrap_or_else(|_| panic!("Unicode message '{}' should decrypt successfully", name));
+      assert_eq!(decrypted, message);
+
+      println!(
+        "✓ Unicode test '{}': {} bytes -> format: {}",
+        name,
+        message.len(),
+        if encrypted.starts_with("CHUNK:") {
+          "chunked"
+        } else {
+          "single"
+        }
+      );
     }
   }
+}
 
-  #[test]
-  fn test_encrypt_json_license_data() {
-    // 测试实际的许可证JSON数据
-    let license_json = r#"{
+#[test]
+fn test_encrypt_json_license_data() {
+  // 测试实际的许可证JSON数据
+  let license_json = r#"{
     "product": "Professional Software License",
     "licensee": "Beijing Tech Co., Ltd",
     "license_key": "PROF-2025-ABCD-EFGH-IJKL",
@@ -272,113 +303,967 @@ mod tests {
     "signature": "SHA256:abcdef1234567890..."
 }"#;
 
-    let result = encrypt(license_json);
+  let result = encrypt_legacy(license_json);
+  assert!(
+    result.is_ok(),
+    "JSON license data should encrypt successfully"
+  );
+
+  if let Ok(encrypted) = result {
+    // JSON数据比较长，应该使用分块格式
+    assert!(
+      encrypted.starts_with("CHUNK:"),
+      "Long JSON should use chunked format"
+    );
+
+    let decrypted = decrypt(&encrypted).expect("JSON license should decrypt successfully");
+    assert_eq!(decrypted, license_json);
+
+    println!(
+      "✓ JSON license test: {} bytes encrypted using chunked format",
+      license_json.len()
+    );
+  }
+}
+
+#[test]
+fn test_encrypt_format_consistency() {
+  // 测试加密格式的一致性
+  let test_cases = vec![
+    (100, false), // 短消息，应该是单块格式
+    (245, false), // 边界消息，应该是单块格式
+    (246, true),  // 刚好超过边界，应该是分块格式
+    (500, true),  // 长消息，应该是分块格式
+    (1000, true), // 很长消息，应该是分块格式
+  ];
+
+  for (length, should_be_chunked) in test_cases {
+    let message = "T".repeat(length);
+    let result = encrypt_legacy(&message);
     assert!(
       result.is_ok(),
-      "JSON license data should encrypt successfully"
+      "Message of length {} should encrypt",
+      length
     );
 
     if let Ok(encrypted) = result {
-      // JSON数据比较长，应该使用分块格式
-      assert!(
-        encrypted.starts_with("CHUNK:"),
-        "Long JSON should use chunked format"
+      let is_chunked = encrypted.starts_with("CHUNK:");
+      assert_eq!(
+        is_chunked,
+        should_be_chunked,
+        "Message of length {} should {} chunked format",
+        length,
+        if should_be_chunked { "use" } else { "not use" }
       );
 
-      let decrypted = decrypt(&encrypted).expect("JSON license should decrypt successfully");
-      assert_eq!(decrypted, license_json);
+      // 验证解密
+      let decrypted = decrypt(&encrypted)
+        .unwrap_or_else(|_| panic!("Message of length {} should decrypt", length));
+      assert_eq!(decrypted, message);
+    }
+  }
+}
+
+#[test]
+fn test_encrypt_performance() {
+  // 测试加密性能
+  use std::time::Instant;
+
+  let test_sizes = vec![(1024, "1KB"), (5120, "5KB"), (10240, "10KB")];
+
+  for (size, name) in test_sizes {
+    let message = "P".repeat(size);
+
+    let start = Instant::now();
+    let result = encrypt(&message);
+    let duration = start.elapsed();
+
+    assert!(
+      result.is_ok(),
+      "{} message should encrypt successfully",
+      name
+    );
+
+    if let Ok(encrypted) = result {
+      let decrypt_start = Instant::now();
+      let decrypted =
+        decrypt(&encrypted).unwrap_or_else(|_| panic!("{} message should decrypt", name));
+      let decrypt_duration = decrypt_start.elapsed();
+
+      assert_eq!(decrypted, message);
 
       println!(
-        "✓ JSON license test: {} bytes encrypted using chunked format",
-        license_json.len()
+        "✓ Performance test {}: encrypt {}ms, decrypt {}ms",
+        name,
+        duration.as_millis(),
+        decrypt_duration.as_millis()
+      );
+
+      // 性能要求：加密和解密都应该在合理时间内完成
+      assert!(
+        duration.as_millis() < 5000,
+        "{} encryption should complete within 5 seconds",
+        name
+      );
+      assert!(
+        decrypt_duration.as_millis() < 3000,
+        "{} decryption should complete within 3 seconds",
+        name
       );
     }
   }
+}
 
-  #[test]
-  fn test_encrypt_format_consistency() {
-    // 测试加密格式的一致性
-    let test_cases = vec![
-      (100, false), // 短消息，应该是单块格式
-      (245, false), // 边界消息，应该是单块格式
-      (246, true),  // 刚好超过边界，应该是分块格式
-      (500, true),  // 长消息，应该是分块格式
-      (1000, true), // 很长消息，应该是分块格式
-    ];
+#[test]
+fn test_sign_and_verify_ed25519() {
+  // 服务端签名，客户端用公钥校验
+  use crate::crypto::sign;
+  use ed25519_dalek::pkcs8::EncodePrivateKey;
+  use lib::{verify_license_with, LicenseError, LicensePayload, VerifyingKey};
+  use rsa::pkcs8::LineEnding;
 
-    for (length, should_be_chunked) in test_cases {
-      let message = "T".repeat(length);
-      let result = encrypt(&message);
-      assert!(
-        result.is_ok(),
-        "Message of length {} should encrypt",
-        length
-      );
+  let signing_key = ed25519_dalek::SigningKey::from_bytes(&[3u8; 32]);
+  let signing_key_pem = signing_key.to_pkcs8_pem(LineEnding::LF).unwrap();
+  let verifying_key = VerifyingKey::Ed25519(signing_key.verifying_key());
 
-      if let Ok(encrypted) = result {
-        let is_chunked = encrypted.starts_with("CHUNK:");
-        assert_eq!(
-          is_chunked,
-          should_be_chunked,
-          "Message of length {} should {} chunked format",
-          length,
-          if should_be_chunked { "use" } else { "not use" }
-        );
+  let mut payload = LicensePayload::new("Beijing Tech Co., Ltd", 1_754_611_200);
+  payload.max_seats = Some(10);
+  let token = sign(&payload.to_json().unwrap(), &signing_key_pem).expect("Signing should succeed");
+  assert!(lib::signature::is_signed(&token));
 
-        // 验证解密
-        let decrypted =
-          decrypt(&encrypted).expect(&format!("Message of length {} should decrypt", length));
-        assert_eq!(decrypted, message);
+  let verified = verify_license_with(&verifying_key, &token).expect("Should verify");
+  assert_eq!(verified, payload);
+
+  // 篡改后校验失败
+  let mut container = lib::container::Container::decode(&token).unwrap();
+  container.payload[10] ^= 1;
+  assert_eq!(
+    verify_license_with(&verifying_key, &container.encode().unwrap()),
+    Err(LicenseError::InvalidSignature)
+  );
+}
+
+#[test]
+fn test_sign_and_verify_rsa() {
+  use crate::crypto::sign;
+  use lib::{verify_license_with, LicensePayload, VerifyingKey, PRIVATE_KEY};
+
+  let payload = LicensePayload::new("RSA signed", 1_754_611_200);
+  let token = sign(&payload.to_json().unwrap(), PRIVATE_KEY).expect("Signing should succeed");
+  let container = lib::container::Container::decode(&token).unwrap();
+  assert_eq!(
+    container.algorithm,
+    lib::container::Algorithm::RsaPkcs1v15Sha256
+  );
+
+  let verifying_key = VerifyingKey::from_public_key_pem(PUBLIC_KEY).unwrap();
+  assert_eq!(container.key_id, verifying_key.key_id().unwrap());
+  let verified = verify_license_with(&verifying_key, &token).expect("Should verify");
+  assert_eq!(verified.licensee, "RSA signed");
+
+  assert!(sign("hello", "not a key").is_err());
+}
+
+#[test]
+fn test_sign_activation() {
+  // 离线激活：客户端生成请求，服务端签名响应，客户端安装
+  use crate::crypto::sign_activation;
+  use ed25519_dalek::pkcs8::EncodePrivateKey;
+  use lib::{ActivationRequest, ActivationResponse, Fingerprint, Keyring, VerifyingKey};
+  use rsa::pkcs8::LineEnding;
+
+  let signing_key = ed25519_dalek::SigningKey::from_bytes(&[5u8; 32]);
+  let signing_key_pem = signing_key.to_pkcs8_pem(LineEnding::LF).unwrap();
+  let mut keyring = Keyring::new();
+  keyring
+    .add_verifying_key(VerifyingKey::Ed25519(signing_key.verifying_key()))
+    .unwrap();
+
+  let machine = Fingerprint::new().with_component("machine_id", "4c4c4544003a");
+  let request = ActivationRequest::new("acme", "CODE-1", machine.clone(), 1_754_611_200);
+  let received = ActivationRequest::decode(&request.encode().unwrap()).unwrap();
+
+  let token = sign_activation(&received, 1_754_611_260, &signing_key_pem).unwrap();
+  let response = ActivationResponse::install(&keyring, &token, &request, &machine, 0).unwrap();
+  assert_eq!(response.license_id, "acme");
+  assert_eq!(response.activated_at, 1_754_611_260);
+
+  assert!(sign_activation(&received, 0, "not a key").is_err());
+}
+
+#[test]
+fn test_api_key_auth() {
+  use crate::auth::*;
+
+  // Bearer 头解析
+  assert_eq!(bearer_token(Some("Bearer blk_abc")), Some("blk_abc"));
+  assert_eq!(bearer_token(Some("bearer  blk_abc ")), Some("blk_abc"));
+  assert_eq!(bearer_token(Some("Basic dXNlcjpwYXNz")), None);
+  assert_eq!(bearer_token(Some("Bearer ")), None);
+  assert_eq!(bearer_token(None), None);
+
+  // 生成的 key 唯一，只保存哈希
+  let key = generate_api_key();
+  assert!(key.starts_with(API_KEY_PREFIX));
+  assert_ne!(key, generate_api_key());
+  let key_hash = hash_api_key(&key);
+  assert_eq!(key_hash.len(), 64);
+  assert!(!key_hash.contains(&key[API_KEY_PREFIX.len()..]));
+  assert!(constant_time_eq(&key_hash, &hash_api_key(&key)));
+  assert!(!constant_time_eq(&key_hash, &hash_api_key("blk_other")));
+
+  assert_eq!(
+    parse_scopes("read, issue").unwrap(),
+    vec![Scope::Read, Scope::Issue]
+  );
+  assert!(parse_scopes("read,root").is_err());
+  assert_eq!(
+    format_scopes(&[Scope::Revoke, Scope::Admin]),
+    "revoke,admin"
+  );
+
+  // 权限检查
+  let mut api_key = ApiKey {
+    name: "ci".to_string(),
+    keyHash: key_hash,
+    scopes: "read,issue".to_string(),
+    createdAt: 1_754_611_200,
+    revokedAt: None,
+  };
+  assert_eq!(check_key(&api_key, Scope::Read), Ok(()));
+  assert_eq!(check_key(&api_key, Scope::Issue), Ok(()));
+  assert_eq!(
+    check_key(&api_key, Scope::Revoke),
+    Err(AuthError::Forbidden(Scope::Revoke))
+  );
+  assert_eq!(AuthError::Forbidden(Scope::Revoke).status(), 403);
+
+  api_key.scopes = "admin".to_string();
+  assert_eq!(check_key(&api_key, Scope::Revoke), Ok(()));
+
+  api_key.revokedAt = Some(1_754_697_600);
+  assert_eq!(check_key(&api_key, Scope::Read), Err(AuthError::Revoked));
+  assert_eq!(AuthError::Revoked.status(), 401);
+}
+
+#[test]
+fn test_encrypt_envelope_format() {
+  // 新版 encrypt 输出混合加密容器
+  use lib::container::{Algorithm, Container, CONTAINER_VERSION};
+
+  let message = "P".repeat(10_000);
+  let encrypted = encrypt(&message).expect("Encryption should succeed");
+  let container = Container::decode(&encrypted).expect("Should be a container");
+  assert_eq!(container.version, CONTAINER_VERSION);
+  assert_eq!(container.algorithm, Algorithm::HybridRsaOaepAes256Gcm);
+  // 头部 key id 与客户端内置私钥推导的一致
+  assert_eq!(
+    container.key_id,
+    lib::Decryptor::default_key().unwrap().key_id().unwrap()
+  );
+
+  // 体积小于 41 个 RSA 块的 CHUNK 格式
+  let legacy = encrypt_legacy(&message).unwrap();
+  assert!(encrypted.len() < legacy.len());
+
+  let decrypted = decrypt(&encrypted).expect("Decryption should succeed");
+  assert_eq!(decrypted, message);
+}
+
+#[test]
+fn test_encrypt_envelope_large_message() {
+  // 旧格式最多 100 块（约 24KB），信封没有这个限制
+  let message = "L".repeat(100 * 1024);
+  assert!(encrypt_legacy(&message).is_err());
+
+  let encrypted = encrypt(&message).expect("Large message should encrypt");
+  let decrypted = decrypt(&encrypted).expect("Large message should decrypt");
+  assert_eq!(decrypted, message);
+}
+
+#[test]
+fn test_encrypt_envelope_tamper_detection() {
+  use lib::container::Container;
+  use lib::LicenseError;
+
+  let encrypted = encrypt("seats=10").unwrap();
+
+  // 修改密文，GCM 认证失败
+  let mut container = Container::decode(&encrypted).unwrap();
+  let last = container.payload.len() - 1;
+  container.payload[last] ^= 1;
+  let tampered = container.encode().unwrap();
+  assert_eq!(decrypt(&tampered), Err(LicenseError::DecryptionFailed));
+
+  // 修改头部中的 key id，AAD 不匹配
+  let mut container = Container::decode(&encrypted).unwrap();
+  container.key_id = "other".to_string();
+  let tampered = container.encode().unwrap();
+  assert_eq!(decrypt(&tampered), Err(LicenseError::DecryptionFailed));
+}
+
+#[test]
+fn test_encrypt_rsa_oaep() {
+  // RSA-OAEP 直接加密，多块拼接
+  use lib::container::{Algorithm, Container};
+
+  for message in [
+    "",
+    "seats=10",
+    &"O".repeat(190),
+    &"O".repeat(191),
+    &"中文".repeat(200),
+  ] {
+    let encrypted = encrypt_rsa_oaep(message).expect("Encryption should succeed");
+    let container = Container::decode(&encrypted).expect("Should be a container");
+    assert_eq!(container.algorithm, Algorithm::RsaOaepSha256);
+    assert_eq!(container.payload.len() % 256, 0);
+
+    let decrypted = decrypt(&encrypted).expect("Decryption should succeed");
+    assert_eq!(decrypted, message);
+  }
+
+  // 同样的明文每次密文不同
+  assert_ne!(
+    encrypt_rsa_oaep("same").unwrap(),
+    encrypt_rsa_oaep("same").unwrap()
+  );
+}
+
+#[test]
+fn test_encryptor_handle() {
+  // 共享加密器只解析一次公钥，可重复使用
+  use crate::crypto::shared_encryptor;
+  use lib::issuer::Encryptor;
+  use lib::Decryptor;
+
+  let shared = shared_encryptor();
+  assert!(std::ptr::eq(shared, shared_encryptor()));
+  assert_eq!(
+    shared.key_id(),
+    Decryptor::default_key().unwrap().key_id().unwrap()
+  );
+
+  let encryptor = Encryptor::from_pem(PUBLIC_KEY).unwrap();
+  for i in 0..10 {
+    let message = format!("license #{}", i);
+    assert_eq!(
+      decrypt(&encryptor.encrypt(&message).unwrap()).unwrap(),
+      message
+    );
+    assert_eq!(
+      decrypt(&encryptor.encrypt_rsa_oaep(&message).unwrap()).unwrap(),
+      message
+    );
+  }
+
+  assert!(Encryptor::from_pem("not a key").is_err());
+}
+
+#[test]
+fn test_license_code_schema() {
+  use crate::license::*;
+
+  // D1 返回的行
+  let row = r#"{"id":1,"name":"acme","code":"CODE-1","createdAt":1754611200,
+      "updatedAt":1754611200,"activated":0,"status":"active","expiredAt":null,
+      "remark":null,"createdBy":"ci"}"#;
+  let mut thing: LicenseCode = serde_json::from_str(row).unwrap();
+  assert_eq!(thing.status, LicenseStatus::Active);
+  assert_eq!(thing.createdBy.as_deref(), Some("ci"));
+  assert_eq!(thing.check_usable(1_754_611_200), Ok(()));
+
+  thing.expiredAt = Some(1_754_697_600);
+  assert_eq!(thing.check_usable(1_754_697_599), Ok(()));
+  assert_eq!(
+    thing.check_usable(1_754_697_600),
+    Err("License has expired")
+  );
+
+  thing.expiredAt = None;
+  thing.status = LicenseStatus::Suspended;
+  assert!(thing.check_usable(1_754_611_200).is_err());
+  thing.status = LicenseStatus::Revoked;
+  assert_eq!(
+    thing.check_usable(1_754_611_200),
+    Err("License has been revoked")
+  );
+  assert_eq!(
+    serde_json::to_value(LicenseStatus::Revoked).unwrap(),
+    LicenseStatus::Revoked.name()
+  );
+
+  // 公开接口只返回公开字段，时间沿用旧的文本格式
+  thing.expiredAt = Some(1_786_147_200);
+  let public = serde_json::to_value(PublicLicense::from(&thing)).unwrap();
+  assert_eq!(public["createdAt"], "2025-08-08T00:00:00Z");
+  assert_eq!(public["expiredAt"], "2026-08-08T00:00:00Z");
+  assert_eq!(public["status"], "revoked");
+  assert!(public.get("remark").is_none() && public.get("createdBy").is_none());
+  assert!(public.get("revokeReason").is_none() && public.get("id").is_none());
+  assert_eq!(iso8601(0), "1970-01-01T00:00:00Z");
+  assert_eq!(iso8601(951_868_799), "2000-02-29T23:59:59Z");
+
+  // 旧客户端上传整个 LicenseCode JSON 激活
+  let body: ActivateLicenseRequest = serde_json::from_str(
+    r#"{"name":"acme","code":"CODE-1","createdAt":"2025-08-08","activated":3}"#,
+  )
+  .unwrap();
+  assert_eq!((body.name.as_str(), body.code.as_str()), ("acme", "CODE-1"));
+
+  // 更新时省略的字段为 None，SQL 中保持原值
+  let update: UpdateLicenseRequest =
+    serde_json::from_str(r#"{"name":"acme","status":"suspended"}"#).unwrap();
+  assert_eq!(update.status, Some(LicenseStatus::Suspended));
+  assert!(update.code.is_none() && update.remark.is_none());
+  assert!(serde_json::from_str::<UpdateLicenseRequest>(r#"{"name":"a","status":"gone"}"#).is_err());
+
+  let create: CreateLicenseRequest =
+    serde_json::from_str(r#"{"name":"acme","code":"CODE-1"}"#).unwrap();
+  assert!(create.expiredAt.is_none() && create.remark.is_none());
+}
+
+#[test]
+fn test_revocation_list() {
+  // 吊销的 license 和密钥生成签名的吊销列表，客户端据此拒绝已签发的 license
+  use crate::crypto::sign_revocation_list;
+  use crate::license::*;
+  use ed25519_dalek::pkcs8::EncodePrivateKey;
+  use lib::issuer::SigningKey;
+  use lib::{Keyring, LicenseError, LicensePayload, RevocationList, VerifyingKey};
+  use rsa::pkcs8::LineEnding;
+
+  let now = 1_754_611_200;
+  let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+  let signing_key_pem = signing_key.to_pkcs8_pem(LineEnding::LF).unwrap();
+  let mut keyring = Keyring::new();
+  keyring
+    .add_verifying_key(VerifyingKey::Ed25519(signing_key.verifying_key()))
+    .unwrap();
+
+  let row = |name: &str, status: LicenseStatus, revoked_at: Option<i64>| LicenseCode {
+    id: 1,
+    name: name.to_string(),
+    code: format!("{}-CODE", name),
+    createdAt: now - 86400,
+    updatedAt: now - 60,
+    activated: 1,
+    status,
+    expiredAt: None,
+    remark: None,
+    createdBy: None,
+    revokedAt: revoked_at,
+    revokeReason: revoked_at.map(|_| "refunded".to_string()),
+    maxActivations: None,
+  };
+  let licenses = [
+    row("acme", LicenseStatus::Revoked, Some(now - 3600)),
+    row("globex", LicenseStatus::Active, None),
+    // 通过 PATCH 改为 revoked 的旧记录没有吊销时间
+    row("initech", LicenseStatus::Revoked, None),
+  ];
+  let keys = [RevokedKeyRecord {
+    keyId: "0011223344556677".to_string(),
+    revokedAt: now - 7200,
+    reason: None,
+  }];
+
+  let list = revocation_list(&licenses, &keys, now);
+  assert_eq!(list.issued_at, now);
+  assert_eq!(list.next_update, Some(now + REVOCATION_LIST_TTL));
+  assert_eq!(list.licenses.len(), 2);
+  assert_eq!(list.licenses[0].reason.as_deref(), Some("refunded"));
+  assert_eq!(list.licenses[1].revoked_at, now - 60);
+  assert_eq!(list.keys[0].key_id, "0011223344556677");
+
+  let token = sign_revocation_list(&list, &signing_key_pem).unwrap();
+  let verified = RevocationList::verify(&keyring, &token).unwrap();
+  assert_eq!(verified, list);
+
+  let issuer = SigningKey::from_pkcs8_pem(&signing_key_pem).unwrap();
+  let mut payload = LicensePayload::new("Acme", now - 86400);
+  payload.license_id = Some("acme".to_string());
+  let license = issuer.sign_payload(&payload).unwrap();
+  assert_eq!(
+    verified.decode_license(&keyring, &license),
+    Err(LicenseError::Revoked(now - 3600))
+  );
+  payload.license_id = Some("globex".to_string());
+  let license = issuer.sign_payload(&payload).unwrap();
+  assert!(verified.decode_license(&keyring, &license).is_ok());
+
+  assert!(sign_revocation_list(&list, "not a key").is_err());
+}
+
+#[test]
+fn test_activation_limit() {
+  use crate::license::*;
+
+  let row = r#"{"id":1,"name":"acme","code":"CODE-1","createdAt":1754611200,
+      "updatedAt":1754611200,"activated":2,"status":"active","expiredAt":null,
+      "remark":null,"createdBy":"ci","revokedAt":null,"revokeReason":null,"maxActivations":3}"#;
+  let thing: LicenseCode = serde_json::from_str(row).unwrap();
+  assert_eq!(thing.maxActivations, Some(3));
+  // 重复激活优先于席位检查
+  assert!(matches!(
+    ActivationOutcome::from_batch(1, 0, thing.clone()),
+    ActivationOutcome::Reactivated(_)
+  ));
+  assert!(matches!(
+    ActivationOutcome::from_batch(0, 1, thing.clone()),
+    ActivationOutcome::Activated(_)
+  ));
+  assert!(matches!(
+    ActivationOutcome::from_batch(0, 0, thing),
+    ActivationOutcome::LimitReached(_)
+  ));
+
+  let activation: Activation = serde_json::from_str(
+    r#"{"id":7,"licenseId":1,"fingerprint":"5f0c2a","ip":"203.0.113.7",
+      "userAgent":"acme-app/1.2","createdAt":1754611200,"lastSeenAt":1754697600}"#,
+  )
+  .unwrap();
+  assert_eq!(activation.fingerprint.as_deref(), Some("5f0c2a"));
+  assert!(activation.lastSeenAt > activation.createdAt);
+
+  // 旧客户端不上传 fingerprint
+  let body: ActivateLicenseRequest =
+    serde_json::from_str(r#"{"name":"acme","code":"CODE-1"}"#).unwrap();
+  assert!(body.fingerprint.is_none());
+  let body: ActivateLicenseRequest =
+    serde_json::from_str(r#"{"name":"acme","code":"CODE-1","fingerprint":"5f0c2a"}"#).unwrap();
+  assert_eq!(body.fingerprint.as_deref(), Some("5f0c2a"));
+
+  // 释放席位必须指定机器
+  assert!(
+    serde_json::from_str::<DeactivateLicenseRequest>(r#"{"name":"acme","code":"CODE-1"}"#).is_err()
+  );
+
+  let create: CreateLicenseRequest =
+    serde_json::from_str(r#"{"name":"acme","code":"CODE-1","maxActivations":3}"#).unwrap();
+  assert_eq!(create.maxActivations, Some(3));
+}
+
+// 本地 SQLite 代替 D1：执行 migrations，batch 在一个 IMMEDIATE 事务中执行，同 D1 串行执行 batch
+mod sqlite {
+  use crate::license::*;
+  use rusqlite::types::{Value, ValueRef};
+  use rusqlite::{params_from_iter, Connection, TransactionBehavior};
+  use std::path::{Path, PathBuf};
+  use std::time::Duration;
+
+  const MIGRATIONS: [&str; 4] = [
+    include_str!("../migrations/0001_create_api_keys.sql"),
+    include_str!("../migrations/0002_license_code.sql"),
+    include_str!("../migrations/0003_revocation.sql"),
+    include_str!("../migrations/0004_activation.sql"),
+  ];
+
+  pub const NOW: i64 = 1_754_611_200;
+
+  // 临时数据库文件，drop 时删除，断言失败时也不会留下文件
+  // 在连接之前声明，连接先于它 drop
+  pub struct TempDb(PathBuf);
+
+  impl TempDb {
+    pub fn new(name: &str) -> Self {
+      let path =
+        std::env::temp_dir().join(format!("license-server-{}-{}.db", name, std::process::id()));
+      let _ = std::fs::remove_file(&path);
+      TempDb(path)
+    }
+
+    pub fn path(&self) -> &Path {
+      &self.0
+    }
+  }
+
+  impl Drop for TempDb {
+    fn drop(&mut self) {
+      let _ = std::fs::remove_file(&self.0);
+    }
+  }
+
+  pub fn open(path: &Path) -> Connection {
+    let conn = Connection::open(path).unwrap();
+    conn.busy_timeout(Duration::from_secs(30)).unwrap();
+    conn
+  }
+
+  pub fn create(path: &Path) -> Connection {
+    let conn = open(path);
+    migrate(&conn);
+    conn
+  }
+
+  pub fn migrate(conn: &Connection) {
+    for migration in MIGRATIONS {
+      conn.execute_batch(migration).unwrap();
+    }
+  }
+
+  pub fn insert_license(conn: &Connection, name: &str, max_activations: Option<u32>) -> i64 {
+    conn
+      .query_row(
+        "INSERT INTO LicenseCode (name, code, createdAt, updatedAt, maxActivations) \
+         VALUES (?1, ?1, ?2, ?2, ?3) RETURNING id",
+        rusqlite::params![name, NOW, max_activations],
+        |row| row.get(0),
+      )
+      .unwrap()
+  }
+
+  // 同 D1 batch，每条语句的结果行转为 JSON，与 D1Result::results 一样按列名反序列化
+  fn batch(
+    conn: &mut Connection,
+    statements: &[(&str, usize)],
+    params: &[Value],
+  ) -> Vec<Vec<serde_json::Value>> {
+    let tx = conn
+      .transaction_with_behavior(TransactionBehavior::Immediate)
+      .unwrap();
+    let mut results = Vec::new();
+    for (sql, count) in statements {
+      let mut statement = tx.prepare(sql).unwrap();
+      let names: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+      let mut rows = statement
+        .query(params_from_iter(&params[..*count]))
+        .unwrap();
+      let mut result = Vec::new();
+      while let Some(row) = rows.next().unwrap() {
+        let mut object = serde_json::Map::new();
+        for (index, name) in names.iter().enumerate() {
+          let value = match row.get_ref(index).unwrap() {
+            ValueRef::Null => serde_json::Value::Null,
+            ValueRef::Integer(value) => value.into(),
+            ValueRef::Real(value) => value.into(),
+            ValueRef::Text(value) => String::from_utf8_lossy(value).into(),
+            ValueRef::Blob(_) => panic!("Unexpected blob column {}", name),
+          };
+          object.insert(name.clone(), value);
+        }
+        result.push(serde_json::Value::Object(object));
       }
+      results.push(result);
     }
+    tx.commit().unwrap();
+    results
+  }
+
+  pub fn activate(
+    conn: &mut Connection,
+    license_id: i64,
+    fingerprint: Option<&str>,
+  ) -> ActivationOutcome {
+    let params = [
+      Value::Integer(license_id),
+      fingerprint.map_or(Value::Null, |fingerprint| Value::Text(fingerprint.into())),
+      Value::Integer(NOW),
+      Value::Text("203.0.113.7".into()),
+      Value::Text("acme-app/1.2".into()),
+    ];
+    let results = batch(conn, &ACTIVATION_STATEMENTS, &params);
+    let thing = serde_json::from_value(results[3][0].clone()).unwrap();
+    ActivationOutcome::from_batch(results[0].len(), results[1].len(), thing)
+  }
+
+  pub fn deactivate(
+    conn: &mut Connection,
+    license_id: i64,
+    fingerprint: &str,
+  ) -> Option<LicenseCode> {
+    let params = [
+      Value::Integer(license_id),
+      Value::Text(fingerprint.into()),
+      Value::Integer(NOW + 60),
+    ];
+    let results = batch(conn, &DEACTIVATION_STATEMENTS, &params);
+    results[1]
+      .first()
+      .map(|row| serde_json::from_value(row.clone()).unwrap())
   }
 
-  #[test]
-  fn test_encrypt_performance() {
-    // 测试加密性能
-    use std::time::Instant;
+  pub fn count_activations(conn: &Connection, license_id: i64) -> i64 {
+    conn
+      .query_row(
+        "SELECT COUNT(*) FROM Activation WHERE licenseId = ?1",
+        [license_id],
+        |row| row.get(0),
+      )
+      .unwrap()
+  }
+}
 
-    let test_sizes = vec![(1024, "1KB"), (5120, "5KB"), (10240, "10KB")];
+// 多个连接同时激活，并发下计数不丢失、不超出上限
+fn activate_concurrently(
+  path: &std::path::Path,
+  license_id: i64,
+  fingerprints: Vec<Option<String>>,
+) -> Vec<crate::license::ActivationOutcome> {
+  let barrier = std::sync::Arc::new(std::sync::Barrier::new(fingerprints.len()));
+  let handles: Vec<_> = fingerprints
+    .into_iter()
+    .map(|fingerprint| {
+      let path = path.to_path_buf();
+      let barrier = barrier.clone();
+      std::thread::spawn(move || {
+        let mut conn = sqlite::open(&path);
+        barrier.wait();
+        sqlite::activate(&mut conn, license_id, fingerprint.as_deref())
+      })
+    })
+    .collect();
+  handles
+    .into_iter()
+    .map(|handle| handle.join().unwrap())
+    .collect()
+}
 
-    for (size, name) in test_sizes {
-      let message = "P".repeat(size);
+#[test]
+fn test_concurrent_activation() {
+  use crate::license::*;
 
-      let start = Instant::now();
-      let result = encrypt(&message);
-      let duration = start.elapsed();
+  let db = sqlite::TempDb::new("activation");
+  let mut conn = sqlite::create(db.path());
+  let limited = sqlite::insert_license(&conn, "acme", Some(5));
+  let unlimited = sqlite::insert_license(&conn, "globex", None);
 
-      assert!(
-        result.is_ok(),
-        "{} message should encrypt successfully",
-        name
-      );
+  // 20 台机器抢 5 个席位
+  let outcomes = activate_concurrently(
+    db.path(),
+    limited,
+    (0..20).map(|i| Some(format!("machine-{}", i))).collect(),
+  );
+  let mut seats: Vec<u32> = outcomes
+    .iter()
+    .filter_map(|outcome| match outcome {
+      ActivationOutcome::Activated(thing) => Some(thing.activated),
+      _ => None,
+    })
+    .collect();
+  seats.sort();
+  // 每次占用返回递增后的计数
+  assert_eq!(seats, vec![1, 2, 3, 4, 5]);
+  assert_eq!(
+    outcomes
+      .iter()
+      .filter(|outcome| matches!(outcome, ActivationOutcome::LimitReached(_)))
+      .count(),
+    15
+  );
+  assert_eq!(sqlite::count_activations(&conn, limited), 5);
 
-      if let Ok(encrypted) = result {
-        let decrypt_start = Instant::now();
-        let decrypted = decrypt(&encrypted).expect(&format!("{} message should decrypt", name));
-        let decrypt_duration = decrypt_start.elapsed();
+  // 已激活的机器同时重复激活，不占用新席位
+  let activated = match &outcomes
+    .iter()
+    .zip(0..)
+    .find(|(outcome, _)| matches!(outcome, ActivationOutcome::Activated(_)))
+  {
+    Some((_, index)) => format!("machine-{}", index),
+    None => unreachable!(),
+  };
+  let outcomes = activate_concurrently(db.path(), limited, vec![Some(activated.clone()); 8]);
+  assert!(outcomes.iter().all(|outcome| matches!(
+    outcome,
+    ActivationOutcome::Reactivated(thing) if thing.activated == 5
+  )));
+  assert_eq!(sqlite::count_activations(&conn, limited), 5);
 
-        assert_eq!(decrypted, message);
+  // 释放席位后新机器可以激活，重复释放返回 None
+  let thing = sqlite::deactivate(&mut conn, limited, &activated).unwrap();
+  assert_eq!(thing.activated, 4);
+  assert!(sqlite::deactivate(&mut conn, limited, &activated).is_none());
+  assert!(sqlite::deactivate(&mut conn, limited, "unknown").is_none());
+  assert!(matches!(
+    sqlite::activate(&mut conn, limited, Some("machine-new")),
+    ActivationOutcome::Activated(thing) if thing.activated == 5
+  ));
+  assert!(matches!(
+    sqlite::activate(&mut conn, limited, Some(&activated)),
+    ActivationOutcome::LimitReached(thing) if thing.activated == 5
+  ));
 
-        println!(
-          "✓ Performance test {}: encrypt {}ms, decrypt {}ms",
-          name,
-          duration.as_millis(),
-          decrypt_duration.as_millis()
-        );
+  // 不限席位，旧客户端不上传 fingerprint，每次激活都计数
+  let outcomes = activate_concurrently(db.path(), unlimited, vec![None; 10]);
+  assert!(outcomes
+    .iter()
+    .all(|outcome| matches!(outcome, ActivationOutcome::Activated(_))));
+  match sqlite::activate(&mut conn, unlimited, None) {
+    ActivationOutcome::Activated(thing) => assert_eq!(thing.activated, 11),
+    outcome => panic!("Unexpected outcome {:?}", outcome),
+  }
+  assert_eq!(sqlite::count_activations(&conn, unlimited), 11);
+}
 
-        // 性能要求：加密和解密都应该在合理时间内完成
-        assert!(
-          duration.as_millis() < 5000,
-          "{} encryption should complete within 5 seconds",
-          name
-        );
-        assert!(
-          decrypt_duration.as_millis() < 3000,
-          "{} decryption should complete within 3 seconds",
-          name
-        );
+#[test]
+fn test_activation_requires_usable_license() {
+  use crate::license::*;
+
+  // 查询之后 license 被吊销、暂停或过期，batch 中不能占用席位或刷新激活记录
+  let db = sqlite::TempDb::new("usable");
+  let mut conn = sqlite::create(db.path());
+  let cases = [
+    (
+      "revoked",
+      "UPDATE LicenseCode SET status = 'revoked', revokedAt = ?2 WHERE id = ?1",
+    ),
+    (
+      "suspended",
+      "UPDATE LicenseCode SET status = 'suspended', updatedAt = ?2 WHERE id = ?1",
+    ),
+    (
+      "expired",
+      "UPDATE LicenseCode SET expiredAt = ?2 WHERE id = ?1",
+    ),
+  ];
+  for (name, sql) in cases {
+    let id = sqlite::insert_license(&conn, name, None);
+    assert!(matches!(
+      sqlite::activate(&mut conn, id, Some("machine-1")),
+      ActivationOutcome::Activated(_)
+    ));
+    conn
+      .execute(sql, rusqlite::params![id, sqlite::NOW])
+      .unwrap();
+
+    for fingerprint in ["machine-1", "machine-2"] {
+      match sqlite::activate(&mut conn, id, Some(fingerprint)) {
+        ActivationOutcome::LimitReached(thing) => {
+          assert_eq!(thing.activated, 1, "{}", name);
+          assert!(thing.check_usable(sqlite::NOW).is_err(), "{}", name);
+        }
+        outcome => panic!("Unexpected outcome for {}: {:?}", name, outcome),
       }
     }
+    assert_eq!(sqlite::count_activations(&conn, id), 1);
   }
 }
+
+#[test]
+fn test_migrate_duplicate_licenses() {
+  // 旧表没有唯一约束，重复的 name 或 code 改名后保留，迁移不会失败
+  let db = sqlite::TempDb::new("dup");
+  let conn = sqlite::open(db.path());
+  conn
+    .execute_batch(
+      "CREATE TABLE LicenseCode (name TEXT NOT NULL, code TEXT NOT NULL, createdAt TEXT, \
+       activated INTEGER NOT NULL DEFAULT 0);
+       INSERT INTO LicenseCode VALUES ('acme', 'C1', '2025-08-08', 1);
+       INSERT INTO LicenseCode VALUES ('acme', 'C2', '1754611200000', 0);
+       INSERT INTO LicenseCode VALUES ('globex', 'C1', NULL, 0);
+       INSERT INTO LicenseCode VALUES ('initech', 'C3', '1754611200', 2);",
+    )
+    .unwrap();
+  sqlite::migrate(&conn);
+
+  let mut statement = conn
+    .prepare("SELECT name, code, createdAt, activated, remark FROM LicenseCode ORDER BY id")
+    .unwrap();
+  let rows: Vec<(String, String, i64, u32, Option<String>)> = statement
+    .query_map([], |row| {
+      Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+      ))
+    })
+    .unwrap()
+    .collect::<rusqlite::Result<_>>()
+    .unwrap();
+  let renamed: Vec<(&str, &str, Option<&str>)> = rows
+    .iter()
+    .map(|(name, code, _, _, remark)| (name.as_str(), code.as_str(), remark.as_deref()))
+    .collect();
+  assert_eq!(
+    renamed,
+    vec![
+      ("acme", "C1", None),
+      (
+        "acme#2",
+        "C2",
+        Some("Duplicate name renamed by migration 0002")
+      ),
+      (
+        "globex",
+        "C1#3",
+        Some("Duplicate code renamed by migration 0002")
+      ),
+      ("initech", "C3", None),
+    ]
+  );
+  assert_eq!(rows[0].2, sqlite::NOW);
+  assert_eq!(rows[1].2, sqlite::NOW);
+  assert_eq!(rows[3].2, sqlite::NOW);
+  assert_eq!(rows[3].3, 2);
+
+  // 唯一索引已经生效
+  assert!(conn
+    .execute(
+      "INSERT INTO LicenseCode (name, code, createdAt, updatedAt) VALUES ('acme', 'C9', 0, 0)",
+      [],
+    )
+    .is_err());
+}
+
+#[test]
+fn test_revoked_license_is_final() {
+  use crate::license::REVOKED_LICENSE_ERROR;
+
+  // 吊销的 license 不能删除、恢复或改名，否则会从之后的吊销列表中消失
+  let db = sqlite::TempDb::new("crl");
+  let conn = sqlite::create(db.path());
+  sqlite::insert_license(&conn, "acme", None);
+  sqlite::insert_license(&conn, "globex", None);
+  conn
+    .execute(
+      "UPDATE LicenseCode SET status = 'revoked', revokedAt = ?1 WHERE name = 'acme'",
+      [sqlite::NOW],
+    )
+    .unwrap();
+
+  for sql in [
+    "DELETE FROM LicenseCode WHERE name = 'acme'",
+    "UPDATE LicenseCode SET status = 'active' WHERE name = 'acme'",
+    "UPDATE LicenseCode SET name = 'acme-2' WHERE name = 'acme'",
+  ] {
+    let error = conn.execute(sql, []).unwrap_err();
+    assert!(
+      error.to_string().contains(REVOKED_LICENSE_ERROR),
+      "{}",
+      error
+    );
+  }
+  // 重复吊销和修改其他字段不受影响
+  conn
+    .execute(
+      "UPDATE LicenseCode SET status = 'revoked', remark = 'refunded' WHERE name = 'acme'",
+      [],
+    )
+    .unwrap();
+
+  // 未吊销的 license 可以暂停、恢复和删除
+  conn
+    .execute(
+      "UPDATE LicenseCode SET status = 'suspended' WHERE name = 'globex'",
+      [],
+    )
+    .unwrap();
+  conn
+    .execute(
+      "UPDATE LicenseCode SET status = 'active' WHERE name = 'globex'",
+      [],
+    )
+    .unwrap();
+  assert_eq!(
+    conn
+      .execute("DELETE FROM LicenseCode WHERE name = 'globex'", [])
+      .unwrap(),
+    1
+  );
+}
@@ -1,18 +1,618 @@
 #![deny(clippy::all)]
 
+use std::collections::HashMap;
+
 use lib::decrypt as lib_decrypt;
+use lib::{
+  Fingerprint, Keyring, LicenseError, LicenseStore, RevocationList, StoredLicense,
+  ValidationOptions, Validator, Validity,
+};
+use napi::bindgen_prelude::{AsyncTask, Either, JsObjectValue};
+use napi::{Env, Status, Task};
 use napi_derive::napi;
+use serde_json::{Map, Value};
 
 #[napi]
 pub fn sum(a: i32, b: i32) -> i32 {
   a + b
 }
 
+// JS Error 的 code 属性，与 LicenseError::code 一一对应
+#[napi(string_enum)]
+pub enum LicenseErrorCode {
+  #[napi(value = "LICENSE_EMPTY_INPUT")]
+  EmptyInput,
+  #[napi(value = "LICENSE_INPUT_TOO_LONG")]
+  InputTooLong,
+  #[napi(value = "LICENSE_BAD_BASE64")]
+  InvalidBase64,
+  #[napi(value = "LICENSE_BAD_CHUNK_HEADER")]
+  InvalidChunkHeader,
+  #[napi(value = "LICENSE_BAD_BLOCK_SIZE")]
+  InvalidBlockSize,
+  #[napi(value = "LICENSE_DECRYPT_FAILED")]
+  DecryptionFailed,
+  #[napi(value = "LICENSE_BAD_PADDING")]
+  InvalidPadding,
+  #[napi(value = "LICENSE_PLAINTEXT_TOO_LONG")]
+  PlaintextTooLong,
+  #[napi(value = "LICENSE_BAD_UTF8")]
+  InvalidUtf8,
+  #[napi(value = "LICENSE_KEY_LOAD_FAILED")]
+  KeyLoad,
+  #[napi(value = "LICENSE_BAD_FORMAT")]
+  InvalidFormat,
+  #[napi(value = "LICENSE_BAD_SIGNATURE")]
+  InvalidSignature,
+  #[napi(value = "LICENSE_UNSUPPORTED_ALGORITHM")]
+  UnsupportedAlgorithm,
+  #[napi(value = "LICENSE_BAD_PAYLOAD")]
+  InvalidPayload,
+  #[napi(value = "LICENSE_EXPIRED")]
+  Expired,
+  #[napi(value = "LICENSE_NOT_YET_VALID")]
+  NotYetValid,
+  #[napi(value = "LICENSE_UNSUPPORTED_VERSION")]
+  UnsupportedVersion,
+  #[napi(value = "LICENSE_UNKNOWN_KEY")]
+  UnknownKeyId,
+  #[napi(value = "LICENSE_MISSING_FEATURE")]
+  MissingFeature,
+  #[napi(value = "LICENSE_ENCRYPT_FAILED")]
+  EncryptionFailed,
+  #[napi(value = "LICENSE_FINGERPRINT_MISMATCH")]
+  FingerprintMismatch,
+  #[napi(value = "LICENSE_ACTIVATION_MISMATCH")]
+  ActivationMismatch,
+  #[napi(value = "LICENSE_STORAGE_FAILED")]
+  Storage,
+  #[napi(value = "LICENSE_STORE_TAMPERED")]
+  StoreTampered,
+  #[napi(value = "LICENSE_CLOCK_ROLLBACK")]
+  ClockRollback,
+  #[napi(value = "LICENSE_REVOKED")]
+  Revoked,
+  #[napi(value = "LICENSE_BAD_OPTIONS")]
+  InvalidOptions,
+}
+
+impl From<&LicenseError> for LicenseErrorCode {
+  fn from(error: &LicenseError) -> Self {
+    match error {
+      LicenseError::EmptyInput => LicenseErrorCode::EmptyInput,
+      LicenseError::InputTooLong => LicenseErrorCode::InputTooLong,
+      LicenseError::InvalidBase64 => LicenseErrorCode::InvalidBase64,
+      LicenseError::InvalidChunkHeader(_) => LicenseErrorCode::InvalidChunkHeader,
+      LicenseError::InvalidBlockSize => LicenseErrorCode::InvalidBlockSize,
+      LicenseError::DecryptionFailed => LicenseErrorCode::DecryptionFailed,
+      LicenseError::InvalidPadding => LicenseErrorCode::InvalidPadding,
+      LicenseError::PlaintextTooLong => LicenseErrorCode::PlaintextTooLong,
+      LicenseError::InvalidUtf8 => LicenseErrorCode::InvalidUtf8,
+      LicenseError::KeyLoad(_) => LicenseErrorCode::KeyLoad,
+      LicenseError::InvalidFormat(_) => LicenseErrorCode::InvalidFormat,
+      LicenseError::InvalidSignature => LicenseErrorCode::InvalidSignature,
+      LicenseError::UnsupportedAlgorithm(_) => LicenseErrorCode::UnsupportedAlgorithm,
+      LicenseError::InvalidPayload(_) => LicenseErrorCode::InvalidPayload,
+      LicenseError::Expired(_) => LicenseErrorCode::Expired,
+      LicenseError::NotYetValid(_) => LicenseErrorCode::NotYetValid,
+      LicenseError::UnsupportedVersion(_) => LicenseErrorCode::UnsupportedVersion,
+      LicenseError::UnknownKeyId(_) => LicenseErrorCode::UnknownKeyId,
+      LicenseError::MissingFeature(_) => LicenseErrorCode::MissingFeature,
+      LicenseError::EncryptionFailed(_) => LicenseErrorCode::EncryptionFailed,
+      LicenseError::FingerprintMismatch => LicenseErrorCode::FingerprintMismatch,
+      LicenseError::ActivationMismatch(_) => LicenseErrorCode::ActivationMismatch,
+      LicenseError::Storage(_) => LicenseErrorCode::Storage,
+      LicenseError::StoreTampered => LicenseErrorCode::StoreTampered,
+      LicenseError::ClockRollback(_) => LicenseErrorCode::ClockRollback,
+      LicenseError::Revoked(_) => LicenseErrorCode::Revoked,
+      LicenseError::InvalidOptions(_) => LicenseErrorCode::InvalidOptions,
+    }
+  }
+}
+
+// JS Error 的 details 属性，只包含与错误相关的字段
+#[napi(object)]
+#[derive(Default)]
+pub struct LicenseErrorDetails {
+  // 格式错误或密钥加载失败的原因
+  pub reason: Option<String>,
+  pub algorithm: Option<String>,
+  pub version: Option<u32>,
+  pub expires_at: Option<i64>,
+  pub not_before: Option<i64>,
+  pub key_id: Option<String>,
+  pub feature: Option<String>,
+  // 时钟回拨时上次看到的时间
+  pub last_seen: Option<i64>,
+  pub revoked_at: Option<i64>,
+}
+
+impl From<&LicenseError> for LicenseErrorDetails {
+  fn from(error: &LicenseError) -> Self {
+    let mut details = LicenseErrorDetails::default();
+    match error {
+      LicenseError::InvalidChunkHeader(reason)
+      | LicenseError::InvalidFormat(reason)
+      | LicenseError::ActivationMismatch(reason)
+      | LicenseError::InvalidOptions(reason) => details.reason = Some(reason.to_string()),
+      LicenseError::KeyLoad(reason)
+      | LicenseError::InvalidPayload(reason)
+      | LicenseError::EncryptionFailed(reason)
+      | LicenseError::Storage(reason) => details.reason = Some(reason.clone()),
+      LicenseError::UnsupportedAlgorithm(algorithm) => details.algorithm = Some(algorithm.clone()),
+      LicenseError::UnsupportedVersion(version) => details.version = Some(u32::from(*version)),
+      LicenseError::Expired(expires_at) => details.expires_at = Some(*expires_at),
+      LicenseError::NotYetValid(not_before) => details.not_before = Some(*not_before),
+      LicenseError::UnknownKeyId(key_id) => details.key_id = Some(key_id.clone()),
+      LicenseError::MissingFeature(feature) => details.feature = Some(feature.clone()),
+      LicenseError::ClockRollback(last_seen) => details.last_seen = Some(*last_seen),
+      LicenseError::Revoked(revoked_at) => details.revoked_at = Some(*revoked_at),
+      _ => {}
+    }
+    details
+  }
+}
+
+// 抛出带 code 和 details 属性的 JS Error，在异步任务中则以该错误 reject Promise
+fn throw_license_error<T>(env: &Env, error: LicenseError) -> napi::Result<T> {
+  let mut js_error = env.create_error(napi::Error::from_reason(error.to_string()))?;
+  js_error.set_named_property("code", LicenseErrorCode::from(&error))?;
+  js_error.set_named_property("details", LicenseErrorDetails::from(&error))?;
+  env.throw(js_error)?;
+  Err(napi::Error::from_status(Status::PendingException))
+}
+
+#[napi]
+pub fn decrypt(env: Env, encrypted_message: String) -> napi::Result<String> {
+  lib_decrypt(&encrypted_message).or_else(|error| throw_license_error(&env, error))
+}
+
+// 在 libuv 线程池中解密，不阻塞事件循环
+pub struct DecryptTask {
+  encrypted_message: String,
+}
+
+#[napi]
+impl Task for DecryptTask {
+  type Output = Result<String, LicenseError>;
+  type JsValue = String;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(lib_decrypt(&self.encrypted_message))
+  }
+
+  fn resolve(&mut self, env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    match output {
+      Ok(result) => Ok(result),
+      Err(error) => throw_license_error(&env, error),
+    }
+  }
+}
+
+#[napi]
+pub fn decrypt_async(encrypted_message: String) -> AsyncTask<DecryptTask> {
+  AsyncTask::new(DecryptTask { encrypted_message })
+}
+
+// 批量解密的单条结果，失败时带错误码，不影响其他条目
+#[napi(object)]
+pub struct DecryptResult {
+  pub ok: bool,
+  pub value: Option<String>,
+  pub code: Option<LicenseErrorCode>,
+  pub message: Option<String>,
+  pub details: Option<LicenseErrorDetails>,
+}
+
+impl From<Result<String, LicenseError>> for DecryptResult {
+  fn from(result: Result<String, LicenseError>) -> Self {
+    match result {
+      Ok(value) => DecryptResult {
+        ok: true,
+        value: Some(value),
+        code: None,
+        message: None,
+        details: None,
+      },
+      Err(error) => DecryptResult {
+        ok: false,
+        value: None,
+        code: Some(LicenseErrorCode::from(&error)),
+        message: Some(error.to_string()),
+        details: Some(LicenseErrorDetails::from(&error)),
+      },
+    }
+  }
+}
+
+pub struct DecryptManyTask {
+  encrypted_messages: Vec<String>,
+}
+
+#[napi]
+impl Task for DecryptManyTask {
+  type Output = Vec<DecryptResult>;
+  type JsValue = Vec<DecryptResult>;
+
+  fn compute(&mut self) -> napi::Result<Self::Output> {
+    Ok(
+      self
+        .encrypted_messages
+        .iter()
+        .map(|message| DecryptResult::from(lib_decrypt(message)))
+        .collect(),
+    )
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+// 批量解密，按输入顺序返回每条结果
+#[napi]
+pub fn decrypt_many(encrypted_messages: Vec<String>) -> AsyncTask<DecryptManyTask> {
+  AsyncTask::new(DecryptManyTask { encrypted_messages })
+}
+
+// license 内容，对应 lib::LicensePayload
+#[napi(object, js_name = "LicensePayload")]
+pub struct JsLicensePayload {
+  pub licensee: String,
+  // 对应服务端 LicenseCode 的 name，吊销列表按它匹配
+  pub license_id: Option<String>,
+  pub issued_at: i64,
+  pub expires_at: Option<i64>,
+  pub not_before: Option<i64>,
+  pub max_seats: Option<u32>,
+  pub edition: Option<String>,
+  pub features: Vec<String>,
+  pub claims: Map<String, Value>,
+  // 绑定的机器指纹，每项为组件名到组件哈希
+  pub fingerprints: Vec<HashMap<String, String>>,
+}
+
+impl From<lib::LicensePayload> for JsLicensePayload {
+  fn from(payload: lib::LicensePayload) -> Self {
+    JsLicensePayload {
+      licensee: payload.licensee,
+      license_id: payload.license_id,
+      issued_at: payload.issued_at,
+      expires_at: payload.expires_at,
+      not_before: payload.not_before,
+      max_seats: payload.max_seats,
+      edition: payload.edition,
+      features: payload.features,
+      claims: payload.claims,
+      fingerprints: payload
+        .fingerprints
+        .iter()
+        .map(|fingerprint| fingerprint.components().clone().into_iter().collect())
+        .collect(),
+    }
+  }
+}
+
+// 机器指纹，components 为组件名到组件哈希，签发时写入 license 的 fingerprints
+#[napi(object)]
+pub struct MachineFingerprint {
+  pub id: String,
+  pub components: HashMap<String, String>,
+}
+
+impl From<&Fingerprint> for MachineFingerprint {
+  fn from(fingerprint: &Fingerprint) -> Self {
+    MachineFingerprint {
+      id: fingerprint.id(),
+      components: fingerprint.components().clone().into_iter().collect(),
+    }
+  }
+}
+
+// 计算当前机器的指纹：machine-id、主机名、MAC 地址、CPU 信息
 #[napi]
-pub fn decrypt(encrypted_message: String) -> napi::Result<String> {
-  match lib_decrypt(&encrypted_message) {
-    Ok(result) => Ok(result),
-    Err(error) => Err(napi::Error::from_reason(error)),
+pub fn get_machine_fingerprint() -> MachineFingerprint {
+  MachineFingerprint::from(&Fingerprint::current())
+}
+
+// LicenseVerifier 选项，时间单位为秒
+#[napi(object)]
+pub struct LicenseVerifierOptions {
+  pub clock_skew: Option<i64>,
+  pub grace_period: Option<i64>,
+  pub required_features: Option<Vec<String>>,
+  // 绑定机器的 license 允许变化的指纹组件数，默认 0
+  pub fingerprint_tolerance: Option<u32>,
+  // 代替当前机器指纹参与比对，取 getMachineFingerprint().components
+  pub machine_fingerprint: Option<HashMap<String, String>>,
+  // 吊销列表文本（服务端 /crl 的响应或 StoredLicense.revocationList），用同一组密钥校验
+  pub revocation_list: Option<String>,
+}
+
+// 吊销列表概要，对应 lib::RevocationList
+#[napi(object)]
+pub struct RevocationListInfo {
+  pub issued_at: i64,
+  // 建议的下次更新时间
+  pub next_update: Option<i64>,
+  pub license_ids: Vec<String>,
+  pub key_ids: Vec<String>,
+}
+
+impl From<&RevocationList> for RevocationListInfo {
+  fn from(list: &RevocationList) -> Self {
+    RevocationListInfo {
+      issued_at: list.issued_at,
+      next_update: list.next_update,
+      license_ids: list
+        .licenses
+        .iter()
+        .map(|revoked| revoked.license_id.clone())
+        .collect(),
+      key_ids: list
+        .keys
+        .iter()
+        .map(|revoked| revoked.key_id.clone())
+        .collect(),
+    }
+  }
+}
+
+// keys 为 PEM 字符串或数组：RSA 私钥用于解密，公钥用于验签；不传时使用内置密钥
+fn keyring_from_keys(keys: Option<Either<String, Vec<String>>>) -> Result<Keyring, LicenseError> {
+  let pems = match keys {
+    None => return Keyring::with_default_keys(),
+    Some(Either::A(pem)) => vec![pem],
+    Some(Either::B(pems)) => pems,
+  };
+  let mut keyring = Keyring::new();
+  for pem in &pems {
+    keyring.add_pem(pem)?;
+  }
+  Ok(keyring)
+}
+
+#[napi(object)]
+pub struct VerifyResult {
+  pub payload: JsLicensePayload,
+  // 已过期但仍在宽限期内
+  pub in_grace_period: bool,
+}
+
+// license 校验器：解密或验签、检查吊销、有效期、必需功能和机器绑定
+#[napi]
+pub struct LicenseVerifier {
+  keyring: Keyring,
+  validator: Validator,
+  required_features: Vec<String>,
+  fingerprint: Fingerprint,
+  fingerprint_tolerance: usize,
+  revocation_list: Option<RevocationList>,
+}
+
+#[napi]
+impl LicenseVerifier {
+  // keys 为 PEM 字符串或数组：RSA 私钥用于解密，公钥用于验签；不传时使用内置密钥
+  #[napi(constructor)]
+  pub fn new(
+    env: Env,
+    keys: Option<Either<String, Vec<String>>>,
+    options: Option<LicenseVerifierOptions>,
+  ) -> napi::Result<Self> {
+    let keyring = keyring_from_keys(keys).or_else(|error| throw_license_error(&env, error))?;
+
+    let options = options.unwrap_or(LicenseVerifierOptions {
+      clock_skew: None,
+      grace_period: None,
+      required_features: None,
+      fingerprint_tolerance: None,
+      machine_fingerprint: None,
+      revocation_list: None,
+    });
+    let validator = Validator::new(
+      ValidationOptions::new(
+        options.grace_period.unwrap_or(0),
+        options.clock_skew.unwrap_or(0),
+      )
+      .or_else(|error| throw_license_error(&env, error))?,
+    );
+
+    let fingerprint = match options.machine_fingerprint {
+      Some(components) => Fingerprint::from_components(components),
+      None => Fingerprint::current(),
+    };
+    let revocation_list = match options.revocation_list {
+      Some(token) => Some(
+        RevocationList::verify(&keyring, &token)
+          .or_else(|error| throw_license_error(&env, error))?,
+      ),
+      None => None,
+    };
+
+    Ok(LicenseVerifier {
+      keyring,
+      validator,
+      required_features: options.required_features.unwrap_or_default(),
+      fingerprint,
+      fingerprint_tolerance: options.fingerprint_tolerance.unwrap_or(0) as usize,
+      revocation_list,
+    })
+  }
+
+  // 更换吊销列表，不比当前的新时保留当前的；返回正在使用的列表
+  #[napi]
+  pub fn set_revocation_list(
+    &mut self,
+    env: Env,
+    token: String,
+  ) -> napi::Result<RevocationListInfo> {
+    let list = RevocationList::verify(&self.keyring, &token)
+      .or_else(|error| throw_license_error(&env, error))?;
+    if self
+      .revocation_list
+      .as_ref()
+      .is_none_or(|current| current.issued_at < list.issued_at)
+    {
+      self.revocation_list = Some(list);
+    }
+    Ok(RevocationListInfo::from(
+      self.revocation_list.as_ref().unwrap(),
+    ))
+  }
+
+  // 解码并校验有效期、必需功能和机器绑定
+  #[napi]
+  pub fn verify(&self, env: Env, token: String) -> napi::Result<VerifyResult> {
+    self
+      .verify_token(&token)
+      .or_else(|error| throw_license_error(&env, error))
+  }
+
+  // 只解码，不检查有效期
+  #[napi]
+  pub fn decode(&self, env: Env, token: String) -> napi::Result<JsLicensePayload> {
+    match self.keyring.decode_license(&token) {
+      Ok(payload) => Ok(payload.into()),
+      Err(error) => throw_license_error(&env, error),
+    }
+  }
+
+  // 校验通过且开通了该功能时返回 true，校验失败时抛出错误
+  #[napi]
+  pub fn has_feature(&self, env: Env, token: String, name: String) -> napi::Result<bool> {
+    match self.verify_token(&token) {
+      Ok(result) => Ok(result.payload.features.contains(&name)),
+      Err(error) => throw_license_error(&env, error),
+    }
+  }
+
+  fn verify_token(&self, token: &str) -> Result<VerifyResult, LicenseError> {
+    let payload = match &self.revocation_list {
+      Some(list) => list.decode_license(&self.keyring, token)?,
+      None => self.keyring.decode_license(token)?,
+    };
+    let validity = self.validator.validate(&payload)?;
+    payload.require_features(&self.required_features)?;
+    payload.check_fingerprint(&self.fingerprint, self.fingerprint_tolerance)?;
+
+    Ok(VerifyResult {
+      payload: payload.into(),
+      in_grace_period: matches!(validity, Validity::InGracePeriod { .. }),
+    })
+  }
+}
+
+// LicenseStore 选项
+#[napi(object)]
+pub struct LicenseStoreOptions {
+  // 判断时钟回拨时允许的偏差（秒），默认 0，不能为负数
+  pub clock_skew: Option<i64>,
+  // 代替当前机器指纹推导存储密钥，取 getMachineFingerprint().components
+  pub machine_fingerprint: Option<HashMap<String, String>>,
+  // 允许变化的指纹组件数，默认 1
+  pub fingerprint_tolerance: Option<u32>,
+}
+
+// 存储的 license，对应 lib::StoredLicense
+#[napi(object, js_name = "StoredLicense")]
+pub struct JsStoredLicense {
+  pub license: String,
+  pub activation: Option<String>,
+  // 缓存的吊销列表文本，可传给 LicenseVerifierOptions.revocationList
+  pub revocation_list: Option<String>,
+  pub saved_at: i64,
+  pub last_seen: i64,
+}
+
+impl From<StoredLicense> for JsStoredLicense {
+  fn from(stored: StoredLicense) -> Self {
+    JsStoredLicense {
+      license: stored.license,
+      activation: stored.activation,
+      revocation_list: stored.revocation_list,
+      saved_at: stored.saved_at,
+      last_seen: stored.last_seen,
+    }
+  }
+}
+
+// 本地 license 存储：文件带机器绑定的 HMAC，加载时检查时钟回拨
+#[napi(js_name = "LicenseStore")]
+pub struct JsLicenseStore {
+  store: LicenseStore,
+}
+
+#[napi]
+impl JsLicenseStore {
+  #[napi(constructor)]
+  pub fn new(env: Env, dir: String, options: Option<LicenseStoreOptions>) -> napi::Result<Self> {
+    let mut store = LicenseStore::new(dir);
+    if let Some(options) = options {
+      if let Some(components) = options.machine_fingerprint {
+        store = store.with_fingerprint(Fingerprint::from_components(components));
+      }
+      if let Some(tolerance) = options.fingerprint_tolerance {
+        store = store.with_fingerprint_tolerance(tolerance as usize);
+      }
+      store = store
+        .with_clock_skew(options.clock_skew.unwrap_or(0))
+        .or_else(|error| throw_license_error(&env, error))?;
+    }
+    Ok(JsLicenseStore { store })
+  }
+
+  // 存储文件路径
+  #[napi(getter)]
+  pub fn path(&self) -> String {
+    self.store.path().display().to_string()
+  }
+
+  // 保存 license 和离线激活响应，覆盖之前的内容
+  #[napi]
+  pub fn save(
+    &self,
+    env: Env,
+    license: String,
+    activation: Option<String>,
+  ) -> napi::Result<JsStoredLicense> {
+    match self.store.save(&license, activation.as_deref()) {
+      Ok(stored) => Ok(stored.into()),
+      Err(error) => throw_license_error(&env, error),
+    }
+  }
+
+  // 缓存吊销列表，需要先保存 license；keys 同 LicenseVerifier，用于校验签名
+  // 不比已缓存的新时保留已缓存的，返回缓存中的列表
+  #[napi]
+  pub fn save_revocation_list(
+    &self,
+    env: Env,
+    token: String,
+    keys: Option<Either<String, Vec<String>>>,
+  ) -> napi::Result<RevocationListInfo> {
+    let result =
+      keyring_from_keys(keys).and_then(|keyring| self.store.save_revocation_list(&keyring, &token));
+    match result {
+      Ok(list) => Ok(RevocationListInfo::from(&list)),
+      Err(error) => throw_license_error(&env, error),
+    }
+  }
+
+  // 启动时加载，没有保存过时返回 null；被改动或时钟回拨时抛出错误
+  #[napi]
+  pub fn load(&self, env: Env) -> napi::Result<Option<JsStoredLicense>> {
+    match self.store.load() {
+      Ok(stored) => Ok(stored.map(Into::into)),
+      Err(error) => throw_license_error(&env, error),
+    }
+  }
+
+  #[napi]
+  pub fn clear(&self, env: Env) -> napi::Result<()> {
+    self
+      .store
+      .clear()
+      .or_else(|error| throw_license_error(&env, error))
   }
 }
 